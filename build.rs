@@ -1,21 +1,36 @@
-use std::{fs, fs::File, path::Path};
+#![allow(clippy::needless_return)]
 
-use rusqlite::{Connection, Result};
+use std::{fs, process::Command};
 
-fn main() -> Result<()> {
-    // println!("cargo:rustc-env=SQLX_OFFLINE=true");
-    let db_path = "sqlite.db";
-    // println!("cargo:rustc-env=SQLX_OFFLINE_DIR=.sqlx/sqlite");
-    println!("cargo:rustc-env=DATABASE_URL=sqlite://{}", db_path);
-
-    let path = Path::new(db_path);
-    if !path.exists() {
-        let _ = File::create(path).expect("Could not create file");
-    }
+/// The commit being built, or `unknown` outside a git checkout
+fn git_sha() -> String {
+    return Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| return output.status.success())
+        .and_then(|output| return String::from_utf8(output.stdout).ok())
+        .map(|sha| return sha.trim().to_owned())
+        .unwrap_or_else(|| return "unknown".to_owned());
+}
 
-    let sql = fs::read_to_string("src/db/sql/1_setup.up.sql").expect("Unable to read the SQL file");
-    let conn = Connection::open(db_path)?;
-    conn.execute_batch(&sql)?;
+fn main() {
+    println!("cargo:rustc-env=WANTJOB_GIT_SHA={}", git_sha());
+    println!(
+        "cargo:rustc-env=WANTJOB_RUSTC_VERSION={}",
+        rustc_version::version()
+            .map(|version| return version.to_string())
+            .unwrap_or_else(|_| return "unknown".to_owned())
+    );
 
-    Ok(())
+    // Rebuilt on commit, which moves the branch HEAD points to rather than HEAD
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/packed-refs");
+    if let Some(branch) = fs::read_to_string(".git/HEAD").ok().and_then(|head| {
+        return head
+            .strip_prefix("ref: ")
+            .map(|r| return r.trim().to_owned());
+    }) {
+        println!("cargo:rerun-if-changed=.git/{branch}");
+    }
 }
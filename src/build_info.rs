@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+/// What this binary was built from, as reported by `/version` and on traces
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub rustc_version: &'static str,
+    /// `unknown` when built outside a git checkout
+    pub git_sha: &'static str,
+}
+
+pub const BUILD_INFO: BuildInfo = BuildInfo {
+    version: env!("CARGO_PKG_VERSION"),
+    rustc_version: env!("WANTJOB_RUSTC_VERSION"),
+    git_sha: env!("WANTJOB_GIT_SHA"),
+};
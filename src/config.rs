@@ -0,0 +1,504 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+/// Read when there's no `--config` or `WANTJOB_CONFIG`, if it exists
+const DEFAULT_CONFIG_FILE: &str = "wantjob.toml";
+
+/// Every setting, as `section.key`. Each can also be set by an environment
+/// variable (`WANTJOB_DATABASE_URL` for `database.url`) or a flag
+/// (`--database-url`).
+const KEYS: &[&str] = &[
+    "bind",
+    "drain_timeout_secs",
+    "exchange_rates",
+    "http_fixtures",
+    "database.url",
+    "database.max_connections",
+    "database.acquire_timeout_secs",
+    "database.seed",
+    "telemetry.otlp_endpoint",
+    "telemetry.honeycomb_team",
+    "telemetry.log_filter",
+    "jobs.stale_after_days",
+    "jobs.archive_after_days",
+];
+
+/// Variables settings were read from before they had `WANTJOB_` ones, still
+/// used when those aren't set
+const LEGACY_VARS: &[(&str, &str)] = &[
+    ("telemetry.otlp_endpoint", "OTEL_ENDPOINT"),
+    ("telemetry.honeycomb_team", "OTEL_HEADER_VALUE"),
+    ("telemetry.log_filter", "RUST_LOG"),
+];
+
+/// Set to send `OTEL_HEADER_VALUE` as the Honeycomb API key
+const LEGACY_HONEYCOMB_VAR: &str = "OTEL_HONEYCOMB_KEY";
+
+/// How an instance runs. Each setting is taken from the first of the command
+/// line, the environment, the config file and the defaults to have it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Where the web server listens
+    pub bind: SocketAddr,
+    /// How long requests underway get to finish when shutting down, before
+    /// they're dropped
+    pub drain_timeout_secs: u64,
+    /// Exchange rates loaded at startup (`.json`, or CSV otherwise), instead
+    /// of the bundled ones
+    pub exchange_rates: Option<PathBuf>,
+    /// Saved responses importers read instead of going to the network
+    pub http_fixtures: Option<PathBuf>,
+    pub database: DatabaseConfig,
+    pub telemetry: TelemetryConfig,
+    pub jobs: JobsConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// e.g. `sqlite://sqlite.db`
+    pub url: String,
+    pub max_connections: u32,
+    /// How long a query waits for a free connection before failing
+    pub acquire_timeout_secs: u64,
+    /// Whether the database is filled with example data at startup, on by
+    /// default in debug builds
+    pub seed: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelemetryConfig {
+    /// OTLP gRPC collector traces and metrics are sent to
+    pub otlp_endpoint: String,
+    /// Honeycomb API key, sent along with telemetry when collecting straight
+    /// into Honeycomb
+    pub honeycomb_team: Option<String>,
+    /// Which logs are printed, as for `RUST_LOG`
+    pub log_filter: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobsConfig {
+    /// Days a source can go without listing a job before it's closed
+    pub stale_after_days: i64,
+    /// Days a job stays closed before it's archived
+    pub archive_after_days: i64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        return Config {
+            bind: SocketAddr::from(([127, 0, 0, 1], 4321)),
+            drain_timeout_secs: 30,
+            exchange_rates: None,
+            http_fixtures: None,
+            database: DatabaseConfig {
+                url: "sqlite://sqlite.db".to_owned(),
+                max_connections: 50,
+                acquire_timeout_secs: 3,
+                seed: cfg!(debug_assertions),
+            },
+            telemetry: TelemetryConfig {
+                otlp_endpoint: "http://localhost:4317".to_owned(),
+                honeycomb_team: None,
+                log_filter: "wantjob=debug,axum::rejection=trace".to_owned(),
+            },
+            jobs: JobsConfig {
+                stale_after_days: 14,
+                archive_after_days: 90,
+            },
+        };
+    }
+}
+
+fn env_var(key: &str) -> String {
+    return format!("WANTJOB_{}", key.replace('.', "_").to_uppercase());
+}
+
+/// The value of `key` from the environment: its `WANTJOB_` variable, else the
+/// variable it was read from before that
+fn env_value<'a>(env: &'a HashMap<String, String>, key: &str) -> Result<Option<&'a str>> {
+    if let Some(value) = env.get(&env_var(key)) {
+        return Ok(Some(value));
+    }
+
+    let Some((_, var)) = LEGACY_VARS.iter().find(|(k, _)| return *k == key) else {
+        return Ok(None);
+    };
+    if *var == "OTEL_HEADER_VALUE" && !env.contains_key(LEGACY_HONEYCOMB_VAR) {
+        return Ok(None);
+    }
+    return match env.get(*var) {
+        Some(value) => Ok(Some(value)),
+        None if *var == "OTEL_HEADER_VALUE" => {
+            bail!("OTEL_HEADER_VALUE must be set if {LEGACY_HONEYCOMB_VAR} is")
+        }
+        None => Ok(None),
+    };
+}
+
+/// The variables config is read from, which have to be UTF-8
+fn read_env() -> Result<HashMap<String, String>> {
+    return std::env::vars_os()
+        .filter_map(|(var, value)| {
+            let var = var.into_string().ok()?;
+            let read = var.starts_with("WANTJOB_")
+                || var == LEGACY_HONEYCOMB_VAR
+                || LEGACY_VARS.iter().any(|(_, legacy)| return *legacy == var);
+            return read.then_some((var, value));
+        })
+        .map(|(var, value)| {
+            return match value.into_string() {
+                Ok(value) => Ok((var, value)),
+                Err(_) => Err(anyhow!("{var} isn't valid UTF-8")),
+            };
+        })
+        .collect();
+}
+
+fn flag(key: &str) -> String {
+    return format!("--{}", key.replace(['.', '_'], "-"));
+}
+
+/// A setting given as text for `key`, typed as it would be in TOML if the
+/// setting takes that type, else taken as a string. That way paths, addresses
+/// and keys needn't be quoted, even ones which look like numbers.
+fn parse_value(key: &str, raw: &str) -> Value {
+    let string = Value::String(raw.to_owned());
+    let typed = format!("value = {raw}")
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| return table.remove("value"))
+        .filter(|value| return !value.is_table());
+
+    return match typed {
+        Some(typed) if accepts(key, typed.clone()) || !accepts(key, string.clone()) => typed,
+        _ => string,
+    };
+}
+
+/// Whether `value` is the right type for `key`, going by whether the defaults
+/// with it set still make a config
+fn accepts(key: &str, value: Value) -> bool {
+    let mut table = Table::try_from(Config::default()).expect("default config to serialize");
+    set(&mut table, key, value);
+    return Value::Table(table).try_into::<Config>().is_ok();
+}
+
+/// Sets `key` (`section.key`) in `table`
+fn set(table: &mut Table, key: &str, value: Value) {
+    let mut table = table;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            table.insert(part.to_owned(), value);
+            return;
+        }
+        table = table
+            .entry(part)
+            .or_insert_with(|| return Value::Table(Table::new()))
+            .as_table_mut()
+            .expect("config sections to be tables");
+    }
+}
+
+/// Sets everything in `overrides` in `table`, section by section
+fn merge(table: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (table.get_mut(&key), value) {
+            (Some(Value::Table(section)), Value::Table(overrides)) => merge(section, overrides),
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Takes the config flags out of `args`, leaving the command. Returns the
+/// config file given, and the settings.
+fn take_flags(args: &mut Vec<String>) -> Result<(Option<PathBuf>, Table)> {
+    let mut file = None;
+    let mut settings = Table::new();
+
+    let mut rest = Vec::new();
+    let mut args_iter = std::mem::take(args).into_iter();
+    while let Some(arg) = args_iter.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_owned(), Some(value.to_owned())),
+            None => (arg.clone(), None),
+        };
+        let key = KEYS.iter().find(|key| return flag(key) == name);
+        if name != "--config" && key.is_none() {
+            rest.push(arg);
+            continue;
+        }
+
+        let value = match inline {
+            Some(value) => value,
+            None => args_iter
+                .next()
+                .with_context(|| return format!("{name} needs a value"))?,
+        };
+        match key {
+            Some(key) => set(&mut settings, key, parse_value(key, &value)),
+            None => file = Some(PathBuf::from(value)),
+        }
+    }
+
+    *args = rest;
+    return Ok((file, settings));
+}
+
+fn read_file(path: &Path) -> Result<Table> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| return format!("Could not read config from {}", path.display()))?;
+    return contents
+        .parse::<Table>()
+        .with_context(|| return format!("Could not parse config from {}", path.display()));
+}
+
+impl Config {
+    /// Loads the config, taking its flags out of `args`. The file is the one
+    /// given by `--config` or `WANTJOB_CONFIG`, else `wantjob.toml` if there
+    /// is one.
+    pub fn load(args: &mut Vec<String>) -> Result<Self> {
+        return Self::load_from(args, &read_env()?);
+    }
+
+    /// Loads the config as [`Config::load`] does, with `env` for the environment
+    fn load_from(args: &mut Vec<String>, env: &HashMap<String, String>) -> Result<Self> {
+        let (file, flags) = take_flags(args)?;
+
+        let mut table = Table::try_from(Config::default()).expect("default config to serialize");
+
+        let file = file.or_else(|| return env.get("WANTJOB_CONFIG").map(PathBuf::from));
+        match file {
+            Some(path) => merge(&mut table, read_file(&path)?),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                merge(&mut table, read_file(Path::new(DEFAULT_CONFIG_FILE))?);
+            }
+            None => {}
+        }
+
+        for key in KEYS {
+            if let Some(value) = env_value(env, key)? {
+                set(&mut table, key, parse_value(key, value));
+            }
+        }
+
+        merge(&mut table, flags);
+
+        let config: Config = Value::Table(table).try_into().context("Invalid config")?;
+        config.validate()?;
+
+        return Ok(config);
+    }
+
+    /// Checks the settings make sense together, listing every one which doesn't
+    fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if !self.database.url.starts_with("sqlite:") {
+            problems.push("database.url must be a sqlite: URL".to_owned());
+        }
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be at least 1".to_owned());
+        }
+        if self.database.acquire_timeout_secs == 0 {
+            problems.push("database.acquire_timeout_secs must be at least 1".to_owned());
+        }
+        if !reqwest::Url::parse(&self.telemetry.otlp_endpoint)
+            .is_ok_and(|url| return matches!(url.scheme(), "https" | "http"))
+        {
+            problems.push("telemetry.otlp_endpoint must be an http(s) URL".to_owned());
+        }
+        if let Some(team) = &self.telemetry.honeycomb_team {
+            if team.parse::<tonic::metadata::AsciiMetadataValue>().is_err() {
+                problems.push("telemetry.honeycomb_team must be printable ASCII".to_owned());
+            }
+        }
+        if let Err(err) = tracing_subscriber::EnvFilter::try_new(&self.telemetry.log_filter) {
+            problems.push(format!("telemetry.log_filter is invalid: {err}"));
+        }
+        if self.jobs.stale_after_days < 1 {
+            problems.push("jobs.stale_after_days must be at least 1".to_owned());
+        }
+        if self.jobs.archive_after_days < 1 {
+            problems.push("jobs.archive_after_days must be at least 1".to_owned());
+        }
+        if let Some(path) = &self.exchange_rates {
+            if !path.is_file() {
+                problems.push(format!("exchange_rates {} isn't a file", path.display()));
+            }
+        }
+        if let Some(path) = &self.http_fixtures {
+            if !path.is_dir() {
+                problems.push(format!(
+                    "http_fixtures {} isn't a directory",
+                    path.display()
+                ));
+            }
+        }
+
+        if !problems.is_empty() {
+            bail!("Invalid config:\n  {}", problems.join("\n  "));
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types_values_by_setting() {
+        let cases = [
+            ("database.max_connections", "5", Value::Integer(5)),
+            ("database.seed", "false", Value::Boolean(false)),
+            ("bind", "0.0.0.0:80", Value::String("0.0.0.0:80".to_owned())),
+            ("http_fixtures", "2024", Value::String("2024".to_owned())),
+            (
+                "telemetry.honeycomb_team",
+                "123456",
+                Value::String("123456".to_owned()),
+            ),
+            (
+                "telemetry.log_filter",
+                "true",
+                Value::String("true".to_owned()),
+            ),
+            // Quoting still works
+            (
+                "telemetry.log_filter",
+                "\"info\"",
+                Value::String("info".to_owned()),
+            ),
+            // Left as given, to be reported as invalid
+            (
+                "database.max_connections",
+                "many",
+                Value::String("many".to_owned()),
+            ),
+            ("database.max_connections", "1.5", Value::Float(1.5)),
+        ];
+        for (key, raw, value) in cases {
+            assert_eq!(parse_value(key, raw), value, "{key} = {raw}");
+        }
+    }
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        return vars
+            .iter()
+            .map(|(var, value)| return ((*var).to_owned(), (*value).to_owned()))
+            .collect();
+    }
+
+    #[test]
+    fn takes_settings_from_flags_over_env_over_file() {
+        let path = std::env::temp_dir().join(format!("wantjob-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+            drain_timeout_secs = 5
+
+            [database]
+            max_connections = 7
+
+            [jobs]
+            stale_after_days = 3
+            "#,
+        )
+        .unwrap();
+
+        let env = env(&[
+            ("WANTJOB_DRAIN_TIMEOUT_SECS", "6"),
+            ("WANTJOB_DATABASE_MAX_CONNECTIONS", "8"),
+        ]);
+
+        let mut args = [
+            "--config",
+            path.to_str().unwrap(),
+            "serve",
+            "--database-max-connections",
+            "9",
+            "--telemetry-honeycomb-team=123456",
+        ]
+        .map(str::to_owned)
+        .to_vec();
+        let config = Config::load_from(&mut args, &env);
+
+        std::fs::remove_file(&path).unwrap();
+        let config = config.unwrap();
+
+        assert_eq!(args, ["serve"]);
+        // Flag over environment over file
+        assert_eq!(config.database.max_connections, 9);
+        // Environment over file
+        assert_eq!(config.drain_timeout_secs, 6);
+        // File over defaults
+        assert_eq!(config.jobs.stale_after_days, 3);
+        assert_eq!(config.jobs.archive_after_days, 90);
+        assert_eq!(config.telemetry.honeycomb_team.as_deref(), Some("123456"));
+    }
+
+    #[test]
+    fn falls_back_to_legacy_variables() {
+        let legacy = env(&[
+            ("OTEL_ENDPOINT", "http://collector:4317"),
+            ("OTEL_HONEYCOMB_KEY", "x-honeycomb-team"),
+            ("OTEL_HEADER_VALUE", "123456"),
+            ("RUST_LOG", "info"),
+        ]);
+        let config = Config::load_from(&mut Vec::new(), &legacy).unwrap();
+        assert_eq!(config.telemetry.otlp_endpoint, "http://collector:4317");
+        assert_eq!(config.telemetry.honeycomb_team.as_deref(), Some("123456"));
+        assert_eq!(config.telemetry.log_filter, "info");
+
+        let mut both = legacy.clone();
+        both.insert("WANTJOB_TELEMETRY_LOG_FILTER".to_owned(), "warn".to_owned());
+        let config = Config::load_from(&mut Vec::new(), &both).unwrap();
+        assert_eq!(config.telemetry.log_filter, "warn");
+
+        // The header value is only the Honeycomb key when it's said to be
+        let header = env(&[("OTEL_HEADER_VALUE", "123456")]);
+        let config = Config::load_from(&mut Vec::new(), &header).unwrap();
+        assert_eq!(config.telemetry.honeycomb_team, None);
+
+        let key = env(&[("OTEL_HONEYCOMB_KEY", "x-honeycomb-team")]);
+        let err = Config::load_from(&mut Vec::new(), &key).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "OTEL_HEADER_VALUE must be set if OTEL_HONEYCOMB_KEY is"
+        );
+    }
+
+    #[test]
+    fn lists_every_problem() {
+        let config = Config {
+            database: DatabaseConfig {
+                max_connections: 0,
+                ..Config::default().database
+            },
+            telemetry: TelemetryConfig {
+                honeycomb_team: Some("key\nwith a newline".to_owned()),
+                ..Config::default().telemetry
+            },
+            ..Config::default()
+        };
+
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("database.max_connections"), "{err}");
+        assert!(err.contains("telemetry.honeycomb_team"), "{err}");
+    }
+}
@@ -0,0 +1,193 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::Deserialize;
+
+/// Currency salaries are shown in until someone picks another
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// Rates shipped with wantjob, used unless a rates file is supplied
+const BUNDLED_RATES: &str = include_str!("exchange_rates.csv");
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExchangeRate {
+    /// ISO 4217 code
+    pub currency: String,
+    /// Units of this currency one US dollar buys
+    pub per_usd: f64,
+}
+
+/// A JSON rates file, e.g. `{"base": "EUR", "rates": {"USD": 1.08, "CAD": 1.47}}`
+#[derive(Deserialize)]
+struct RatesFile {
+    #[serde(default = "default_base")]
+    base: String,
+    rates: HashMap<String, f64>,
+}
+
+fn default_base() -> String {
+    return DEFAULT_CURRENCY.to_owned();
+}
+
+/// Loads exchange rates from `path` (a `.json` file, or CSV otherwise), or the
+/// bundled rates when there is no path
+pub fn load_rates(path: Option<&Path>) -> Result<Vec<ExchangeRate>> {
+    let Some(path) = path else {
+        return parse_csv(BUNDLED_RATES).context("Could not parse bundled exchange rates");
+    };
+
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| return format!("Could not read exchange rates from {}", path.display()))?;
+
+    let rates = if path.extension().is_some_and(|e| return e == "json") {
+        parse_json(&contents)
+    } else {
+        parse_csv(&contents)
+    };
+
+    return rates.with_context(|| {
+        return format!("Could not parse exchange rates from {}", path.display());
+    });
+}
+
+/// Parses `currency,per_usd` lines, skipping blank lines, `#` comments and an
+/// optional header row
+fn parse_csv(contents: &str) -> Result<Vec<ExchangeRate>> {
+    let mut rates = HashMap::new();
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| return (i, line.trim()))
+        .filter(|(_, line)| return !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    if lines
+        .peek()
+        .is_some_and(|(_, line)| return line.eq_ignore_ascii_case("currency,per_usd"))
+    {
+        lines.next();
+    }
+
+    for (i, line) in lines {
+        let Some((currency, rate)) = line.split_once(',') else {
+            bail!("line {}: expected `currency,per_usd`", i + 1);
+        };
+        let Ok(rate) = rate.trim().parse::<f64>() else {
+            bail!("line {}: invalid rate {:?}", i + 1, rate.trim());
+        };
+
+        rates.insert(currency.trim().to_uppercase(), rate);
+    }
+
+    return normalize(DEFAULT_CURRENCY, rates);
+}
+
+fn parse_json(contents: &str) -> Result<Vec<ExchangeRate>> {
+    let file: RatesFile = serde_json::from_str(contents)?;
+    let rates = file
+        .rates
+        .into_iter()
+        .map(|(currency, rate)| return (currency.to_uppercase(), rate))
+        .collect();
+
+    return normalize(&file.base.to_uppercase(), rates);
+}
+
+/// Rebases rates quoted against `base` onto the US dollar, validating them
+fn normalize(base: &str, mut rates: HashMap<String, f64>) -> Result<Vec<ExchangeRate>> {
+    match rates.get(base) {
+        Some(&rate) if rate != 1.0 => bail!("{base} is the base currency but has a rate of {rate}"),
+        Some(_) => {}
+        None => {
+            rates.insert(base.to_owned(), 1.0);
+        }
+    }
+
+    let Some(usd) = rates.get(DEFAULT_CURRENCY).copied() else {
+        bail!("no rate for {DEFAULT_CURRENCY}");
+    };
+
+    let mut rates = rates
+        .into_iter()
+        .map(|(currency, rate)| {
+            ensure!(
+                currency.len() == 3 && currency.chars().all(|c| return c.is_ascii_uppercase()),
+                "{currency:?} is not a currency code"
+            );
+            ensure!(
+                rate.is_finite() && rate > 0.0,
+                "{currency} has an invalid rate of {rate}"
+            );
+            return Ok(ExchangeRate {
+                currency,
+                per_usd: rate / usd,
+            });
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    rates.sort_by(|a, b| return a.currency.cmp(&b.currency));
+
+    return Ok(rates);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn per_usd(rates: &[ExchangeRate]) -> Vec<(&str, f64)> {
+        return rates
+            .iter()
+            .map(|rate| return (rate.currency.as_str(), rate.per_usd))
+            .collect();
+    }
+
+    #[test]
+    fn parses_csv() {
+        let rates = parse_csv("currency,per_usd\n# As of March\n\neur, 0.5\nUSD,1\n").unwrap();
+        assert_eq!(per_usd(&rates), [("EUR", 0.5), ("USD", 1.0)]);
+
+        // USD is the base, so it may be left out
+        let rates = parse_csv("CAD,1.25").unwrap();
+        assert_eq!(per_usd(&rates), [("CAD", 1.25), ("USD", 1.0)]);
+    }
+
+    #[test]
+    fn parses_json() {
+        let rates = parse_json(r#"{"rates": {"gbp": 0.8}}"#).unwrap();
+        assert_eq!(per_usd(&rates), [("GBP", 0.8), ("USD", 1.0)]);
+    }
+
+    #[test]
+    fn rebases_onto_usd() {
+        let rates = parse_json(r#"{"base": "EUR", "rates": {"USD": 2.0, "GBP": 1.5}}"#).unwrap();
+        assert_eq!(per_usd(&rates), [("EUR", 0.5), ("GBP", 0.75), ("USD", 1.0)]);
+    }
+
+    #[test]
+    fn needs_a_usd_rate() {
+        let err = parse_json(r#"{"base": "EUR", "rates": {"GBP": 0.85}}"#).unwrap_err();
+        assert_eq!(err.to_string(), "no rate for USD");
+    }
+
+    #[test]
+    fn rejects_bad_rows() {
+        let cases = [
+            ("EUR", "line 1: expected `currency,per_usd`"),
+            ("EUR,lots", "line 1: invalid rate \"lots\""),
+            ("EUR,0", "EUR has an invalid rate of 0"),
+            ("EUR,-1", "EUR has an invalid rate of -1"),
+            ("EURO,0.9", "\"EURO\" is not a currency code"),
+            ("USD,1.1", "USD is the base currency but has a rate of 1.1"),
+        ];
+        for (csv, message) in cases {
+            let err = parse_csv(csv).unwrap_err();
+            assert_eq!(err.to_string(), message, "{csv:?}");
+        }
+
+        let err = parse_json(r#"{"base": "EUR", "rates": {"EUR": 0.9, "USD": 1.1}}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "EUR is the base currency but has a rate of 0.9"
+        );
+    }
+}
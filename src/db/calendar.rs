@@ -0,0 +1,218 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::{Date, Time};
+
+use super::{
+    users::{random_token, token_hash},
+    DbPool,
+};
+
+/// What a calendar event is for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum EventKind {
+    Interview,
+    /// A take-home assignment is due
+    TakeHome,
+    /// Applications to the job close
+    ApplicationClose,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 3] = [
+        EventKind::Interview,
+        EventKind::TakeHome,
+        EventKind::ApplicationClose,
+    ];
+
+    /// Name of the kind in forms
+    pub fn slug(self) -> &'static str {
+        return match self {
+            EventKind::Interview => "interview",
+            EventKind::TakeHome => "take_home",
+            EventKind::ApplicationClose => "application_close",
+        };
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            EventKind::Interview => "Interview",
+            EventKind::TakeHome => "Take-home due",
+            EventKind::ApplicationClose => "Applications close",
+        });
+    }
+}
+
+/// An event to add to a user's calendar
+#[derive(Debug)]
+pub struct NewEvent {
+    pub kind: EventKind,
+    pub day: Date,
+    /// `None` for all day events
+    pub start_time: Option<Time>,
+    pub duration_minutes: Option<i64>,
+    pub notes: String,
+}
+
+/// An event on a user's calendar, in their own (unknown) time zone
+#[derive(Clone, Debug, FromRow)]
+pub struct CalendarEvent {
+    pub id: String,
+    pub job_id: String,
+    pub title: String,
+    pub company: String,
+    pub kind: EventKind,
+    /// `YYYY-MM-DD`
+    pub day: String,
+    /// `HH:MM`, `None` for all day events
+    pub start_time: Option<String>,
+    pub duration_minutes: Option<i64>,
+    pub notes: String,
+    /// When the event last changed, in UTC
+    pub updated_at: String,
+    /// Whether the event's day has gone by
+    pub past: bool,
+}
+
+const CALENDAR_EVENT_COLUMNS: &str = r#"
+    job_events.event_id AS id,
+    job_events.job_id,
+    jobs.title,
+    companies.name AS company,
+    job_events.kind,
+    job_events.day,
+    job_events.start_time,
+    job_events.duration_minutes,
+    job_events.notes,
+    job_events.updated_at,
+    job_events.day < date('now', 'localtime') AS past
+FROM job_events
+INNER JOIN jobs ON jobs.job_id = job_events.job_id
+INNER JOIN companies ON companies.company_id = jobs.company_id
+"#;
+
+/// Adds an event against a job, returning whether the job exists
+#[tracing::instrument(skip(db))]
+pub async fn add_event(db: &DbPool, user_id: &str, job_id: &str, event: &NewEvent) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO job_events (event_id, user_id, job_id, kind, day, start_time, duration_minutes, notes)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(random_token(16))
+    .bind(user_id)
+    .bind(job_id)
+    .bind(event.kind)
+    .bind(event.day.to_string())
+    .bind(
+        event
+            .start_time
+            .map(|t| return format!("{:02}:{:02}", t.hour(), t.minute())),
+    )
+    .bind(event.duration_minutes)
+    .bind(event.notes.trim())
+    .execute(db)
+    .await;
+
+    return match result {
+        Ok(_) => Ok(true),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => Ok(false),
+        Err(err) => Err(err).context("Could not add calendar event"),
+    };
+}
+
+/// Deletes one of the user's events, returning the job it was for if it existed
+#[tracing::instrument(skip(db))]
+pub async fn delete_event(db: &DbPool, user_id: &str, event_id: &str) -> Result<Option<String>> {
+    return sqlx::query_scalar(
+        "DELETE FROM job_events WHERE user_id = ? AND event_id = ? RETURNING job_id",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_optional(db)
+    .await
+    .context("Could not delete calendar event");
+}
+
+/// The user's events for a job, soonest first
+#[tracing::instrument(skip(db))]
+pub async fn job_events(db: &DbPool, user_id: &str, job_id: &str) -> Result<Vec<CalendarEvent>> {
+    return sqlx::query_as::<_, CalendarEvent>(&format!(
+        r#"
+        SELECT {CALENDAR_EVENT_COLUMNS}
+        WHERE job_events.user_id = ? AND job_events.job_id = ?
+        ORDER BY job_events.day, job_events.start_time NULLS FIRST
+        "#
+    ))
+    .bind(user_id)
+    .bind(job_id)
+    .fetch_all(db)
+    .await
+    .context("Could not list job events");
+}
+
+/// Every event on the user's calendar, soonest first
+#[tracing::instrument(skip(db))]
+pub async fn user_events(db: &DbPool, user_id: &str) -> Result<Vec<CalendarEvent>> {
+    return sqlx::query_as::<_, CalendarEvent>(&format!(
+        r#"
+        SELECT {CALENDAR_EVENT_COLUMNS}
+        WHERE job_events.user_id = ?
+        ORDER BY job_events.day, job_events.start_time NULLS FIRST
+        "#
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+    .context("Could not list calendar events");
+}
+
+/// Creates a new secret token for the user's calendar feed, so only the URL
+/// containing it can be used to read the feed from now on
+#[tracing::instrument(skip(db))]
+pub async fn rotate_feed_token(db: &DbPool, user_id: &str) -> Result<String> {
+    let token = random_token(32);
+
+    sqlx::query(
+        r#"
+        INSERT INTO calendar_feeds (user_id, token_hash) VALUES (?, ?)
+        ON CONFLICT (user_id) DO UPDATE SET
+            token_hash = excluded.token_hash,
+            created_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(user_id)
+    .bind(token_hash(&token))
+    .execute(db)
+    .await
+    .context("Could not create calendar feed token")?;
+
+    return Ok(token);
+}
+
+/// Whether the user has a calendar feed
+#[tracing::instrument(skip(db))]
+pub async fn has_feed(db: &DbPool, user_id: &str) -> Result<bool> {
+    return sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM calendar_feeds WHERE user_id = ?)")
+        .bind(user_id)
+        .fetch_one(db)
+        .await
+        .context("Could not check for calendar feed");
+}
+
+/// The user whose calendar feed `token` is for
+#[tracing::instrument(skip_all)]
+pub async fn feed_user_id(db: &DbPool, token: &str) -> Result<Option<String>> {
+    return sqlx::query_scalar("SELECT user_id FROM calendar_feeds WHERE token_hash = ?")
+        .bind(token_hash(token))
+        .fetch_optional(db)
+        .await
+        .context("Could not get calendar feed");
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use sqlx::{FromRow, QueryBuilder, SqliteConnection};
+
+use super::{
+    jobs::{push_job_details, push_job_details_from, JobDetails, Viewer},
+    DbPool, DB,
+};
+
+#[derive(Clone, Debug, FromRow)]
+pub struct Company {
+    #[sqlx(rename = "company_id")]
+    pub id: i64,
+    pub name: String,
+    pub slug: String,
+    #[sqlx(flatten)]
+    pub profile: CompanyProfile,
+}
+
+/// What's known about a company besides its name
+#[derive(Clone, Debug, Default, FromRow)]
+pub struct CompanyProfile {
+    /// e.g. `acme.com`
+    pub domain: Option<String>,
+    pub description: String,
+    pub size: Option<String>,
+    pub headquarters: Option<String>,
+    pub careers_url: Option<String>,
+    pub logo_url: Option<String>,
+}
+
+/// A name's words, lowercased and joined by dashes
+fn slugify(name: &str) -> String {
+    let slug = name
+        .split(|c: char| return !c.is_alphanumeric())
+        .filter(|word| return !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        return "company".to_owned();
+    }
+    return slug;
+}
+
+/// A slug for `name` no other company has, numbered if its plain one is taken
+async fn unique_slug(conn: &mut SqliteConnection, name: &str) -> Result<String> {
+    let base = slugify(name);
+
+    let mut slug = base.clone();
+    for n in 2.. {
+        let taken: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM companies WHERE slug = ?)")
+                .bind(&slug)
+                .fetch_one(&mut *conn)
+                .await
+                .context("Could not check company slug")?;
+        if !taken {
+            break;
+        }
+        slug = format!("{base}-{n}");
+    }
+
+    return Ok(slug);
+}
+
+/// The id of the company called `name`, adding it if it's new
+pub(super) async fn ensure_company(conn: &mut SqliteConnection, name: &str) -> Result<i64> {
+    let existing: Option<i64> =
+        sqlx::query_scalar("SELECT company_id FROM companies WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&mut *conn)
+            .await
+            .context("Could not get company")?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let slug = unique_slug(conn, name).await?;
+    let id = sqlx::query("INSERT INTO companies (name, slug) VALUES (?, ?)")
+        .bind(name)
+        .bind(slug)
+        .execute(&mut *conn)
+        .await
+        .context("Could not add company")?
+        .last_insert_rowid();
+
+    return Ok(id);
+}
+
+/// Gives every company without a slug one, e.g. those from before companies
+/// had pages
+#[tracing::instrument(skip(db))]
+pub async fn backfill_slugs(db: &DbPool) -> Result<()> {
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    let unslugged: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT company_id, name FROM companies WHERE slug IS NULL ORDER BY company_id",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Could not list companies without slugs")?;
+
+    for (id, name) in unslugged {
+        let slug = unique_slug(&mut tx, &name).await?;
+        sqlx::query("UPDATE companies SET slug = ? WHERE company_id = ?")
+            .bind(slug)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .context("Could not set company slug")?;
+    }
+
+    return tx.commit().await.context("Could not set company slugs");
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn get_company(db: &DbPool, slug: &str) -> Result<Option<Company>> {
+    return sqlx::query_as::<_, Company>(
+        r#"
+        SELECT company_id, name, slug, domain, description, size, headquarters, careers_url,
+            logo_url
+        FROM companies WHERE slug = ?
+        "#,
+    )
+    .bind(slug)
+    .fetch_optional(db)
+    .await
+    .context("Could not get company");
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn update_profile(db: &DbPool, company_id: i64, profile: &CompanyProfile) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE companies SET
+            domain = ?,
+            description = ?,
+            size = ?,
+            headquarters = ?,
+            careers_url = ?,
+            logo_url = ?
+        WHERE company_id = ?
+        "#,
+    )
+    .bind(&profile.domain)
+    .bind(&profile.description)
+    .bind(&profile.size)
+    .bind(&profile.headquarters)
+    .bind(&profile.careers_url)
+    .bind(&profile.logo_url)
+    .bind(company_id)
+    .execute(db)
+    .await
+    .context("Could not update company")?;
+
+    return Ok(());
+}
+
+/// The company's open jobs, newest first, once each
+#[tracing::instrument(skip(db))]
+pub async fn open_jobs(db: &DbPool, company_id: i64, viewer: &Viewer) -> Result<Vec<JobDetails>> {
+    let mut qb = QueryBuilder::<DB>::new("SELECT ");
+    push_job_details(&mut qb, viewer);
+    push_job_details_from(&mut qb, viewer);
+    qb.push(" WHERE jobs.company_id = ")
+        .push_bind(company_id)
+        .push(
+            r#"
+            AND jobs.status = 'open' AND jobs.canonical_job_id IS NULL
+            ORDER BY jobs.created_at DESC, jobs.job_id
+            "#,
+        );
+
+    return qb
+        .build_query_as::<JobDetails>()
+        .fetch_all(db)
+        .await
+        .context("Could not list company's jobs");
+}
+
+/// The company's jobs in the viewer's pipeline, most recently moved first
+#[tracing::instrument(skip(db))]
+pub async fn applications(
+    db: &DbPool,
+    company_id: i64,
+    viewer: &Viewer,
+) -> Result<Vec<JobDetails>> {
+    if viewer.user_id.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let mut qb = QueryBuilder::<DB>::new("SELECT ");
+    push_job_details(&mut qb, viewer);
+    push_job_details_from(&mut qb, viewer);
+    qb.push(" WHERE applications.job_id IS NOT NULL AND jobs.company_id = ")
+        .push_bind(company_id)
+        .push(" ORDER BY applications.updated_at DESC");
+
+    return qb
+        .build_query_as::<JobDetails>()
+        .fetch_all(db)
+        .await
+        .context("Could not list company's applications");
+}
@@ -0,0 +1,605 @@
+use anyhow::{Context, Result};
+use sqlx::{FromRow, QueryBuilder, SqliteConnection};
+use tracing::info;
+
+use super::{DbPool, DB};
+use crate::dedupe;
+
+/// Postings at least this similar are taken to be the same job, as long as
+/// they're at the same company or have the same title
+const DUPLICATE_SIMILARITY: f64 = 0.8;
+
+/// Postings at least this similar are suggested as duplicates. Identical
+/// titles at the same company and place less similar than this are taken to
+/// be different jobs, e.g. on different teams.
+const SIMILAR_SIMILARITY: f64 = 0.5;
+
+/// Most similar postings suggested for a job
+const MAX_SIMILAR: usize = 5;
+
+/// Joins how the user bound to it regrouped each job, if they did
+const USER_GROUPS: &str = "LEFT JOIN user_job_groups AS mine \
+    ON mine.job_id = jobs.job_id AND mine.user_id = ?";
+
+/// The job each job is grouped under, as the user regrouped it or else as
+/// detected. Needs [`USER_GROUPS`].
+const CANONICAL: &str = "CASE WHEN mine.job_id IS NULL THEN jobs.canonical_job_id \
+    ELSE mine.canonical_job_id END";
+
+/// Another posting of a job
+#[derive(Clone, Debug, FromRow)]
+pub struct Posting {
+    #[sqlx(rename = "job_id")]
+    pub id: String,
+    pub title: String,
+    pub company: String,
+    pub location: String,
+    pub url: Option<String>,
+}
+
+pub struct Duplicates {
+    pub job_id: String,
+    /// Other postings of the same job
+    pub group: Vec<Posting>,
+    /// Postings which may be of the same job
+    pub similar: Vec<Posting>,
+}
+
+#[derive(FromRow)]
+struct Candidate {
+    job_id: String,
+    title: String,
+    company: String,
+    fingerprint: Option<String>,
+    minhash: Option<Vec<u8>>,
+    canonical_job_id: Option<String>,
+}
+
+#[derive(FromRow)]
+struct SimilarPosting {
+    #[sqlx(flatten)]
+    posting: Posting,
+    minhash: Vec<u8>,
+}
+
+/// Jobs which may be copies of `job_id`: those with its fingerprint, or
+/// sharing a band of its signature
+async fn candidates(
+    conn: &mut SqliteConnection,
+    job_id: &str,
+    fingerprint: &str,
+    bands: &[i64],
+) -> Result<Vec<Candidate>> {
+    let mut qb = QueryBuilder::<DB>::new(
+        r#"
+        SELECT jobs.job_id, jobs.title, companies.name AS company, jobs.fingerprint, jobs.minhash,
+            jobs.canonical_job_id
+        FROM jobs INNER JOIN companies ON companies.company_id = jobs.company_id
+        WHERE jobs.job_id != "#,
+    );
+    qb.push_bind(job_id.to_owned())
+        .push(" AND (jobs.fingerprint = ")
+        .push_bind(fingerprint.to_owned());
+
+    if !bands.is_empty() {
+        qb.push(" OR jobs.job_id IN (SELECT job_id FROM job_minhash_bands WHERE FALSE");
+        for (band, hash) in bands.iter().enumerate() {
+            qb.push(" OR (band = ")
+                .push_bind(band as i64)
+                .push(" AND hash = ")
+                .push_bind(*hash)
+                .push(")");
+        }
+        qb.push(")");
+    }
+    qb.push(")");
+
+    return qb
+        .build_query_as::<Candidate>()
+        .fetch_all(conn)
+        .await
+        .context("Could not find duplicate candidates");
+}
+
+/// Fingerprints a job as it's stored, grouping it under a job it duplicates.
+/// Jobs already in a group are left where they are.
+pub(super) async fn index_job(
+    conn: &mut SqliteConnection,
+    job_id: &str,
+    company: &str,
+    title: &str,
+    location: &str,
+    description: &str,
+) -> Result<()> {
+    let fingerprint = dedupe::fingerprint(company, title, location);
+    let signature = dedupe::signature(description);
+    let bands = signature.as_deref().map(dedupe::bands).unwrap_or_default();
+
+    sqlx::query("UPDATE jobs SET fingerprint = ?, minhash = ? WHERE job_id = ?")
+        .bind(&fingerprint)
+        .bind(signature.as_deref().map(dedupe::to_bytes))
+        .bind(job_id)
+        .execute(&mut *conn)
+        .await
+        .context("Could not fingerprint job")?;
+
+    sqlx::query("DELETE FROM job_minhash_bands WHERE job_id = ?")
+        .bind(job_id)
+        .execute(&mut *conn)
+        .await
+        .context("Could not clear job signature bands")?;
+    for (band, hash) in bands.iter().enumerate() {
+        sqlx::query("INSERT INTO job_minhash_bands (band, hash, job_id) VALUES (?, ?, ?)")
+            .bind(band as i64)
+            .bind(hash)
+            .bind(job_id)
+            .execute(&mut *conn)
+            .await
+            .context("Could not store job signature bands")?;
+    }
+
+    let grouped: bool = sqlx::query_scalar(
+        r#"
+        SELECT canonical_job_id IS NOT NULL
+            OR EXISTS (SELECT 1 FROM jobs AS duplicate WHERE duplicate.canonical_job_id = jobs.job_id)
+        FROM jobs WHERE job_id = ?
+        "#,
+    )
+    .bind(job_id)
+    .fetch_one(&mut *conn)
+    .await
+    .context("Could not check job's duplicates")?;
+    if grouped {
+        return Ok(());
+    }
+
+    let company = dedupe::normalize_company(company);
+    let title = dedupe::normalize_title(title);
+    let best = candidates(conn, job_id, &fingerprint, &bands)
+        .await?
+        .into_iter()
+        .filter_map(|candidate| {
+            let similarity = signature
+                .as_deref()
+                .zip(candidate.minhash.as_deref())
+                .map(|(a, b)| return dedupe::similarity(a, &dedupe::from_bytes(b)));
+
+            let duplicate = match similarity {
+                _ if candidate.fingerprint.as_deref() == Some(fingerprint.as_str()) => {
+                    similarity.is_none_or(|s| return s >= SIMILAR_SIMILARITY)
+                }
+                Some(similarity) => {
+                    similarity >= DUPLICATE_SIMILARITY
+                        && (dedupe::normalize_company(&candidate.company) == company
+                            || dedupe::normalize_title(&candidate.title) == title)
+                }
+                None => false,
+            };
+
+            return duplicate.then(|| return (similarity.unwrap_or(1.0), candidate));
+        })
+        .max_by(|(a, _), (b, _)| return a.total_cmp(b));
+
+    if let Some((_, candidate)) = best {
+        sqlx::query("UPDATE jobs SET canonical_job_id = ? WHERE job_id = ?")
+            .bind(candidate.canonical_job_id.unwrap_or(candidate.job_id))
+            .bind(job_id)
+            .execute(&mut *conn)
+            .await
+            .context("Could not group duplicate job")?;
+    }
+
+    return Ok(());
+}
+
+/// Fingerprints every job which hasn't been yet, e.g. those from before
+/// duplicates were detected
+#[tracing::instrument(skip(db))]
+pub async fn backfill(db: &DbPool) -> Result<()> {
+    let unindexed: Vec<(String, String, String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT jobs.job_id, companies.name, jobs.title, jobs.location, jobs.description
+        FROM jobs INNER JOIN companies ON companies.company_id = jobs.company_id
+        WHERE jobs.fingerprint IS NULL
+        ORDER BY jobs.created_at, jobs.job_id
+        "#,
+    )
+    .fetch_all(db)
+    .await
+    .context("Could not list jobs without fingerprints")?;
+
+    if unindexed.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+    for (job_id, company, title, location, description) in &unindexed {
+        index_job(&mut tx, job_id, company, title, location, description).await?;
+    }
+    tx.commit().await.context("Could not fingerprint jobs")?;
+
+    info!(count = unindexed.len(), "fingerprinted jobs");
+    return Ok(());
+}
+
+/// The other postings of a job, and postings which may be, as grouped for
+/// `user_id`, or `None` if the job doesn't exist
+#[tracing::instrument(skip(db))]
+pub async fn duplicates(
+    db: &DbPool,
+    user_id: Option<&str>,
+    job_id: &str,
+) -> Result<Option<Duplicates>> {
+    let mut conn = db.acquire().await.context("Could not get connection")?;
+    let Some(root) = group_root(&mut conn, user_id, job_id).await? else {
+        return Ok(None);
+    };
+
+    let group = sqlx::query_as::<_, Posting>(&format!(
+        r#"
+        SELECT jobs.job_id, jobs.title, companies.name AS company, jobs.location, jobs.url
+        FROM jobs INNER JOIN companies ON companies.company_id = jobs.company_id
+        {USER_GROUPS}
+        WHERE (jobs.job_id = ? OR {CANONICAL} = ?) AND jobs.job_id != ?
+        ORDER BY jobs.created_at, jobs.job_id
+        "#
+    ))
+    .bind(user_id)
+    .bind(&root)
+    .bind(&root)
+    .bind(job_id)
+    .fetch_all(&mut *conn)
+    .await
+    .context("Could not get job's duplicates")?;
+
+    let signature: Option<Vec<u8>> =
+        sqlx::query_scalar("SELECT minhash FROM jobs WHERE job_id = ?")
+            .bind(job_id)
+            .fetch_one(&mut *conn)
+            .await
+            .context("Could not get job's signature")?;
+    let similar = match signature {
+        Some(signature) => {
+            let signature = dedupe::from_bytes(&signature);
+            let mut similar = sqlx::query_as::<_, SimilarPosting>(&format!(
+                r#"
+                SELECT DISTINCT jobs.job_id, jobs.title, companies.name AS company, jobs.location,
+                    jobs.url, jobs.minhash
+                FROM job_minhash_bands AS ours
+                INNER JOIN job_minhash_bands AS theirs
+                    ON theirs.band = ours.band AND theirs.hash = ours.hash
+                INNER JOIN jobs ON jobs.job_id = theirs.job_id
+                INNER JOIN companies ON companies.company_id = jobs.company_id
+                {USER_GROUPS}
+                WHERE ours.job_id = ? AND COALESCE({CANONICAL}, jobs.job_id) != ?
+                "#
+            ))
+            .bind(user_id)
+            .bind(job_id)
+            .bind(&root)
+            .fetch_all(&mut *conn)
+            .await
+            .context("Could not find similar jobs")?
+            .into_iter()
+            .map(|s| {
+                let similarity = dedupe::similarity(&signature, &dedupe::from_bytes(&s.minhash));
+                return (similarity, s.posting);
+            })
+            .filter(|(similarity, _)| return *similarity >= SIMILAR_SIMILARITY)
+            .collect::<Vec<_>>();
+
+            similar.sort_by(|(a, _), (b, _)| return b.total_cmp(a));
+            similar
+                .into_iter()
+                .take(MAX_SIMILAR)
+                .map(|(_, posting)| return posting)
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    return Ok(Some(Duplicates {
+        job_id: job_id.to_owned(),
+        group,
+        similar,
+    }));
+}
+
+async fn group_root(
+    conn: &mut SqliteConnection,
+    user_id: Option<&str>,
+    job_id: &str,
+) -> Result<Option<String>> {
+    return sqlx::query_scalar(&format!(
+        "SELECT COALESCE({CANONICAL}, jobs.job_id) FROM jobs {USER_GROUPS} WHERE jobs.job_id = ?"
+    ))
+    .bind(user_id)
+    .bind(job_id)
+    .fetch_optional(conn)
+    .await
+    .context("Could not get job's group");
+}
+
+/// Groups every job in `group` under `canonical_job_id` for the user, or
+/// lists them on their own when it's `None`
+async fn regroup(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    group: &str,
+    canonical_job_id: Option<&str>,
+) -> Result<()> {
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO user_job_groups (user_id, job_id, canonical_job_id)
+        SELECT ?, jobs.job_id, ? FROM jobs {USER_GROUPS}
+        WHERE {CANONICAL} = ?
+        ON CONFLICT (user_id, job_id) DO UPDATE SET canonical_job_id = excluded.canonical_job_id
+        "#
+    ))
+    .bind(user_id)
+    .bind(canonical_job_id)
+    .bind(user_id)
+    .bind(group)
+    .execute(&mut *conn)
+    .await
+    .context("Could not regroup duplicate jobs")?;
+
+    return Ok(());
+}
+
+async fn set_group(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    job_id: &str,
+    canonical_job_id: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_job_groups (user_id, job_id, canonical_job_id) VALUES (?, ?, ?)
+        ON CONFLICT (user_id, job_id) DO UPDATE SET canonical_job_id = excluded.canonical_job_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(job_id)
+    .bind(canonical_job_id)
+    .execute(&mut *conn)
+    .await
+    .context("Could not regroup duplicate job")?;
+
+    return Ok(());
+}
+
+/// Groups `other`, along with any duplicates of it, with `job_id` for the
+/// user. Returns whether both jobs exist.
+#[tracing::instrument(skip(db))]
+pub async fn merge(db: &DbPool, user_id: &str, job_id: &str, other: &str) -> Result<bool> {
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    let (Some(root), Some(other_root)) = (
+        group_root(&mut tx, Some(user_id), job_id).await?,
+        group_root(&mut tx, Some(user_id), other).await?,
+    ) else {
+        return Ok(false);
+    };
+    if root == other_root {
+        return Ok(true);
+    }
+
+    regroup(&mut tx, user_id, &other_root, Some(&root)).await?;
+    set_group(&mut tx, user_id, &other_root, Some(&root)).await?;
+
+    tx.commit()
+        .await
+        .context("Could not merge duplicate jobs")?;
+    return Ok(true);
+}
+
+/// Makes another job of the group `job_id` is canonical for canonical in its
+/// place, preferring open ones, then older ones
+pub(super) async fn hand_over(conn: &mut SqliteConnection, job_id: &str) -> Result<()> {
+    let next: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT job_id FROM jobs WHERE canonical_job_id = ?
+        ORDER BY status != 'open', created_at, job_id
+        LIMIT 1
+        "#,
+    )
+    .bind(job_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .context("Could not pick canonical job")?;
+    let Some(next) = next else {
+        return Ok(());
+    };
+
+    sqlx::query("UPDATE jobs SET canonical_job_id = NULL WHERE job_id = ?")
+        .bind(&next)
+        .execute(&mut *conn)
+        .await
+        .context("Could not hand over canonical job")?;
+    sqlx::query("UPDATE jobs SET canonical_job_id = ? WHERE canonical_job_id = ? OR job_id = ?")
+        .bind(&next)
+        .bind(job_id)
+        .bind(job_id)
+        .execute(&mut *conn)
+        .await
+        .context("Could not hand over canonical job")?;
+
+    return Ok(());
+}
+
+/// Takes a job out of its group of duplicates for the user, returning whether
+/// it exists. When it's the group's canonical job, another posting takes over.
+#[tracing::instrument(skip(db))]
+pub async fn split(db: &DbPool, user_id: &str, job_id: &str) -> Result<bool> {
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    let Some(root) = group_root(&mut tx, Some(user_id), job_id).await? else {
+        return Ok(false);
+    };
+
+    if root == job_id {
+        let next: Option<String> = sqlx::query_scalar(&format!(
+            r#"
+            SELECT jobs.job_id FROM jobs {USER_GROUPS} WHERE {CANONICAL} = ?
+            ORDER BY jobs.status != 'open', jobs.created_at, jobs.job_id
+            LIMIT 1
+            "#
+        ))
+        .bind(user_id)
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Could not pick canonical job")?;
+
+        if let Some(next) = next {
+            set_group(&mut tx, user_id, &next, None).await?;
+            regroup(&mut tx, user_id, job_id, Some(&next)).await?;
+        }
+    }
+
+    set_group(&mut tx, user_id, job_id, None).await?;
+
+    tx.commit().await.context("Could not split duplicate job")?;
+    return Ok(true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{jobs, migrate, test_pool, users},
+        dedupe::tests::{near_copy, OTHER_TEAM, POSTING},
+    };
+
+    fn job(
+        id: &str,
+        company: &str,
+        title: &str,
+        location: &str,
+        description: &str,
+    ) -> jobs::NewJob {
+        return jobs::NewJob {
+            id: id.to_owned(),
+            title: title.to_owned(),
+            company: company.to_owned(),
+            location: location.to_owned(),
+            salary: String::new(),
+            salary_range: None,
+            description: description.to_owned(),
+            work_mode: None,
+            seniority: None,
+            employment_type: None,
+            url: None,
+            posted_at: None,
+            valid_through: None,
+        };
+    }
+
+    async fn canonical(db: &DbPool, job_id: &str) -> Option<String> {
+        return sqlx::query_scalar("SELECT canonical_job_id FROM jobs WHERE job_id = ?")
+            .bind(job_id)
+            .fetch_one(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn groups_copies_but_not_other_teams() {
+        let db = test_pool().await;
+        migrate::up(&db).await.unwrap();
+
+        let jobs = [
+            job("a", "Acme", "Senior Backend Engineer", "Toronto", POSTING),
+            // Written differently elsewhere, so only the description matches
+            job(
+                "copy",
+                "Acme Inc.",
+                "Sr. Backend Eng",
+                "Toronto, ON",
+                &near_copy(),
+            ),
+            // Same title, company and place, but a different job
+            job(
+                "team",
+                "Acme",
+                "Senior Backend Engineer",
+                "Toronto",
+                OTHER_TEAM,
+            ),
+        ];
+        for job in &jobs {
+            jobs::upsert_job(&db, None, job).await.unwrap();
+        }
+
+        assert_eq!(canonical(&db, "a").await, None);
+        assert_eq!(canonical(&db, "copy").await.as_deref(), Some("a"));
+        assert_eq!(canonical(&db, "team").await, None);
+
+        let duplicates = duplicates(&db, None, "a").await.unwrap().unwrap();
+        let group = duplicates
+            .group
+            .iter()
+            .map(|p| return p.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(group, ["copy"]);
+    }
+
+    async fn group(db: &DbPool, user_id: Option<&str>, job_id: &str) -> Vec<String> {
+        let duplicates = duplicates(db, user_id, job_id).await.unwrap().unwrap();
+        return duplicates.group.into_iter().map(|p| return p.id).collect();
+    }
+
+    #[tokio::test]
+    async fn regroups_for_one_user_only() {
+        let db = test_pool().await;
+        migrate::up(&db).await.unwrap();
+        let alice = users::create_user(&db, "alice", "correct horse battery")
+            .await
+            .unwrap()
+            .unwrap();
+        let bob = users::create_user(&db, "bob", "correct horse battery")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let jobs = [
+            job("a", "Acme", "Senior Backend Engineer", "Toronto", POSTING),
+            job(
+                "copy",
+                "Acme Inc.",
+                "Sr. Backend Eng",
+                "Toronto, ON",
+                &near_copy(),
+            ),
+            job(
+                "team",
+                "Acme",
+                "Senior Backend Engineer",
+                "Toronto",
+                OTHER_TEAM,
+            ),
+        ];
+        for job in &jobs {
+            jobs::upsert_job(&db, None, job).await.unwrap();
+        }
+
+        assert!(merge(&db, &alice.id, "a", "team").await.unwrap());
+        assert_eq!(group(&db, Some(&alice.id), "a").await, ["copy", "team"]);
+        assert_eq!(group(&db, Some(&bob.id), "a").await, ["copy"]);
+        assert_eq!(group(&db, None, "a").await, ["copy"]);
+
+        // The oldest other posting takes over the group
+        assert!(split(&db, &alice.id, "a").await.unwrap());
+        assert_eq!(group(&db, Some(&alice.id), "a").await, Vec::<String>::new());
+        assert_eq!(group(&db, Some(&alice.id), "copy").await, ["team"]);
+        assert_eq!(group(&db, Some(&bob.id), "a").await, ["copy"]);
+
+        assert!(split(&db, &bob.id, "copy").await.unwrap());
+        assert_eq!(group(&db, Some(&bob.id), "a").await, Vec::<String>::new());
+        assert_eq!(group(&db, Some(&alice.id), "copy").await, ["team"]);
+        assert_eq!(canonical(&db, "copy").await.as_deref(), Some("a"));
+
+        assert!(!merge(&db, &alice.id, "a", "missing").await.unwrap());
+    }
+}
@@ -0,0 +1,1168 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, QueryBuilder, Row};
+
+use super::{companies, duplicates, notes, pipeline::Stage, shortlists::Shortlist, DbPool, DB};
+use crate::salary::{Salary, SalaryPeriod};
+
+/// Wraps matched terms in FTS5 snippets; control characters can't appear in
+/// stored postings so they survive HTML escaping unambiguously.
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_END: char = '\u{3}';
+
+/// Thresholds offered by the minimum salary facet
+pub const MIN_SALARY_BUCKETS: [i64; 4] = [50_000, 100_000, 150_000, 200_000];
+
+/// Day windows offered by the posted-within facet
+pub const POSTED_WITHIN_BUCKETS: [i64; 3] = [1, 7, 30];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum WorkMode {
+    Remote,
+    Hybrid,
+    Onsite,
+}
+
+impl fmt::Display for WorkMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            WorkMode::Remote => "Remote",
+            WorkMode::Hybrid => "Hybrid",
+            WorkMode::Onsite => "On-site",
+        });
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum Seniority {
+    Intern,
+    Junior,
+    Mid,
+    Senior,
+    Staff,
+    Principal,
+}
+
+impl Seniority {
+    /// Guesses the seniority from words in a job title
+    pub fn from_title(title: &str) -> Option<Self> {
+        let title = title.to_lowercase();
+        let words = title
+            .split(|c: char| return !c.is_alphanumeric())
+            .collect::<Vec<_>>();
+        let has = |word: &str| return words.contains(&word);
+
+        if has("intern") || has("internship") || has("co") && has("op") {
+            return Some(Seniority::Intern);
+        }
+        if has("principal") || has("distinguished") {
+            return Some(Seniority::Principal);
+        }
+        if has("staff") {
+            return Some(Seniority::Staff);
+        }
+        if has("senior") || has("sr") || has("lead") {
+            return Some(Seniority::Senior);
+        }
+        if has("junior") || has("jr") || has("graduate") || has("entry") {
+            return Some(Seniority::Junior);
+        }
+        return None;
+    }
+}
+
+impl fmt::Display for Seniority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            Seniority::Intern => "Intern",
+            Seniority::Junior => "Junior",
+            Seniority::Mid => "Intermediate",
+            Seniority::Senior => "Senior",
+            Seniority::Staff => "Staff",
+            Seniority::Principal => "Principal",
+        });
+    }
+}
+
+/// How a job is employed, as schema.org has it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum EmploymentType {
+    FullTime,
+    PartTime,
+    Contractor,
+    Temporary,
+    Intern,
+    Volunteer,
+    PerDiem,
+    Other,
+}
+
+impl EmploymentType {
+    /// Reads schema.org's `FULL_TIME` style values, along with labels like
+    /// "Full-time" used by job boards
+    pub fn parse(text: &str) -> Option<Self> {
+        let normalized = text.trim().to_lowercase().replace(['-', ' '], "_");
+
+        return match normalized.as_str() {
+            "full_time" | "fulltime" | "permanent" => Some(EmploymentType::FullTime),
+            "part_time" | "parttime" => Some(EmploymentType::PartTime),
+            "contractor" | "contract" => Some(EmploymentType::Contractor),
+            "temporary" | "temp" | "seasonal" => Some(EmploymentType::Temporary),
+            "intern" | "internship" => Some(EmploymentType::Intern),
+            "volunteer" => Some(EmploymentType::Volunteer),
+            "per_diem" => Some(EmploymentType::PerDiem),
+            "other" => Some(EmploymentType::Other),
+            _ => None,
+        };
+    }
+}
+
+impl fmt::Display for EmploymentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            EmploymentType::FullTime => "Full-time",
+            EmploymentType::PartTime => "Part-time",
+            EmploymentType::Contractor => "Contract",
+            EmploymentType::Temporary => "Temporary",
+            EmploymentType::Intern => "Internship",
+            EmploymentType::Volunteer => "Volunteer",
+            EmploymentType::PerDiem => "Per diem",
+            EmploymentType::Other => "Other",
+        });
+    }
+}
+
+/// Whether a job is still taking applications
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum JobStatus {
+    Open,
+    /// Gone from its source, or past when applications close
+    Closed,
+    /// Closed for a while, and only listed for those keeping track of it
+    Archived,
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            JobStatus::Open => "Open",
+            JobStatus::Closed => "Closed",
+            JobStatus::Archived => "Archived",
+        });
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobSort {
+    Newest,
+    /// Highest annualized salary (in the display currency) first
+    Salary,
+}
+
+/// How a listing is actually ordered: searches default to relevance
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum JobOrder {
+    Relevance,
+    Newest,
+    Salary,
+}
+
+#[derive(Clone, FromRow)]
+pub struct JobDetails {
+    pub id: String,
+    pub title: String,
+    pub company: String,
+    pub company_slug: String,
+    pub company_domain: Option<String>,
+    pub company_logo_url: Option<String>,
+    pub location: String,
+    /// The salary as posted
+    pub salary: String,
+    pub salary_min: Option<f64>,
+    pub salary_max: Option<f64>,
+    pub salary_currency: Option<String>,
+    pub salary_period: Option<SalaryPeriod>,
+    pub salary_equity: bool,
+    /// Top of the salary range per year in the display currency, for sorting
+    /// and filtering
+    pub salary_display: Option<i64>,
+    pub display_currency: Option<String>,
+    /// Display currency units per unit of the posted currency, when both have
+    /// an exchange rate
+    pub salary_exchange_rate: Option<f64>,
+    pub description: String,
+    pub work_mode: Option<WorkMode>,
+    pub seniority: Option<Seniority>,
+    pub employment_type: Option<EmploymentType>,
+    /// The original posting, for imported jobs
+    pub url: Option<String>,
+    /// When the job was posted, in UTC
+    pub posted_at: Option<String>,
+    /// When applications close, in UTC
+    pub valid_through: Option<String>,
+    pub status: JobStatus,
+    pub created_at: String,
+    /// Best matching excerpt, delimited by [`HIGHLIGHT_START`]/[`HIGHLIGHT_END`],
+    /// when the job was found through a search
+    #[sqlx(default)]
+    pub snippet: Option<String>,
+    /// Whether the viewer saved the job, when they're logged in
+    #[sqlx(default)]
+    pub saved: Option<bool>,
+    /// Whether the viewer applied to the job, when they're logged in
+    #[sqlx(default)]
+    pub applied: Option<bool>,
+    /// Where the job is in the viewer's pipeline, if it's in there
+    #[sqlx(default)]
+    pub stage: Option<Stage>,
+}
+
+impl JobDetails {
+    /// The salary parsed out of its posted text, if it could be
+    pub fn salary_range(&self) -> Option<Salary> {
+        return Some(Salary {
+            min: self.salary_min?,
+            max: self.salary_max?,
+            currency: self.salary_currency.clone()?,
+            period: self.salary_period?,
+            equity: self.salary_equity,
+        });
+    }
+
+    /// The annualized salary converted into the display currency, if it was
+    /// posted in another one
+    pub fn salary_converted(&self) -> Option<Salary> {
+        let salary = self.salary_range()?.annualized();
+        let currency = self.display_currency.clone()?;
+        if salary.currency == currency {
+            return None;
+        }
+
+        let rate = self.salary_exchange_rate?;
+        return Some(Salary {
+            min: (salary.min * rate).round(),
+            max: (salary.max * rate).round(),
+            currency,
+            equity: false,
+            ..salary
+        });
+    }
+}
+
+/// Who jobs are shown to, deciding the salary currency and per-user state
+#[derive(Clone, Debug)]
+pub struct Viewer {
+    /// Currency salaries are shown and compared in
+    pub currency: String,
+    /// The logged in user, if any
+    pub user_id: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct JobFilter {
+    pub viewer: Viewer,
+    /// Only jobs on one of the viewer's lists
+    pub shortlist: Option<Shortlist>,
+    /// Only jobs the viewer tagged with this
+    pub tag: Option<String>,
+    pub query: Option<String>,
+    pub location: Option<String>,
+    pub company: Option<String>,
+    /// In the display currency
+    pub min_salary: Option<i64>,
+    pub work_mode: Option<WorkMode>,
+    pub seniority: Option<Seniority>,
+    pub posted_within_days: Option<i64>,
+    /// Also list closed jobs, which are otherwise only on the viewer's lists
+    pub include_closed: bool,
+    pub sort: Option<JobSort>,
+}
+
+impl JobFilter {
+    fn order(&self) -> JobOrder {
+        return match self.sort {
+            Some(JobSort::Newest) => JobOrder::Newest,
+            Some(JobSort::Salary) => JobOrder::Salary,
+            None if self.query.as_deref().and_then(fts_query).is_some() => JobOrder::Relevance,
+            None => JobOrder::Newest,
+        };
+    }
+}
+
+/// Position in a job listing to continue after. Listings are ordered by a
+/// unique key, so pages stay stable when new jobs are inserted. Search results
+/// are paged by offset instead, as bm25 scores are floats that shift whenever
+/// jobs are added.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JobCursor {
+    offset: Option<i64>,
+    salary: Option<i64>,
+    created_at: String,
+    id: String,
+}
+
+impl JobCursor {
+    fn after(job: &JobDetails, offset: Option<i64>) -> Self {
+        return JobCursor {
+            offset,
+            salary: job.salary_display,
+            created_at: job.created_at.clone(),
+            id: job.id.clone(),
+        };
+    }
+
+    /// Opaque, URL safe representation of the cursor
+    pub fn encode(&self) -> String {
+        return URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("job cursor to serialize"));
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        return serde_json::from_slice(&json).ok();
+    }
+}
+
+pub struct JobPage {
+    pub jobs: Vec<JobDetails>,
+    /// Where the next page starts, if there is one
+    pub next: Option<JobCursor>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Facet {
+    Location,
+    Company,
+    MinSalary,
+    WorkMode,
+    Seniority,
+    PostedWithin,
+    Tag,
+}
+
+#[derive(FromRow)]
+pub struct FacetCount<T> {
+    pub value: T,
+    pub count: i64,
+}
+
+/// Number of jobs matching each facet value, given every *other* active filter
+pub struct JobFacets {
+    pub locations: Vec<FacetCount<String>>,
+    pub companies: Vec<FacetCount<String>>,
+    pub min_salaries: Vec<FacetCount<i64>>,
+    pub work_modes: Vec<FacetCount<WorkMode>>,
+    pub seniorities: Vec<FacetCount<Seniority>>,
+    pub posted_within: Vec<FacetCount<i64>>,
+    /// The viewer's tags, empty when nobody is logged in
+    pub tags: Vec<FacetCount<String>>,
+}
+
+const JOB_DETAILS_COLUMNS: &str = r#"
+    jobs.job_id AS id,
+    jobs.title,
+    companies.name AS company,
+    companies.slug AS company_slug,
+    companies.domain AS company_domain,
+    companies.logo_url AS company_logo_url,
+    jobs.location,
+    jobs.salary,
+    jobs.salary_min,
+    jobs.salary_max,
+    jobs.salary_currency,
+    jobs.salary_period,
+    jobs.salary_equity,
+    display_rate.currency AS display_currency,
+    display_rate.per_usd / posted_rate.per_usd AS salary_exchange_rate,
+    jobs.description,
+    jobs.work_mode,
+    jobs.seniority,
+    jobs.employment_type,
+    jobs.url,
+    jobs.posted_at,
+    jobs.valid_through,
+    jobs.status,
+    jobs.created_at
+"#;
+
+/// Top of a job's annual salary range, converted into the display currency
+const SALARY_DISPLAY: &str =
+    "CAST(ROUND(jobs.salary_annual * display_rate.per_usd / posted_rate.per_usd) AS INTEGER)";
+
+/// Pushes the job details columns, along with the salary in the display
+/// currency and the viewer's pipeline
+pub(super) fn push_job_details(qb: &mut QueryBuilder<'_, DB>, viewer: &Viewer) {
+    qb.push(JOB_DETAILS_COLUMNS)
+        .push(format!(", {SALARY_DISPLAY} AS salary_display"));
+
+    if viewer.user_id.is_some() {
+        qb.push(", applications.stage");
+        for list in [Shortlist::Saved, Shortlist::Applied] {
+            qb.push(format!(
+                ", ({}) IS TRUE AS {}",
+                list.condition(),
+                list.slug()
+            ));
+        }
+    }
+}
+
+/// Pushes the tables behind the job details, as seen by `viewer`
+pub(super) fn push_job_details_from(qb: &mut QueryBuilder<'_, DB>, viewer: &Viewer) {
+    qb.push(
+        r#"
+        FROM jobs
+        INNER JOIN companies ON companies.company_id = jobs.company_id
+        LEFT JOIN exchange_rates AS posted_rate ON posted_rate.currency = jobs.salary_currency
+        LEFT JOIN exchange_rates AS display_rate ON display_rate.currency = "#,
+    )
+    .push_bind(viewer.currency.clone());
+
+    if let Some(user_id) = &viewer.user_id {
+        qb.push(
+            " LEFT JOIN applications ON applications.job_id = jobs.job_id AND applications.user_id = ",
+        )
+        .push_bind(user_id.clone());
+    }
+}
+
+/// Turns free text into an FTS5 query of quoted prefix terms, so user input
+/// can never be parsed as FTS5 syntax.
+fn fts_query(query: &str) -> Option<String> {
+    let terms = query
+        .split_whitespace()
+        .map(|t| return format!("\"{}\"*", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+
+    return (!terms.is_empty()).then(|| return terms.join(" "));
+}
+
+/// Pushes the `FROM ... WHERE ...` of a job listing, applying every filter
+/// except `except` (so a facet's counts aren't narrowed by its own value).
+fn push_filtered_from(qb: &mut QueryBuilder<'_, DB>, filter: &JobFilter, except: Option<Facet>) {
+    let applies = |facet: Facet| return except != Some(facet);
+
+    push_job_details_from(qb, &filter.viewer);
+
+    let fts = filter.query.as_deref().and_then(fts_query);
+    if fts.is_some() {
+        qb.push(" INNER JOIN jobs_fts ON jobs_fts.rowid = jobs.search_rowid");
+    }
+
+    qb.push(" WHERE TRUE");
+
+    // Each open job is listed once, under its canonical posting, unless the
+    // viewer kept track of it
+    if filter.shortlist.is_none() && filter.tag.is_none() {
+        // As the viewer regrouped it, if they did
+        qb.push(
+            " AND COALESCE((SELECT mine.canonical_job_id IS NULL FROM user_job_groups AS mine \
+            WHERE mine.job_id = jobs.job_id AND mine.user_id = ",
+        )
+        .push_bind(filter.viewer.user_id.clone())
+        .push("), jobs.canonical_job_id IS NULL)");
+        qb.push(match filter.include_closed {
+            true => " AND jobs.status != 'archived'",
+            false => " AND jobs.status = 'open'",
+        });
+    }
+
+    if let Some(fts) = fts {
+        qb.push(" AND jobs_fts MATCH ").push_bind(fts);
+    }
+    if let Some(list) = filter.shortlist {
+        match filter.viewer.user_id {
+            Some(_) => qb.push(format!(" AND {}", list.condition())),
+            // Nobody to have listed anything
+            None => qb.push(" AND FALSE"),
+        };
+    }
+    if let Some(tag) = filter.tag.as_deref().filter(|_| return applies(Facet::Tag)) {
+        match &filter.viewer.user_id {
+            Some(user_id) => {
+                qb.push(" AND");
+                notes::push_tagged(qb, user_id, tag);
+            }
+            // Nobody to have tagged anything
+            None => {
+                qb.push(" AND FALSE");
+            }
+        }
+    }
+    if let Some(location) = filter
+        .location
+        .clone()
+        .filter(|_| return applies(Facet::Location))
+    {
+        qb.push(" AND jobs.location = ").push_bind(location);
+    }
+    if let Some(company) = filter
+        .company
+        .clone()
+        .filter(|_| return applies(Facet::Company))
+    {
+        qb.push(" AND companies.name = ").push_bind(company);
+    }
+    if let Some(min_salary) = filter
+        .min_salary
+        .filter(|_| return applies(Facet::MinSalary))
+    {
+        qb.push(format!(" AND {SALARY_DISPLAY} >= "))
+            .push_bind(min_salary);
+    }
+    if let Some(work_mode) = filter.work_mode.filter(|_| return applies(Facet::WorkMode)) {
+        qb.push(" AND jobs.work_mode = ").push_bind(work_mode);
+    }
+    if let Some(seniority) = filter
+        .seniority
+        .filter(|_| return applies(Facet::Seniority))
+    {
+        qb.push(" AND jobs.seniority = ").push_bind(seniority);
+    }
+    if let Some(days) = filter
+        .posted_within_days
+        .filter(|_| return applies(Facet::PostedWithin))
+    {
+        qb.push(" AND");
+        push_posted_within(qb, days);
+    }
+}
+
+fn push_posted_within(qb: &mut QueryBuilder<'_, DB>, days: i64) {
+    qb.push(" jobs.created_at >= datetime('now', ")
+        .push_bind(format!("-{days} days"))
+        .push(")");
+}
+
+/// Pushes the keyset condition selecting jobs ordered after `cursor`
+fn push_after(qb: &mut QueryBuilder<'_, DB>, cursor: &JobCursor, order: JobOrder) {
+    if order == JobOrder::Relevance {
+        return;
+    }
+
+    qb.push(" AND (");
+    match (order, cursor.salary) {
+        // Jobs without a salary sort last
+        (JobOrder::Salary, Some(salary)) => {
+            qb.push(format!("{SALARY_DISPLAY} < "))
+                .push_bind(salary)
+                .push(format!(
+                    " OR {SALARY_DISPLAY} IS NULL OR ({SALARY_DISPLAY} = "
+                ))
+                .push_bind(salary)
+                .push(" AND ");
+            push_newest_after(qb, cursor);
+            qb.push(")");
+        }
+        (JobOrder::Salary, None) => {
+            qb.push(format!("{SALARY_DISPLAY} IS NULL AND "));
+            push_newest_after(qb, cursor);
+        }
+        _ => push_newest_after(qb, cursor),
+    }
+    qb.push(")");
+}
+
+fn push_newest_after(qb: &mut QueryBuilder<'_, DB>, cursor: &JobCursor) {
+    qb.push("(jobs.created_at < ")
+        .push_bind(cursor.created_at.clone())
+        .push(" OR (jobs.created_at = ")
+        .push_bind(cursor.created_at.clone())
+        .push(" AND jobs.job_id > ")
+        .push_bind(cursor.id.clone())
+        .push("))");
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn list_jobs(
+    db: &DbPool,
+    filter: &JobFilter,
+    after: Option<&JobCursor>,
+    limit: i64,
+) -> Result<JobPage> {
+    let searching = filter.query.as_deref().and_then(fts_query).is_some();
+    let order = filter.order();
+
+    let mut qb = QueryBuilder::<DB>::new("SELECT ");
+    push_job_details(&mut qb, &filter.viewer);
+
+    if searching {
+        qb.push(format!(
+            ", snippet(jobs_fts, -1, char({}), char({}), '…', 24) AS snippet",
+            HIGHLIGHT_START as u32, HIGHLIGHT_END as u32
+        ));
+    }
+
+    push_filtered_from(&mut qb, filter, None);
+
+    if let Some(cursor) = after {
+        push_after(&mut qb, cursor, order);
+    }
+
+    qb.push(match order {
+        JobOrder::Relevance => {
+            " ORDER BY bm25(jobs_fts), jobs.created_at DESC, jobs.job_id".to_owned()
+        }
+        JobOrder::Newest => " ORDER BY jobs.created_at DESC, jobs.job_id".to_owned(),
+        JobOrder::Salary => {
+            format!(" ORDER BY {SALARY_DISPLAY} DESC NULLS LAST, jobs.created_at DESC, jobs.job_id")
+        }
+    });
+
+    // One extra row tells us whether there is another page
+    qb.push(" LIMIT ").push_bind(limit + 1);
+
+    let offset = match order {
+        JobOrder::Relevance => Some(after.and_then(|cursor| cursor.offset).unwrap_or(0)),
+        _ => None,
+    };
+    if let Some(offset) = offset {
+        qb.push(" OFFSET ").push_bind(offset);
+    }
+
+    let mut jobs = qb
+        .build_query_as::<JobDetails>()
+        .fetch_all(db)
+        .await
+        .context("Could not list jobs")?;
+
+    let next = (jobs.len() as i64 > limit).then(|| {
+        jobs.truncate(limit as usize);
+        let job = jobs.last().expect("a full page of jobs");
+        return JobCursor::after(job, offset.map(|offset| offset + limit));
+    });
+
+    return Ok(JobPage { jobs, next });
+}
+
+async fn value_counts<T>(
+    db: &DbPool,
+    filter: &JobFilter,
+    facet: Facet,
+    column: &str,
+) -> Result<Vec<FacetCount<T>>>
+where
+    T: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+{
+    let mut qb = QueryBuilder::<DB>::new(format!("SELECT {column} AS value, COUNT(*) AS count"));
+    push_filtered_from(&mut qb, filter, Some(facet));
+    qb.push(format!(
+        " AND {column} IS NOT NULL GROUP BY {column} ORDER BY count DESC, value"
+    ));
+
+    return qb
+        .build_query_as::<FacetCount<T>>()
+        .fetch_all(db)
+        .await
+        .with_context(|| return format!("Could not count jobs by {column}"));
+}
+
+/// Counts jobs for every bucket in a single pass, `condition` pushing the SQL
+/// predicate a job must satisfy to be counted in the given bucket
+async fn bucket_counts(
+    db: &DbPool,
+    filter: &JobFilter,
+    facet: Facet,
+    buckets: &[i64],
+    condition: impl Fn(&mut QueryBuilder<'_, DB>, i64),
+) -> Result<Vec<FacetCount<i64>>> {
+    let mut qb = QueryBuilder::<DB>::new("SELECT ");
+    for (i, bucket) in buckets.iter().enumerate() {
+        if i > 0 {
+            qb.push(", ");
+        }
+        qb.push("COALESCE(SUM(");
+        condition(&mut qb, *bucket);
+        qb.push("), 0)");
+    }
+    push_filtered_from(&mut qb, filter, Some(facet));
+
+    let row = qb
+        .build()
+        .fetch_one(db)
+        .await
+        .context("Could not count jobs per bucket")?;
+
+    return buckets
+        .iter()
+        .enumerate()
+        .map(|(i, bucket)| {
+            return Ok(FacetCount {
+                value: *bucket,
+                count: row.try_get(i)?,
+            });
+        })
+        .collect();
+}
+
+/// Counts jobs per tag the viewer used
+async fn tag_counts(db: &DbPool, filter: &JobFilter) -> Result<Vec<FacetCount<String>>> {
+    let Some(user_id) = &filter.viewer.user_id else {
+        return Ok(Vec::new());
+    };
+
+    let mut qb = QueryBuilder::<DB>::new(
+        "SELECT job_tags.tag AS value, COUNT(*) AS count FROM job_tags WHERE job_tags.user_id = ",
+    );
+    qb.push_bind(user_id.clone())
+        .push(" AND job_tags.job_id IN (SELECT jobs.job_id");
+    push_filtered_from(&mut qb, filter, Some(Facet::Tag));
+    qb.push(") GROUP BY job_tags.tag ORDER BY count DESC, value");
+
+    return qb
+        .build_query_as::<FacetCount<String>>()
+        .fetch_all(db)
+        .await
+        .context("Could not count jobs by tag");
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn facet_counts(db: &DbPool, filter: &JobFilter) -> Result<JobFacets> {
+    return Ok(JobFacets {
+        locations: value_counts(db, filter, Facet::Location, "jobs.location").await?,
+        companies: value_counts(db, filter, Facet::Company, "companies.name").await?,
+        min_salaries: bucket_counts(
+            db,
+            filter,
+            Facet::MinSalary,
+            &MIN_SALARY_BUCKETS,
+            |qb, bucket| {
+                qb.push(format!(" {SALARY_DISPLAY} >= ")).push_bind(bucket);
+            },
+        )
+        .await?,
+        work_modes: value_counts(db, filter, Facet::WorkMode, "jobs.work_mode").await?,
+        seniorities: value_counts(db, filter, Facet::Seniority, "jobs.seniority").await?,
+        posted_within: bucket_counts(
+            db,
+            filter,
+            Facet::PostedWithin,
+            &POSTED_WITHIN_BUCKETS,
+            push_posted_within,
+        )
+        .await?,
+        tags: tag_counts(db, filter).await?,
+    });
+}
+
+/// Gets a job, as seen by `viewer`
+#[tracing::instrument(skip(db))]
+pub async fn get_job(db: &DbPool, id: &str, viewer: &Viewer) -> Result<Option<JobDetails>> {
+    let mut qb = QueryBuilder::<DB>::new("SELECT ");
+    push_job_details(&mut qb, viewer);
+    push_job_details_from(&mut qb, viewer);
+    qb.push(" WHERE jobs.job_id = ").push_bind(id.to_owned());
+
+    return qb
+        .build_query_as::<JobDetails>()
+        .fetch_optional(db)
+        .await
+        .context("Could not get job");
+}
+
+/// A job as imported, and as exported by `wantjob export`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewJob {
+    /// Unique across every source, e.g. `greenhouse-acme-1234`
+    pub id: String,
+    pub title: String,
+    pub company: String,
+    #[serde(default)]
+    pub location: String,
+    /// As posted, parsed into a structured salary if there isn't one
+    #[serde(default)]
+    pub salary: String,
+    pub salary_range: Option<Salary>,
+    #[serde(default)]
+    pub description: String,
+    pub work_mode: Option<WorkMode>,
+    pub seniority: Option<Seniority>,
+    pub employment_type: Option<EmploymentType>,
+    pub url: Option<String>,
+    /// When the job was posted, in UTC as `YYYY-MM-DD HH:MM:SS`
+    pub posted_at: Option<String>,
+    /// When applications close, in UTC as `YYYY-MM-DD HH:MM:SS`
+    pub valid_through: Option<String>,
+}
+
+/// Stores a job imported from `source_id`, updating it if it was imported before
+#[tracing::instrument(skip(db, job), fields(job.id = job.id))]
+pub async fn upsert_job(db: &DbPool, source_id: Option<i64>, job: &NewJob) -> Result<()> {
+    store_job(db, source_id, job, true).await?;
+    return Ok(());
+}
+
+/// Stores a job a user imported, unless that would replace one imported from a
+/// source. Returns whether it was stored.
+#[tracing::instrument(skip(db, job), fields(job.id = job.id))]
+pub async fn upsert_user_job(db: &DbPool, job: &NewJob) -> Result<bool> {
+    return store_job(db, None, job, false).await;
+}
+
+async fn store_job(
+    db: &DbPool,
+    source_id: Option<i64>,
+    job: &NewJob,
+    replace_sourced: bool,
+) -> Result<bool> {
+    let salary = job
+        .salary_range
+        .clone()
+        .or_else(|| return Salary::parse(&job.salary));
+    let salary_text = match (&job.salary_range, job.salary.is_empty()) {
+        (Some(range), true) => range.to_string(),
+        _ => job.salary.clone(),
+    };
+
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    let company_id = companies::ensure_company(&mut tx, &job.company).await?;
+
+    let stored = sqlx::query(
+        r#"
+        INSERT INTO jobs (
+            job_id, company_id, source_id, title, location, salary,
+            salary_min, salary_max, salary_currency, salary_period, salary_equity, salary_annual,
+            description, work_mode, seniority, employment_type, url, posted_at, valid_through,
+            created_at, last_seen_at
+        )
+        VALUES (
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+            COALESCE(?, CURRENT_TIMESTAMP), CURRENT_TIMESTAMP
+        )
+        ON CONFLICT (job_id) DO UPDATE SET
+            company_id = excluded.company_id,
+            -- Imported from a file it's the same job, still from its source
+            source_id = COALESCE(excluded.source_id, jobs.source_id),
+            title = excluded.title,
+            location = excluded.location,
+            salary = excluded.salary,
+            salary_min = excluded.salary_min,
+            salary_max = excluded.salary_max,
+            salary_currency = excluded.salary_currency,
+            salary_period = excluded.salary_period,
+            salary_equity = excluded.salary_equity,
+            salary_annual = excluded.salary_annual,
+            description = excluded.description,
+            work_mode = excluded.work_mode,
+            seniority = excluded.seniority,
+            employment_type = excluded.employment_type,
+            url = excluded.url,
+            posted_at = COALESCE(excluded.posted_at, jobs.posted_at),
+            valid_through = excluded.valid_through,
+            last_seen_at = excluded.last_seen_at,
+            -- Listed again, so open again unless it's past closing
+            status = CASE
+                WHEN excluded.valid_through <= CURRENT_TIMESTAMP THEN jobs.status
+                ELSE 'open'
+            END,
+            closed_at = CASE
+                WHEN excluded.valid_through <= CURRENT_TIMESTAMP THEN jobs.closed_at
+            END
+        WHERE jobs.source_id IS NULL OR ?
+        "#,
+    )
+    .bind(&job.id)
+    .bind(company_id)
+    .bind(source_id)
+    .bind(&job.title)
+    .bind(&job.location)
+    .bind(salary_text)
+    .bind(salary.as_ref().map(|s| return s.min))
+    .bind(salary.as_ref().map(|s| return s.max))
+    .bind(salary.as_ref().map(|s| return s.currency.clone()))
+    .bind(salary.as_ref().map(|s| return s.period))
+    .bind(salary.as_ref().is_some_and(|s| return s.equity))
+    .bind(
+        salary
+            .as_ref()
+            .map(|s| return s.annual_max().round() as i64),
+    )
+    .bind(&job.description)
+    .bind(job.work_mode)
+    .bind(job.seniority)
+    .bind(job.employment_type)
+    .bind(&job.url)
+    .bind(&job.posted_at)
+    .bind(&job.valid_through)
+    .bind(&job.posted_at)
+    .bind(replace_sourced)
+    .execute(&mut *tx)
+    .await
+    .context("Could not store imported job")?;
+    if stored.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    duplicates::index_job(
+        &mut tx,
+        &job.id,
+        &job.company,
+        &job.title,
+        &job.location,
+        &job.description,
+    )
+    .await?;
+
+    tx.commit().await.context("Could not store imported job")?;
+    return Ok(true);
+}
+
+/// Every open job, oldest first, as it would be imported
+#[tracing::instrument(skip(db))]
+pub async fn export_jobs(db: &DbPool) -> Result<Vec<NewJob>> {
+    let viewer = Viewer {
+        currency: "USD".to_owned(),
+        user_id: None,
+    };
+    let mut qb = QueryBuilder::<DB>::new("SELECT ");
+    push_job_details(&mut qb, &viewer);
+    push_job_details_from(&mut qb, &viewer);
+    qb.push(" WHERE jobs.status = 'open' ORDER BY jobs.created_at, jobs.job_id");
+
+    let jobs = qb
+        .build_query_as::<JobDetails>()
+        .fetch_all(db)
+        .await
+        .context("Could not list jobs to export")?;
+
+    return Ok(jobs
+        .into_iter()
+        .map(|job| {
+            return NewJob {
+                salary_range: job.salary_range(),
+                id: job.id,
+                title: job.title,
+                company: job.company,
+                location: job.location,
+                salary: job.salary,
+                description: job.description,
+                work_mode: job.work_mode,
+                seniority: job.seniority,
+                employment_type: job.employment_type,
+                url: job.url,
+                posted_at: job.posted_at,
+                valid_through: job.valid_through,
+            };
+        })
+        .collect());
+}
+
+/// How many jobs there are in each status, counting groups of duplicates once
+#[tracing::instrument(skip(db))]
+pub async fn count_by_status(db: &DbPool) -> Result<Vec<(JobStatus, i64)>> {
+    return sqlx::query_as(
+        "SELECT status, COUNT(*) FROM jobs WHERE canonical_job_id IS NULL GROUP BY status",
+    )
+    .fetch_all(db)
+    .await
+    .context("Could not count jobs");
+}
+
+/// Parses the free-text salary of every job that hasn't been yet, storing its
+/// structured and annualized form
+#[tracing::instrument(skip(db))]
+pub async fn backfill_salaries(db: &DbPool) -> Result<()> {
+    let unparsed: Vec<(String, String)> = sqlx::query_as(
+        "SELECT job_id, salary FROM jobs WHERE salary_period IS NULL AND salary != ''",
+    )
+    .fetch_all(db)
+    .await
+    .context("Could not list jobs with unparsed salaries")?;
+
+    for (job_id, text) in unparsed {
+        let Some(salary) = Salary::parse(&text) else {
+            continue;
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE jobs SET
+                salary_min = ?,
+                salary_max = ?,
+                salary_currency = ?,
+                salary_period = ?,
+                salary_equity = ?,
+                salary_annual = ?
+            WHERE job_id = ?
+            "#,
+        )
+        .bind(salary.min)
+        .bind(salary.max)
+        .bind(&salary.currency)
+        .bind(salary.period)
+        .bind(salary.equity)
+        .bind(salary.annual_max().round() as i64)
+        .bind(&job_id)
+        .execute(db)
+        .await
+        .context("Could not store parsed salary")?;
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{
+        migrate,
+        sources::{self, SourceKind},
+        test_pool,
+    };
+
+    fn job(id: &str, title: &str) -> NewJob {
+        return NewJob {
+            id: id.to_owned(),
+            title: title.to_owned(),
+            company: "Acme".to_owned(),
+            location: String::new(),
+            salary: String::new(),
+            salary_range: None,
+            description: String::new(),
+            work_mode: None,
+            seniority: None,
+            employment_type: None,
+            url: None,
+            posted_at: None,
+            valid_through: None,
+        };
+    }
+
+    fn searching(query: &str) -> JobFilter {
+        return JobFilter {
+            viewer: Viewer {
+                currency: "USD".to_owned(),
+                user_id: None,
+            },
+            shortlist: None,
+            tag: None,
+            query: Some(query.to_owned()),
+            location: None,
+            company: None,
+            min_salary: None,
+            work_mode: None,
+            seniority: None,
+            posted_within_days: None,
+            include_closed: false,
+            sort: None,
+        };
+    }
+
+    async fn search(db: &DbPool, query: &str) -> Vec<String> {
+        let page = list_jobs(db, &searching(query), None, 10).await.unwrap();
+        return page.jobs.into_iter().map(|job| job.id).collect();
+    }
+
+    #[tokio::test]
+    async fn search_survives_vacuum() {
+        let db = test_pool().await;
+        migrate::up(&db).await.unwrap();
+        upsert_job(&db, None, &job("a", "Gardener")).await.unwrap();
+        upsert_job(&db, None, &job("b", "Plumber")).await.unwrap();
+        upsert_job(&db, None, &job("c", "Welder")).await.unwrap();
+
+        // Leaves a gap in the rowids for VACUUM to close up
+        sqlx::query("DELETE FROM jobs WHERE job_id = 'a'")
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query("VACUUM").execute(&db).await.unwrap();
+        upsert_job(&db, None, &job("b", "Electrician"))
+            .await
+            .unwrap();
+
+        assert_eq!(search(&db, "gardener").await, Vec::<String>::new());
+        assert_eq!(search(&db, "plumber").await, Vec::<String>::new());
+        assert_eq!(search(&db, "electrician").await, vec!["b"]);
+        assert_eq!(search(&db, "welder").await, vec!["c"]);
+    }
+
+    #[tokio::test]
+    async fn pages_through_equally_relevant_results() {
+        let db = test_pool().await;
+        migrate::up(&db).await.unwrap();
+        for id in ["a", "b", "c", "d", "e"] {
+            let job = NewJob {
+                company: format!("Acme {id}"),
+                ..job(id, "Plumber")
+            };
+            upsert_job(&db, None, &job).await.unwrap();
+        }
+
+        let filter = searching("plumber");
+        let mut seen = Vec::new();
+        let mut after = None;
+        loop {
+            let page = list_jobs(&db, &filter, after.as_ref(), 2).await.unwrap();
+            seen.extend(page.jobs.into_iter().map(|job| job.id));
+            match page.next {
+                Some(next) => after = JobCursor::decode(&next.encode()),
+                None => break,
+            }
+        }
+
+        seen.sort();
+        assert_eq!(seen, ["a", "b", "c", "d", "e"]);
+    }
+
+    #[tokio::test]
+    async fn reimporting_keeps_the_source() {
+        let db = test_pool().await;
+        migrate::up(&db).await.unwrap();
+        let source = sources::add_source(&db, SourceKind::Greenhouse, "acme", "Acme", None, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        upsert_job(&db, Some(source.id), &job("a", "Gardener"))
+            .await
+            .unwrap();
+        upsert_job(&db, None, &job("a", "Head Gardener"))
+            .await
+            .unwrap();
+
+        let source_id: Option<i64> =
+            sqlx::query_scalar("SELECT source_id FROM jobs WHERE job_id = 'a'")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(source_id, Some(source.id));
+    }
+
+    #[tokio::test]
+    async fn user_imports_leave_sourced_jobs_alone() {
+        let db = test_pool().await;
+        migrate::up(&db).await.unwrap();
+        let source = sources::add_source(&db, SourceKind::Greenhouse, "acme", "Acme", None, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        upsert_job(&db, Some(source.id), &job("a", "Gardener"))
+            .await
+            .unwrap();
+        assert!(!upsert_user_job(&db, &job("a", "Hijacked")).await.unwrap());
+        assert!(upsert_user_job(&db, &job("b", "Plumber")).await.unwrap());
+        assert!(upsert_user_job(&db, &job("b", "Head Plumber"))
+            .await
+            .unwrap());
+
+        let titles: Vec<String> = sqlx::query_scalar("SELECT title FROM jobs ORDER BY job_id")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(titles, ["Gardener", "Head Plumber"]);
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use sqlx::QueryBuilder;
+use tracing::info;
+
+use super::{duplicates, DbPool, DB};
+
+/// Marks the jobs just imported from a source as seen together, so an
+/// unchanged feed can tell which of its jobs it still lists
+#[tracing::instrument(skip(db, job_ids), fields(count = job_ids.len()))]
+pub async fn mark_seen(db: &DbPool, source_id: i64, job_ids: &[&str]) -> Result<()> {
+    if job_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut qb = QueryBuilder::<DB>::new(
+        "UPDATE jobs SET last_seen_at = CURRENT_TIMESTAMP WHERE source_id = ",
+    );
+    qb.push_bind(source_id).push(" AND job_id IN (");
+    let mut ids = qb.separated(", ");
+    for id in job_ids {
+        ids.push_bind(id.to_string());
+    }
+    qb.push(")");
+
+    qb.build()
+        .execute(db)
+        .await
+        .context("Could not mark jobs as seen")?;
+
+    return Ok(());
+}
+
+/// Marks the jobs a source listed last time as seen again, for sources which
+/// haven't changed since
+#[tracing::instrument(skip(db))]
+pub async fn mark_seen_again(db: &DbPool, source_id: i64) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE jobs SET last_seen_at = CURRENT_TIMESTAMP
+        WHERE source_id = ?
+            AND last_seen_at = (SELECT MAX(last_seen_at) FROM jobs WHERE source_id = ?)
+        "#,
+    )
+    .bind(source_id)
+    .bind(source_id)
+    .execute(db)
+    .await
+    .context("Could not mark jobs as seen")?;
+
+    return Ok(());
+}
+
+/// Closes jobs past when applications close, along with those their source
+/// stopped listing more than `stale_after_days` ago, and archives jobs closed
+/// more than `archive_after_days` ago
+#[tracing::instrument(skip(db))]
+pub async fn sweep(db: &DbPool, stale_after_days: i64, archive_after_days: i64) -> Result<()> {
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    let closed = sqlx::query(
+        r#"
+        UPDATE jobs SET status = 'closed', closed_at = CURRENT_TIMESTAMP
+        WHERE status = 'open' AND (
+            valid_through <= CURRENT_TIMESTAMP
+            -- Jobs added by hand have no source to stop listing them
+            OR (source_id IS NOT NULL AND last_seen_at < datetime('now', ?))
+        )
+        "#,
+    )
+    .bind(format!("-{stale_after_days} days"))
+    .execute(&mut *tx)
+    .await
+    .context("Could not close stale jobs")?
+    .rows_affected();
+
+    let archived = sqlx::query(
+        "UPDATE jobs SET status = 'archived' WHERE status = 'closed' AND closed_at < datetime('now', ?)",
+    )
+    .bind(format!("-{archive_after_days} days"))
+    .execute(&mut *tx)
+    .await
+    .context("Could not archive closed jobs")?
+    .rows_affected();
+
+    // Groups of duplicates are listed under their canonical job, so it has to
+    // be open for the group to be
+    let closed_groups: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT job_id FROM jobs
+        WHERE canonical_job_id IS NULL AND status != 'open' AND EXISTS (
+            SELECT 1 FROM jobs AS duplicate
+            WHERE duplicate.canonical_job_id = jobs.job_id AND duplicate.status = 'open'
+        )
+        "#,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Could not find closed duplicate groups")?;
+    for job_id in &closed_groups {
+        duplicates::hand_over(&mut tx, job_id).await?;
+    }
+
+    tx.commit().await.context("Could not sweep jobs")?;
+
+    if closed > 0 || archived > 0 {
+        info!(closed, archived, "swept jobs");
+    }
+    return Ok(());
+}
@@ -0,0 +1,318 @@
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use tracing::{info, warn};
+
+use super::DbPool;
+
+/// A numbered schema migration, loaded from `sql/{version}_{name}.{up,down}.sql`.
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+impl Migration {
+    fn checksum(&self) -> String {
+        return hex::encode(Sha256::digest(self.up.as_bytes()));
+    }
+}
+
+macro_rules! migration {
+    ($version:literal, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!("sql/", $version, "_", $name, ".up.sql")),
+            down: include_str!(concat!("sql/", $version, "_", $name, ".down.sql")),
+        }
+    };
+}
+
+/// Every migration known to this binary, in the order they must be applied.
+/// Migrations are forward-only once released: add a new one instead of editing.
+static MIGRATIONS: &[Migration] = &[
+    migration!(1, "setup"),
+    migration!(2, "job_search"),
+    migration!(3, "job_facets"),
+    migration!(4, "job_pagination"),
+    migration!(5, "structured_salary"),
+    migration!(6, "exchange_rates"),
+    migration!(7, "user_accounts"),
+    migration!(8, "saved_jobs"),
+    migration!(9, "application_pipeline"),
+    migration!(10, "job_notes"),
+    migration!(11, "calendar"),
+    migration!(12, "job_sources"),
+    migration!(13, "job_posting_details"),
+    migration!(14, "job_feeds"),
+    migration!(15, "import_schedule"),
+    migration!(16, "job_duplicates"),
+    migration!(17, "job_lifecycle"),
+    migration!(18, "company_profiles"),
+    migration!(19, "job_search_key"),
+    migration!(20, "user_job_groups"),
+];
+
+#[derive(FromRow)]
+struct AppliedMigration {
+    version: i64,
+    checksum: String,
+}
+
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: &'static str,
+    pub applied_at: Option<String>,
+}
+
+async fn ensure_migrations_table(db: &DbPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(db)
+    .await
+    .context("Could not create schema_migrations table")?;
+
+    return Ok(());
+}
+
+async fn applied_migrations(db: &DbPool) -> Result<Vec<AppliedMigration>> {
+    return sqlx::query_as::<_, AppliedMigration>(
+        "SELECT version, checksum FROM schema_migrations ORDER BY version",
+    )
+    .fetch_all(db)
+    .await
+    .context("Could not read schema_migrations");
+}
+
+/// Checks that every applied migration is still known to this binary and
+/// unchanged since it was applied.
+fn verify(applied: &[AppliedMigration]) -> Result<()> {
+    for a in applied {
+        let Some(m) = MIGRATIONS.iter().find(|m| return m.version == a.version) else {
+            bail!(
+                "database has migration {} applied which this build does not know about",
+                a.version
+            );
+        };
+
+        if m.checksum() != a.checksum {
+            bail!(
+                "migration {}_{} has been edited since it was applied (checksum mismatch)",
+                m.version,
+                m.name
+            );
+        }
+    }
+
+    return Ok(());
+}
+
+/// Applies every pending migration, each in its own transaction.
+pub async fn up(db: &DbPool) -> Result<()> {
+    ensure_migrations_table(db).await?;
+
+    let applied = applied_migrations(db).await?;
+    verify(&applied)?;
+
+    for m in MIGRATIONS
+        .iter()
+        .filter(|m| return !applied.iter().any(|a| return a.version == m.version))
+    {
+        let mut tx = db.begin().await?;
+
+        sqlx::query(m.up).execute(&mut *tx).await.with_context(|| {
+            return format!("Could not apply migration {}_{}", m.version, m.name);
+        })?;
+
+        sqlx::query("INSERT INTO schema_migrations (version, name, checksum) VALUES (?, ?, ?)")
+            .bind(m.version)
+            .bind(m.name)
+            .bind(m.checksum())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        info!(version = m.version, name = m.name, "applied migration");
+    }
+
+    return Ok(());
+}
+
+/// Reverts applied migrations, newest first, until the schema is at version `to`.
+pub async fn down(db: &DbPool, to: i64) -> Result<()> {
+    ensure_migrations_table(db).await?;
+
+    let applied = applied_migrations(db).await?;
+    verify(&applied)?;
+
+    for a in applied.iter().rev().filter(|a| return a.version > to) {
+        let m = MIGRATIONS
+            .iter()
+            .find(|m| return m.version == a.version)
+            .expect("applied migrations to have been verified");
+
+        let mut tx = db.begin().await?;
+
+        sqlx::query(m.down)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| {
+                return format!("Could not revert migration {}_{}", m.version, m.name);
+            })?;
+
+        sqlx::query("DELETE FROM schema_migrations WHERE version = ?")
+            .bind(m.version)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        warn!(version = m.version, name = m.name, "reverted migration");
+    }
+
+    return Ok(());
+}
+
+/// How many migrations known to this binary haven't been applied
+pub async fn pending(db: &DbPool) -> Result<usize> {
+    ensure_migrations_table(db).await?;
+
+    let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM schema_migrations")
+        .fetch_all(db)
+        .await
+        .context("Could not list applied migrations")?;
+
+    return Ok(MIGRATIONS
+        .iter()
+        .filter(|m| return !applied.contains(&m.version))
+        .count());
+}
+
+pub async fn status(db: &DbPool) -> Result<Vec<MigrationStatus>> {
+    ensure_migrations_table(db).await?;
+
+    let applied: Vec<(i64, String)> =
+        sqlx::query_as("SELECT version, applied_at FROM schema_migrations")
+            .fetch_all(db)
+            .await?;
+
+    return Ok(MIGRATIONS
+        .iter()
+        .map(|m| {
+            return MigrationStatus {
+                version: m.version,
+                name: m.name,
+                applied_at: applied
+                    .iter()
+                    .find(|(v, _)| return *v == m.version)
+                    .map(|(_, at)| return at.clone()),
+            };
+        })
+        .collect());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn applied(db: &DbPool) -> Vec<i64> {
+        return status(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|m| return m.applied_at.is_some())
+            .map(|m| return m.version)
+            .collect();
+    }
+
+    fn all() -> Vec<i64> {
+        return MIGRATIONS.iter().map(|m| return m.version).collect();
+    }
+
+    #[tokio::test]
+    async fn counts_pending_before_anything_ran() {
+        let db = test_pool().await;
+        assert_eq!(pending(&db).await.unwrap(), MIGRATIONS.len());
+    }
+
+    #[tokio::test]
+    async fn applies_every_migration_once() {
+        let db = test_pool().await;
+
+        up(&db).await.unwrap();
+        assert_eq!(applied(&db).await, all());
+        assert_eq!(pending(&db).await.unwrap(), 0);
+
+        // Nothing left to do the second time
+        up(&db).await.unwrap();
+        assert_eq!(applied(&db).await, all());
+    }
+
+    #[tokio::test]
+    async fn reverts_down_to_a_version_and_back() {
+        let db = test_pool().await;
+        up(&db).await.unwrap();
+
+        down(&db, 5).await.unwrap();
+        assert_eq!(applied(&db).await, [1, 2, 3, 4, 5]);
+        assert_eq!(pending(&db).await.unwrap(), MIGRATIONS.len() - 5);
+        let sessions: Option<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'sessions'",
+        )
+        .fetch_optional(&db)
+        .await
+        .unwrap();
+        assert_eq!(sessions, None);
+
+        up(&db).await.unwrap();
+        assert_eq!(applied(&db).await, all());
+
+        down(&db, 0).await.unwrap();
+        assert!(applied(&db).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn refuses_edited_migrations() {
+        let db = test_pool().await;
+        up(&db).await.unwrap();
+
+        // As if 3's script changed after it was applied
+        sqlx::query("UPDATE schema_migrations SET checksum = 'edited' WHERE version = 3")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let err = up(&db).await.unwrap_err().to_string();
+        assert!(err.contains("3_job_facets has been edited"), "{err}");
+        assert!(down(&db, 0).await.is_err());
+        assert_eq!(applied(&db).await, all());
+    }
+
+    #[tokio::test]
+    async fn refuses_unknown_migrations() {
+        let db = test_pool().await;
+        up(&db).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO schema_migrations (version, name, checksum) VALUES (999, 'future', '')",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let err = up(&db).await.unwrap_err().to_string();
+        assert!(err.contains("999"), "{err}");
+    }
+}
@@ -1,7 +1,24 @@
-use std::time::Duration;
+use std::{str::FromStr, time::Duration};
 
 use anyhow::{Context, Result};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite, SqlitePool};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Pool, Sqlite, SqlitePool,
+};
+
+pub mod calendar;
+pub mod companies;
+pub mod duplicates;
+pub mod jobs;
+pub mod lifecycle;
+pub mod migrate;
+pub mod notes;
+pub mod pipeline;
+pub mod rates;
+pub mod runs;
+pub mod shortlists;
+pub mod sources;
+pub mod users;
 
 pub type DB = Sqlite;
 
@@ -9,34 +26,57 @@ pub type DbPool = SqlitePool;
 
 use tracing::info;
 
-pub async fn init_dbpool() -> Result<DbPool> {
+use crate::config::DatabaseConfig;
+
+pub async fn init_dbpool(config: &DatabaseConfig) -> Result<DbPool> {
+    let options = SqliteConnectOptions::from_str(&config.url)
+        .context("Invalid database URL")?
+        .create_if_missing(true);
+
     return SqlitePoolOptions::new()
-        .max_connections(50)
-        .acquire_timeout(Duration::from_secs(3))
-        .connect("sqlite://sqlite.db")
+        .max_connections(config.max_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .connect_with(options)
         .await
         .context("Could not connect to database (with URL)");
 }
 
-pub async fn setup_database(db: &Pool<DB>) {
-    if cfg!(debug_assertions) {
-        sqlx::query(include_str!("sql/1_setup.down.sql"))
-            .execute(db)
-            .await
-            .unwrap();
-    }
+/// An empty in-memory database. It has one connection, as each would have its
+/// own database.
+#[cfg(test)]
+pub async fn test_pool() -> DbPool {
+    return SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("in-memory database to open");
+}
 
-    sqlx::query(include_str!("sql/1_setup.up.sql"))
+/// Whether the database answers, with as little work as it can
+pub async fn ping(db: &DbPool) -> Result<()> {
+    sqlx::query("SELECT 1")
         .execute(db)
         .await
-        .unwrap();
+        .context("Could not reach database")?;
+
+    return Ok(());
+}
 
-    if cfg!(debug_assertions) {
-        sqlx::query(include_str!("sql/2_test_data.up.sql"))
+pub async fn setup_database(db: &Pool<DB>, config: &DatabaseConfig) -> Result<()> {
+    migrate::up(db).await?;
+
+    if config.seed {
+        sqlx::query(include_str!("sql/seed.sql"))
             .execute(db)
             .await
-            .unwrap();
+            .context("Could not seed database")?;
     }
 
+    companies::backfill_slugs(db).await?;
+    jobs::backfill_salaries(db).await?;
+    duplicates::backfill(db).await?;
+
     info!("database sucessfully setup");
+
+    return Ok(());
 }
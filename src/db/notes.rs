@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use sqlx::QueryBuilder;
+
+use super::{DbPool, DB};
+
+/// Longest tag accepted, in characters
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// What a user wrote down about a job, only ever shown to them
+#[derive(Clone, Debug)]
+pub struct JobNotes {
+    pub job_id: String,
+    /// Markdown, empty when there is no note
+    pub note: String,
+    /// Alphabetical
+    pub tags: Vec<String>,
+}
+
+/// Turns free text into a tag: trimmed, lowercased and with runs of
+/// whitespace joined by a dash, or `None` if nothing is left of it
+pub fn normalize_tag(text: &str) -> Option<String> {
+    let tag = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+
+    if tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH || tag.chars().any(char::is_control) {
+        return None;
+    }
+
+    return Some(tag);
+}
+
+/// Whether `err` is because the job (or user) doesn't exist
+fn is_missing_job(err: &sqlx::Error) -> bool {
+    return matches!(err, sqlx::Error::Database(err) if err.is_foreign_key_violation());
+}
+
+/// The user's note and tags on a job
+#[tracing::instrument(skip(db))]
+pub async fn job_notes(db: &DbPool, user_id: &str, job_id: &str) -> Result<JobNotes> {
+    let note: Option<String> =
+        sqlx::query_scalar("SELECT body FROM job_notes WHERE user_id = ? AND job_id = ?")
+            .bind(user_id)
+            .bind(job_id)
+            .fetch_optional(db)
+            .await
+            .context("Could not get job note")?;
+
+    let tags: Vec<String> = sqlx::query_scalar(
+        "SELECT tag FROM job_tags WHERE user_id = ? AND job_id = ? ORDER BY tag",
+    )
+    .bind(user_id)
+    .bind(job_id)
+    .fetch_all(db)
+    .await
+    .context("Could not get job tags")?;
+
+    return Ok(JobNotes {
+        job_id: job_id.to_owned(),
+        note: note.unwrap_or_default(),
+        tags,
+    });
+}
+
+/// Replaces the user's note on a job, removing it when blank. Returns whether
+/// the job exists.
+#[tracing::instrument(skip(db, body))]
+pub async fn save_note(db: &DbPool, user_id: &str, job_id: &str, body: &str) -> Result<bool> {
+    let query = if body.trim().is_empty() {
+        sqlx::query("DELETE FROM job_notes WHERE user_id = ? AND job_id = ?")
+            .bind(user_id)
+            .bind(job_id)
+    } else {
+        sqlx::query(
+            r#"
+            INSERT INTO job_notes (user_id, job_id, body, updated_at)
+            VALUES (?, ?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id, job_id) DO UPDATE SET
+                body = excluded.body,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(job_id)
+        .bind(body)
+    };
+
+    return match query.execute(db).await {
+        Ok(_) => Ok(true),
+        Err(err) if is_missing_job(&err) => Ok(false),
+        Err(err) => Err(err).context("Could not save job note"),
+    };
+}
+
+/// Tags a job for the user, returning whether the job exists
+#[tracing::instrument(skip(db))]
+pub async fn add_tag(db: &DbPool, user_id: &str, job_id: &str, tag: &str) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO job_tags (user_id, job_id, tag) VALUES (?, ?, ?) ON CONFLICT DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(job_id)
+    .bind(tag)
+    .execute(db)
+    .await;
+
+    return match result {
+        Ok(_) => Ok(true),
+        Err(err) if is_missing_job(&err) => Ok(false),
+        Err(err) => Err(err).context("Could not tag job"),
+    };
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn remove_tag(db: &DbPool, user_id: &str, job_id: &str, tag: &str) -> Result<()> {
+    sqlx::query("DELETE FROM job_tags WHERE user_id = ? AND job_id = ? AND tag = ?")
+        .bind(user_id)
+        .bind(job_id)
+        .bind(tag)
+        .execute(db)
+        .await
+        .context("Could not untag job")?;
+
+    return Ok(());
+}
+
+/// Pushes a condition selecting the jobs the user tagged with `tag`
+pub(super) fn push_tagged(qb: &mut QueryBuilder<'_, DB>, user_id: &str, tag: &str) {
+    qb.push(" EXISTS (SELECT 1 FROM job_tags WHERE job_tags.job_id = jobs.job_id AND job_tags.user_id = ")
+        .push_bind(user_id.to_owned())
+        .push(" AND job_tags.tag = ")
+        .push_bind(tag.to_owned())
+        .push(")");
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, QueryBuilder, SqliteConnection};
+
+use super::{
+    jobs::{push_job_details, push_job_details_from, JobDetails, Viewer},
+    DbPool, DB,
+};
+
+/// Where a job is in a user's application pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Stage {
+    Saved,
+    Applied,
+    PhoneScreen,
+    Onsite,
+    Offer,
+    Rejected,
+    Withdrawn,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 7] = [
+        Stage::Saved,
+        Stage::Applied,
+        Stage::PhoneScreen,
+        Stage::Onsite,
+        Stage::Offer,
+        Stage::Rejected,
+        Stage::Withdrawn,
+    ];
+
+    /// Name of the stage in forms
+    pub fn slug(self) -> &'static str {
+        return match self {
+            Stage::Saved => "saved",
+            Stage::Applied => "applied",
+            Stage::PhoneScreen => "phone_screen",
+            Stage::Onsite => "onsite",
+            Stage::Offer => "offer",
+            Stage::Rejected => "rejected",
+            Stage::Withdrawn => "withdrawn",
+        };
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            Stage::Saved => "Saved",
+            Stage::Applied => "Applied",
+            Stage::PhoneScreen => "Phone screen",
+            Stage::Onsite => "Onsite",
+            Stage::Offer => "Offer",
+            Stage::Rejected => "Rejected",
+            Stage::Withdrawn => "Withdrawn",
+        });
+    }
+}
+
+/// A job moving between stages, `None` being outside of the pipeline
+#[derive(Clone, Debug, FromRow)]
+pub struct StageChange {
+    pub job_id: String,
+    pub from_stage: Option<Stage>,
+    pub to_stage: Option<Stage>,
+    pub changed_at: String,
+}
+
+pub(super) async fn job_exists(conn: &mut SqliteConnection, job_id: &str) -> Result<bool> {
+    return sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM jobs WHERE job_id = ?)")
+        .bind(job_id)
+        .fetch_one(conn)
+        .await
+        .context("Could not check job exists");
+}
+
+pub(super) async fn current_stage(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    job_id: &str,
+) -> Result<Option<Stage>> {
+    return sqlx::query_scalar("SELECT stage FROM applications WHERE user_id = ? AND job_id = ?")
+        .bind(user_id)
+        .bind(job_id)
+        .fetch_optional(conn)
+        .await
+        .context("Could not get application stage");
+}
+
+/// Moves a job from stage `from` to `to`, recording the change in its history
+pub(super) async fn set_stage(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    job_id: &str,
+    from: Option<Stage>,
+    to: Option<Stage>,
+) -> Result<()> {
+    if from == to {
+        return Ok(());
+    }
+
+    match to {
+        Some(stage) => sqlx::query(
+            r#"
+            INSERT INTO applications (user_id, job_id, stage, updated_at)
+            VALUES (?, ?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id, job_id) DO UPDATE SET
+                stage = excluded.stage,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(job_id)
+        .bind(stage),
+        None => sqlx::query("DELETE FROM applications WHERE user_id = ? AND job_id = ?")
+            .bind(user_id)
+            .bind(job_id),
+    }
+    .execute(&mut *conn)
+    .await
+    .context("Could not update application stage")?;
+
+    sqlx::query(
+        "INSERT INTO application_stages (user_id, job_id, from_stage, to_stage) VALUES (?, ?, ?, ?)",
+    )
+    .bind(user_id)
+    .bind(job_id)
+    .bind(from)
+    .bind(to)
+    .execute(&mut *conn)
+    .await
+    .context("Could not record application stage change")?;
+
+    return Ok(());
+}
+
+/// Moves a job to `stage` in the user's pipeline, returning whether the job exists
+#[tracing::instrument(skip(db))]
+pub async fn move_job(db: &DbPool, user_id: &str, job_id: &str, stage: Stage) -> Result<bool> {
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    if !job_exists(&mut tx, job_id).await? {
+        return Ok(false);
+    }
+
+    let from = current_stage(&mut tx, user_id, job_id).await?;
+    set_stage(&mut tx, user_id, job_id, from, Some(stage)).await?;
+
+    tx.commit().await.context("Could not move job")?;
+
+    return Ok(true);
+}
+
+/// Every job in the viewer's pipeline, most recently moved first
+#[tracing::instrument(skip(db))]
+pub async fn pipeline_jobs(db: &DbPool, viewer: &Viewer) -> Result<Vec<JobDetails>> {
+    if viewer.user_id.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let mut qb = QueryBuilder::<DB>::new("SELECT ");
+    push_job_details(&mut qb, viewer);
+    push_job_details_from(&mut qb, viewer);
+    qb.push(" WHERE applications.job_id IS NOT NULL ORDER BY applications.updated_at DESC");
+
+    return qb
+        .build_query_as::<JobDetails>()
+        .fetch_all(db)
+        .await
+        .context("Could not list pipeline jobs");
+}
+
+/// How many jobs are in each stage, across every user's pipeline
+#[tracing::instrument(skip(db))]
+pub async fn count_by_stage(db: &DbPool) -> Result<Vec<(Stage, i64)>> {
+    return sqlx::query_as("SELECT stage, COUNT(*) FROM applications GROUP BY stage")
+        .fetch_all(db)
+        .await
+        .context("Could not count applications");
+}
+
+/// Every stage change in the user's pipeline, oldest first
+#[tracing::instrument(skip(db))]
+pub async fn stage_history(db: &DbPool, user_id: &str) -> Result<Vec<StageChange>> {
+    return sqlx::query_as::<_, StageChange>(
+        r#"
+        SELECT job_id, from_stage, to_stage, changed_at FROM application_stages
+        WHERE user_id = ? ORDER BY changed_at, rowid
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+    .context("Could not get application stage history");
+}
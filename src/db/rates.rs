@@ -0,0 +1,35 @@
+use anyhow::{Context, Result};
+
+use super::DbPool;
+use crate::currency::ExchangeRate;
+
+/// Replaces the stored exchange rates with `rates`
+#[tracing::instrument(skip_all, fields(rates = rates.len()))]
+pub async fn replace_rates(db: &DbPool, rates: &[ExchangeRate]) -> Result<()> {
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    sqlx::query("DELETE FROM exchange_rates")
+        .execute(&mut *tx)
+        .await
+        .context("Could not clear exchange rates")?;
+
+    for rate in rates {
+        sqlx::query("INSERT INTO exchange_rates (currency, per_usd) VALUES (?, ?)")
+            .bind(&rate.currency)
+            .bind(rate.per_usd)
+            .execute(&mut *tx)
+            .await
+            .context("Could not store exchange rate")?;
+    }
+
+    return tx.commit().await.context("Could not store exchange rates");
+}
+
+/// Every currency salaries can be converted into
+#[tracing::instrument(skip(db))]
+pub async fn currencies(db: &DbPool) -> Result<Vec<String>> {
+    return sqlx::query_scalar("SELECT currency FROM exchange_rates ORDER BY currency")
+        .fetch_all(db)
+        .await
+        .context("Could not list currencies");
+}
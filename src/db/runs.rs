@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use sqlx::FromRow;
+
+use super::{sources::SourceKind, DbPool};
+
+/// One import of a source, finished or not
+#[derive(Clone, Debug, FromRow)]
+pub struct ImportRun {
+    pub kind: SourceKind,
+    pub board: String,
+    pub started_at: String,
+    /// Unset while running
+    pub finished_at: Option<String>,
+    pub jobs_imported: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// Records an import of the source starting, returning the run's id
+#[tracing::instrument(skip(db))]
+pub async fn start_run(db: &DbPool, source_id: i64) -> Result<i64> {
+    let (id,): (i64,) =
+        sqlx::query_as("INSERT INTO import_runs (source_id) VALUES (?) RETURNING run_id")
+            .bind(source_id)
+            .fetch_all(db)
+            .await
+            .context("Could not start import run")?
+            .pop()
+            .context("Could not start import run")?;
+
+    return Ok(id);
+}
+
+/// Records how an import went: how many jobs it imported, or why it failed
+#[tracing::instrument(skip(db, outcome))]
+pub async fn finish_run(db: &DbPool, id: i64, outcome: &Result<usize>) -> Result<()> {
+    let (jobs_imported, error) = match outcome {
+        Ok(count) => (Some(*count as i64), None),
+        Err(err) => (None, Some(format!("{err:#}"))),
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE import_runs SET finished_at = CURRENT_TIMESTAMP, jobs_imported = ?, error = ?
+        WHERE run_id = ?
+        "#,
+    )
+    .bind(jobs_imported)
+    .bind(error)
+    .bind(id)
+    .execute(db)
+    .await
+    .context("Could not finish import run")?;
+
+    return Ok(());
+}
+
+/// The latest imports across all sources, newest first
+#[tracing::instrument(skip(db))]
+pub async fn recent_runs(db: &DbPool, limit: i64) -> Result<Vec<ImportRun>> {
+    return sqlx::query_as::<_, ImportRun>(
+        r#"
+        SELECT job_sources.kind, job_sources.board, import_runs.started_at, import_runs.finished_at, import_runs.jobs_imported,
+            import_runs.error
+        FROM import_runs INNER JOIN job_sources ON job_sources.source_id = import_runs.source_id
+        ORDER BY import_runs.run_id DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await
+    .context("Could not list import runs");
+}
+
+/// Marks runs left unfinished by a previous process as failed
+#[tracing::instrument(skip(db))]
+pub async fn abandon_unfinished(db: &DbPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE import_runs SET finished_at = CURRENT_TIMESTAMP, error = 'Interrupted'
+        WHERE finished_at IS NULL
+        "#,
+    )
+    .execute(db)
+    .await
+    .context("Could not abandon import runs")?;
+
+    return Ok(());
+}
@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    pipeline::{self, Stage},
+    DbPool,
+};
+
+/// A per-user list of jobs, backed by the application pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shortlist {
+    /// Jobs in the first stage
+    Saved,
+    /// Jobs past the first stage
+    Applied,
+}
+
+impl Shortlist {
+    /// Name of the list in URLs
+    pub fn slug(self) -> &'static str {
+        return match self {
+            Shortlist::Saved => "saved",
+            Shortlist::Applied => "applied",
+        };
+    }
+
+    /// SQL condition on the joined `applications` selecting jobs on the list
+    pub(super) fn condition(self) -> &'static str {
+        return match self {
+            Shortlist::Saved => "applications.stage = 'saved'",
+            Shortlist::Applied => "applications.stage != 'saved'",
+        };
+    }
+}
+
+/// Adds a job to one of the user's lists, returning whether the job exists.
+/// Jobs further along the pipeline stay where they are.
+#[tracing::instrument(skip(db))]
+pub async fn add(db: &DbPool, list: Shortlist, user_id: &str, job_id: &str) -> Result<bool> {
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    if !pipeline::job_exists(&mut tx, job_id).await? {
+        return Ok(false);
+    }
+
+    let from = pipeline::current_stage(&mut tx, user_id, job_id).await?;
+    let to = match (list, from) {
+        (Shortlist::Saved, None) => Some(Stage::Saved),
+        (Shortlist::Applied, None | Some(Stage::Saved)) => Some(Stage::Applied),
+        (_, stage) => stage,
+    };
+    pipeline::set_stage(&mut tx, user_id, job_id, from, to).await?;
+
+    tx.commit().await.context("Could not add job to list")?;
+
+    return Ok(true);
+}
+
+/// Takes a job off one of the user's lists, and so out of their pipeline
+#[tracing::instrument(skip(db))]
+pub async fn remove(db: &DbPool, list: Shortlist, user_id: &str, job_id: &str) -> Result<()> {
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    let from = pipeline::current_stage(&mut tx, user_id, job_id).await?;
+    let to = match (list, from) {
+        (Shortlist::Saved, Some(Stage::Saved)) => None,
+        (Shortlist::Applied, Some(stage)) if stage != Stage::Saved => None,
+        (_, stage) => stage,
+    };
+    pipeline::set_stage(&mut tx, user_id, job_id, from, to).await?;
+
+    return tx.commit().await.context("Could not remove job from list");
+}
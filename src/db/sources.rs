@@ -0,0 +1,254 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::DbPool;
+
+/// The kind of job board a source imports from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SourceKind {
+    Greenhouse,
+    Lever,
+    /// RSS, Atom or JSON Feed
+    Feed,
+}
+
+impl SourceKind {
+    pub fn slug(self) -> &'static str {
+        return match self {
+            SourceKind::Greenhouse => "greenhouse",
+            SourceKind::Lever => "lever",
+            SourceKind::Feed => "feed",
+        };
+    }
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        return match slug {
+            "greenhouse" => Some(SourceKind::Greenhouse),
+            "lever" => Some(SourceKind::Lever),
+            "feed" => Some(SourceKind::Feed),
+            _ => None,
+        };
+    }
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            SourceKind::Greenhouse => "Greenhouse",
+            SourceKind::Lever => "Lever",
+            SourceKind::Feed => "Feed",
+        });
+    }
+}
+
+/// A job board jobs are imported from
+#[derive(Clone, Debug, FromRow)]
+pub struct Source {
+    #[sqlx(rename = "source_id")]
+    pub id: i64,
+    pub kind: SourceKind,
+    /// Greenhouse board token, Lever site name, feed URL, ...
+    pub board: String,
+    /// Name imported jobs are filed under
+    pub company: String,
+    /// Overrides the public API, e.g. to point at a stub server
+    pub api_url: Option<String>,
+    pub enabled: bool,
+    /// JSON naming the feed item fields jobs are read from
+    pub field_mapping: Option<String>,
+    /// How often the source is imported in the background
+    pub interval_minutes: i64,
+    /// Unset until the first run
+    pub next_run_at: Option<String>,
+    /// Runs failed in a row
+    pub failures: i64,
+}
+
+const SOURCE_COLUMNS: &str = "source_id, kind, board, company, api_url, enabled, field_mapping, \
+                              interval_minutes, next_run_at, failures";
+
+/// Validators from the last response for a source, to make the next request
+/// conditional on it having changed
+#[derive(Clone, Debug, Default, FromRow)]
+pub struct FetchState {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// Adds a job board to import from, or `None` if it already was
+#[tracing::instrument(skip(db))]
+pub async fn add_source(
+    db: &DbPool,
+    kind: SourceKind,
+    board: &str,
+    company: &str,
+    api_url: Option<&str>,
+    field_mapping: Option<&str>,
+) -> Result<Option<Source>> {
+    let source = sqlx::query_as::<_, Source>(&format!(
+        r#"
+        INSERT INTO job_sources (kind, board, company, api_url, field_mapping) VALUES (?, ?, ?, ?, ?)
+        RETURNING {SOURCE_COLUMNS}
+        "#
+    ))
+    .bind(kind)
+    .bind(board)
+    .bind(company)
+    .bind(api_url)
+    .bind(field_mapping)
+    // Stepped to the end, as SQLite only commits a `RETURNING` statement once it's done
+    .fetch_all(db)
+    .await;
+
+    return match source {
+        Ok(mut sources) => Ok(sources.pop()),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(None),
+        Err(err) => Err(err).context("Could not add job source"),
+    };
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn list_sources(db: &DbPool) -> Result<Vec<Source>> {
+    return sqlx::query_as::<_, Source>(&format!(
+        "SELECT {SOURCE_COLUMNS} FROM job_sources ORDER BY source_id"
+    ))
+    .fetch_all(db)
+    .await
+    .context("Could not list job sources");
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn get_source(db: &DbPool, id: i64) -> Result<Option<Source>> {
+    return sqlx::query_as::<_, Source>(&format!(
+        "SELECT {SOURCE_COLUMNS} FROM job_sources WHERE source_id = ?"
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .context("Could not get job source");
+}
+
+/// Turns a source on or off, returning whether it exists
+#[tracing::instrument(skip(db))]
+pub async fn set_enabled(db: &DbPool, id: i64, enabled: bool) -> Result<bool> {
+    let result = sqlx::query("UPDATE job_sources SET enabled = ? WHERE source_id = ?")
+        .bind(enabled)
+        .bind(id)
+        .execute(db)
+        .await
+        .context("Could not update job source")?;
+
+    return Ok(result.rows_affected() > 0);
+}
+
+/// Replaces a source's field mapping, returning whether it exists
+#[tracing::instrument(skip(db))]
+pub async fn set_field_mapping(db: &DbPool, id: i64, field_mapping: Option<&str>) -> Result<bool> {
+    let result = sqlx::query("UPDATE job_sources SET field_mapping = ? WHERE source_id = ?")
+        .bind(field_mapping)
+        .bind(id)
+        .execute(db)
+        .await
+        .context("Could not update job source")?;
+
+    return Ok(result.rows_affected() > 0);
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn fetch_state(db: &DbPool, id: i64) -> Result<FetchState> {
+    let state = sqlx::query_as::<_, FetchState>(
+        "SELECT etag, last_modified FROM source_fetch_state WHERE source_id = ?",
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .context("Could not get source fetch state")?;
+
+    return Ok(state.unwrap_or_default());
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn save_fetch_state(db: &DbPool, id: i64, state: &FetchState) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO source_fetch_state (source_id, etag, last_modified) VALUES (?, ?, ?)
+        ON CONFLICT (source_id) DO UPDATE SET
+            etag = excluded.etag,
+            last_modified = excluded.last_modified,
+            fetched_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(id)
+    .bind(&state.etag)
+    .bind(&state.last_modified)
+    .execute(db)
+    .await
+    .context("Could not save source fetch state")?;
+
+    return Ok(());
+}
+
+/// Sets how often a source is imported, returning whether it exists
+#[tracing::instrument(skip(db))]
+pub async fn set_interval(db: &DbPool, id: i64, minutes: i64) -> Result<bool> {
+    let result = sqlx::query("UPDATE job_sources SET interval_minutes = ? WHERE source_id = ?")
+        .bind(minutes)
+        .bind(id)
+        .execute(db)
+        .await
+        .context("Could not update job source")?;
+
+    return Ok(result.rows_affected() > 0);
+}
+
+/// Enabled sources due to be imported
+#[tracing::instrument(skip(db))]
+pub async fn due_sources(db: &DbPool) -> Result<Vec<Source>> {
+    return sqlx::query_as::<_, Source>(&format!(
+        r#"
+        SELECT {SOURCE_COLUMNS} FROM job_sources
+        WHERE enabled AND (next_run_at IS NULL OR next_run_at <= CURRENT_TIMESTAMP)
+        ORDER BY next_run_at NULLS FIRST, source_id
+        "#
+    ))
+    .fetch_all(db)
+    .await
+    .context("Could not list due job sources");
+}
+
+/// Schedules a source's next import `delay_seconds` from now, recording how
+/// many runs have failed in a row
+#[tracing::instrument(skip(db))]
+pub async fn schedule(db: &DbPool, id: i64, delay_seconds: i64, failures: i64) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE job_sources SET next_run_at = datetime('now', ?), failures = ?
+        WHERE source_id = ?
+        "#,
+    )
+    .bind(format!("+{delay_seconds} seconds"))
+    .bind(failures)
+    .bind(id)
+    .execute(db)
+    .await
+    .context("Could not schedule job source")?;
+
+    return Ok(());
+}
+
+/// Makes a source due right away, returning whether it exists
+#[tracing::instrument(skip(db))]
+pub async fn run_now(db: &DbPool, id: i64) -> Result<bool> {
+    let result = sqlx::query("UPDATE job_sources SET next_run_at = NULL WHERE source_id = ?")
+        .bind(id)
+        .execute(db)
+        .await
+        .context("Could not schedule job source")?;
+
+    return Ok(result.rows_affected() > 0);
+}
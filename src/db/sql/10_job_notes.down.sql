@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS job_tags_user_id_tag_idx;
+DROP TABLE IF EXISTS job_tags;
+DROP TABLE IF EXISTS job_notes;
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS job_notes (
+    user_id TEXT NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+    -- Markdown, only ever shown to its author
+    body TEXT NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, job_id)
+);
+
+CREATE TABLE IF NOT EXISTS job_tags (
+    user_id TEXT NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, job_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS job_tags_user_id_tag_idx ON job_tags (user_id, tag);
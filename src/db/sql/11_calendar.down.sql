@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS calendar_feeds;
+DROP INDEX IF EXISTS job_events_user_id_day_idx;
+DROP TABLE IF EXISTS job_events;
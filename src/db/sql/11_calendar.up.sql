@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS job_events (
+    event_id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('interview', 'take_home', 'application_close')),
+    -- Local to whoever entered it, as we don't know their time zone
+    day TEXT NOT NULL,
+    -- NULL for all day events
+    start_time TEXT,
+    duration_minutes INTEGER CHECK (duration_minutes > 0),
+    notes TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS job_events_user_id_day_idx ON job_events (user_id, day, start_time);
+
+CREATE TABLE IF NOT EXISTS calendar_feeds (
+    user_id TEXT PRIMARY KEY NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    -- SHA-256 of the token in the feed URL
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
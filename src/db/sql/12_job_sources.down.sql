@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS jobs_source_id_idx;
+
+ALTER TABLE jobs DROP COLUMN url;
+ALTER TABLE jobs DROP COLUMN source_id;
+
+DROP TABLE IF EXISTS job_sources;
@@ -0,0 +1,21 @@
+-- Where jobs are imported from, e.g. a company's Greenhouse board. `kind` is
+-- checked by the application so new kinds don't need the table rebuilt.
+CREATE TABLE IF NOT EXISTS job_sources (
+    source_id INTEGER PRIMARY KEY,
+    kind TEXT NOT NULL,
+    -- Greenhouse board token, Lever site name, ...
+    board TEXT NOT NULL,
+    -- Name imported jobs are filed under
+    company TEXT NOT NULL,
+    -- Overrides the public API, e.g. to point at a stub server
+    api_url TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (kind, board)
+);
+
+ALTER TABLE jobs ADD COLUMN source_id INTEGER REFERENCES job_sources (source_id) ON DELETE SET NULL;
+-- The original posting
+ALTER TABLE jobs ADD COLUMN url TEXT;
+
+CREATE INDEX IF NOT EXISTS jobs_source_id_idx ON jobs (source_id);
@@ -0,0 +1,2 @@
+ALTER TABLE jobs DROP COLUMN valid_through;
+ALTER TABLE jobs DROP COLUMN employment_type;
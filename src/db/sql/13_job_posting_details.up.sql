@@ -0,0 +1,3 @@
+ALTER TABLE jobs ADD COLUMN employment_type TEXT CHECK (employment_type IN ('full_time', 'part_time', 'contractor', 'temporary', 'intern', 'volunteer', 'per_diem', 'other'));
+-- When applications close, in UTC
+ALTER TABLE jobs ADD COLUMN valid_through TIMESTAMP;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS source_fetch_state;
+
+ALTER TABLE job_sources DROP COLUMN field_mapping;
@@ -0,0 +1,12 @@
+-- Feed sources keep the feed's URL in `board`. The mapping is JSON naming
+-- the item fields jobs are read from, when a feed doesn't use the standard ones.
+ALTER TABLE job_sources ADD COLUMN field_mapping TEXT;
+
+-- Validators from the last time a source was fetched, so unchanged feeds
+-- aren't downloaded and imported again
+CREATE TABLE IF NOT EXISTS source_fetch_state (
+    source_id INTEGER PRIMARY KEY REFERENCES job_sources (source_id) ON DELETE CASCADE,
+    etag TEXT,
+    last_modified TEXT,
+    fetched_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
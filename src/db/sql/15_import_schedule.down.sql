@@ -0,0 +1,9 @@
+ALTER TABLE users DROP COLUMN is_admin;
+
+DROP INDEX IF EXISTS import_runs_source_id_idx;
+DROP INDEX IF EXISTS import_runs_started_at_idx;
+DROP TABLE IF EXISTS import_runs;
+
+ALTER TABLE job_sources DROP COLUMN failures;
+ALTER TABLE job_sources DROP COLUMN next_run_at;
+ALTER TABLE job_sources DROP COLUMN interval_minutes;
@@ -0,0 +1,22 @@
+-- How often each source is imported in the background, and when it's next due
+ALTER TABLE job_sources ADD COLUMN interval_minutes INTEGER NOT NULL DEFAULT 360 CHECK (interval_minutes > 0);
+-- Unset until the first run, making the source due right away
+ALTER TABLE job_sources ADD COLUMN next_run_at TIMESTAMP;
+-- Runs failed in a row, to back off from sources which keep failing
+ALTER TABLE job_sources ADD COLUMN failures INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS import_runs (
+    run_id INTEGER PRIMARY KEY,
+    source_id INTEGER NOT NULL REFERENCES job_sources (source_id) ON DELETE CASCADE,
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- Unset while running
+    finished_at TIMESTAMP,
+    jobs_imported INTEGER,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS import_runs_started_at_idx ON import_runs (started_at);
+CREATE INDEX IF NOT EXISTS import_runs_source_id_idx ON import_runs (source_id);
+
+-- Admins can see how imports are going
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,10 @@
+DROP INDEX IF EXISTS job_minhash_bands_job_id_idx;
+DROP TABLE IF EXISTS job_minhash_bands;
+
+DROP INDEX IF EXISTS jobs_canonical_job_id_idx;
+DROP INDEX IF EXISTS jobs_fingerprint_idx;
+
+ALTER TABLE jobs DROP COLUMN duplicate_locked;
+ALTER TABLE jobs DROP COLUMN canonical_job_id;
+ALTER TABLE jobs DROP COLUMN minhash;
+ALTER TABLE jobs DROP COLUMN fingerprint;
@@ -0,0 +1,23 @@
+-- Normalized company, title and location, identical for copies of a posting
+ALTER TABLE jobs ADD COLUMN fingerprint TEXT;
+-- MinHash signature of the description, for finding near copies
+ALTER TABLE jobs ADD COLUMN minhash BLOB;
+-- The job this one duplicates, unset for jobs which aren't a duplicate.
+-- Always points at a job which isn't a duplicate itself.
+ALTER TABLE jobs ADD COLUMN canonical_job_id TEXT REFERENCES jobs (job_id) ON DELETE SET NULL;
+-- Set when someone merged or split the job by hand, so it isn't regrouped
+ALTER TABLE jobs ADD COLUMN duplicate_locked BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS jobs_fingerprint_idx ON jobs (fingerprint);
+CREATE INDEX IF NOT EXISTS jobs_canonical_job_id_idx ON jobs (canonical_job_id);
+
+-- Locality-sensitive hashes of each band of a job's signature. Jobs sharing
+-- a band are candidate duplicates.
+CREATE TABLE IF NOT EXISTS job_minhash_bands (
+    band INTEGER NOT NULL,
+    hash INTEGER NOT NULL,
+    job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+    PRIMARY KEY (band, hash, job_id)
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS job_minhash_bands_job_id_idx ON job_minhash_bands (job_id);
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS jobs_status_idx;
+
+ALTER TABLE jobs DROP COLUMN closed_at;
+ALTER TABLE jobs DROP COLUMN status;
+ALTER TABLE jobs DROP COLUMN last_seen_at;
+ALTER TABLE jobs DROP COLUMN posted_at;
@@ -0,0 +1,14 @@
+-- When the job was posted, as its source has it
+ALTER TABLE jobs ADD COLUMN posted_at TIMESTAMP;
+-- When the job's source last listed it
+ALTER TABLE jobs ADD COLUMN last_seen_at TIMESTAMP;
+-- Closed jobs are no longer taking applications, and archived ones have
+-- been closed for long enough that only those keeping track of them care
+ALTER TABLE jobs ADD COLUMN status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed', 'archived'));
+ALTER TABLE jobs ADD COLUMN closed_at TIMESTAMP;
+
+-- Jobs were created as of when they were posted, and every source has just
+-- listed its jobs as far as anyone knows
+UPDATE jobs SET posted_at = created_at, last_seen_at = CURRENT_TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS jobs_status_idx ON jobs (status);
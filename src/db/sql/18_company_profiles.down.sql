@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS companies_slug_idx;
+
+ALTER TABLE companies DROP COLUMN logo_url;
+ALTER TABLE companies DROP COLUMN careers_url;
+ALTER TABLE companies DROP COLUMN headquarters;
+ALTER TABLE companies DROP COLUMN size;
+ALTER TABLE companies DROP COLUMN description;
+ALTER TABLE companies DROP COLUMN domain;
+ALTER TABLE companies DROP COLUMN slug;
@@ -0,0 +1,13 @@
+-- Where the company's page lives, e.g. /companies/acme. Set for existing
+-- companies once migrated, since slugs are made in Rust.
+ALTER TABLE companies ADD COLUMN slug TEXT;
+-- e.g. acme.com, which logos are looked up by when there's no logo_url
+ALTER TABLE companies ADD COLUMN domain TEXT;
+ALTER TABLE companies ADD COLUMN description TEXT NOT NULL DEFAULT '';
+-- Free text, e.g. 51-200 employees
+ALTER TABLE companies ADD COLUMN size TEXT;
+ALTER TABLE companies ADD COLUMN headquarters TEXT;
+ALTER TABLE companies ADD COLUMN careers_url TEXT;
+ALTER TABLE companies ADD COLUMN logo_url TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS companies_slug_idx ON companies (slug);
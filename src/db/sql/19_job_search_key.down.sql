@@ -0,0 +1,45 @@
+DROP TRIGGER IF EXISTS companies_fts_update;
+DROP TRIGGER IF EXISTS jobs_fts_delete;
+DROP TRIGGER IF EXISTS jobs_fts_update;
+DROP TRIGGER IF EXISTS jobs_fts_insert;
+DROP TABLE IF EXISTS jobs_fts;
+DROP INDEX IF EXISTS jobs_search_rowid_idx;
+ALTER TABLE jobs DROP COLUMN search_rowid;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS jobs_fts USING fts5 (
+    job_id UNINDEXED,
+    title,
+    company,
+    location,
+    description,
+    tokenize = 'porter unicode61'
+);
+
+INSERT INTO jobs_fts (job_id, title, company, location, description)
+SELECT jobs.job_id, jobs.title, companies.name, jobs.location, jobs.description
+FROM jobs
+INNER JOIN companies ON companies.company_id = jobs.company_id;
+
+CREATE TRIGGER IF NOT EXISTS jobs_fts_insert AFTER INSERT ON jobs BEGIN
+    INSERT INTO jobs_fts (job_id, title, company, location, description)
+    SELECT NEW.job_id, NEW.title, companies.name, NEW.location, NEW.description
+    FROM companies
+    WHERE companies.company_id = NEW.company_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS jobs_fts_update AFTER UPDATE ON jobs BEGIN
+    DELETE FROM jobs_fts WHERE job_id = OLD.job_id;
+    INSERT INTO jobs_fts (job_id, title, company, location, description)
+    SELECT NEW.job_id, NEW.title, companies.name, NEW.location, NEW.description
+    FROM companies
+    WHERE companies.company_id = NEW.company_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS jobs_fts_delete AFTER DELETE ON jobs BEGIN
+    DELETE FROM jobs_fts WHERE job_id = OLD.job_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS companies_fts_update AFTER UPDATE OF name ON companies BEGIN
+    UPDATE jobs_fts SET company = NEW.name
+    WHERE job_id IN (SELECT job_id FROM jobs WHERE company_id = NEW.company_id);
+END;
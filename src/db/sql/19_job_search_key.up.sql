@@ -0,0 +1,60 @@
+-- Search rows are keyed by a stable integer so keeping them in sync is a
+-- lookup rather than a scan over the unindexed job_id. jobs' own rowid won't
+-- do, as VACUUM may renumber it with the primary key being TEXT.
+ALTER TABLE jobs ADD COLUMN search_rowid INTEGER;
+UPDATE jobs SET search_rowid = rowid;
+CREATE UNIQUE INDEX IF NOT EXISTS jobs_search_rowid_idx ON jobs (search_rowid);
+
+DROP TRIGGER IF EXISTS companies_fts_update;
+DROP TRIGGER IF EXISTS jobs_fts_delete;
+DROP TRIGGER IF EXISTS jobs_fts_update;
+DROP TRIGGER IF EXISTS jobs_fts_insert;
+DROP TABLE IF EXISTS jobs_fts;
+
+CREATE VIRTUAL TABLE jobs_fts USING fts5 (
+    job_id UNINDEXED,
+    title,
+    company,
+    location,
+    description,
+    tokenize = 'porter unicode61'
+);
+
+INSERT INTO jobs_fts (rowid, job_id, title, company, location, description)
+SELECT jobs.search_rowid, jobs.job_id, jobs.title, companies.name, jobs.location, jobs.description
+FROM jobs
+INNER JOIN companies ON companies.company_id = jobs.company_id;
+
+CREATE TRIGGER jobs_fts_insert AFTER INSERT ON jobs BEGIN
+    UPDATE jobs SET search_rowid = (SELECT COALESCE(MAX(search_rowid), 0) + 1 FROM jobs)
+    WHERE job_id = NEW.job_id;
+    INSERT INTO jobs_fts (rowid, job_id, title, company, location, description)
+    SELECT jobs.search_rowid, jobs.job_id, jobs.title, companies.name, jobs.location, jobs.description
+    FROM jobs
+    INNER JOIN companies ON companies.company_id = jobs.company_id
+    WHERE jobs.job_id = NEW.job_id;
+END;
+
+-- Only for the indexed columns, so status changes and last-seen bumps
+-- don't rewrite the index
+CREATE TRIGGER jobs_fts_update AFTER UPDATE OF title, location, description, company_id ON jobs
+WHEN OLD.title IS NOT NEW.title
+    OR OLD.location IS NOT NEW.location
+    OR OLD.description IS NOT NEW.description
+    OR OLD.company_id IS NOT NEW.company_id
+BEGIN
+    DELETE FROM jobs_fts WHERE rowid = OLD.search_rowid;
+    INSERT INTO jobs_fts (rowid, job_id, title, company, location, description)
+    SELECT NEW.search_rowid, NEW.job_id, NEW.title, companies.name, NEW.location, NEW.description
+    FROM companies
+    WHERE companies.company_id = NEW.company_id;
+END;
+
+CREATE TRIGGER jobs_fts_delete AFTER DELETE ON jobs BEGIN
+    DELETE FROM jobs_fts WHERE rowid = OLD.search_rowid;
+END;
+
+CREATE TRIGGER companies_fts_update AFTER UPDATE OF name ON companies BEGIN
+    UPDATE jobs_fts SET company = NEW.name
+    WHERE rowid IN (SELECT search_rowid FROM jobs WHERE company_id = NEW.company_id);
+END;
@@ -1 +1,3 @@
+DROP TABLE IF EXISTS jobs;
+DROP TABLE IF EXISTS companies;
 DROP TABLE IF EXISTS users;
@@ -0,0 +1,4 @@
+ALTER TABLE jobs ADD COLUMN duplicate_locked BOOLEAN NOT NULL DEFAULT FALSE;
+
+DROP INDEX IF EXISTS user_job_groups_user_id_canonical_job_id_idx;
+DROP TABLE IF EXISTS user_job_groups;
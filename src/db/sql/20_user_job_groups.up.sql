@@ -0,0 +1,14 @@
+-- Duplicates a user regrouped by hand, in place of the groups detected for
+-- everyone. A NULL canonical_job_id lists the job on its own for them.
+CREATE TABLE IF NOT EXISTS user_job_groups (
+    user_id TEXT NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+    canonical_job_id TEXT REFERENCES jobs (job_id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, job_id)
+);
+
+CREATE INDEX IF NOT EXISTS user_job_groups_user_id_canonical_job_id_idx
+    ON user_job_groups (user_id, canonical_job_id);
+
+-- Hand edits no longer change the detected groups, so nothing is locked
+ALTER TABLE jobs DROP COLUMN duplicate_locked;
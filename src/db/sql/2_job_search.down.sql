@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS companies_fts_update;
+DROP TRIGGER IF EXISTS jobs_fts_delete;
+DROP TRIGGER IF EXISTS jobs_fts_update;
+DROP TRIGGER IF EXISTS jobs_fts_insert;
+DROP TABLE IF EXISTS jobs_fts;
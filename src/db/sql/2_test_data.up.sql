@@ -1 +0,0 @@
-INSERT INTO users (user_id, username) VALUES ('5678', 'testuser');
@@ -0,0 +1,8 @@
+DROP INDEX IF EXISTS jobs_salary_annual_idx;
+DROP INDEX IF EXISTS jobs_seniority_idx;
+DROP INDEX IF EXISTS jobs_work_mode_idx;
+DROP INDEX IF EXISTS jobs_location_idx;
+
+ALTER TABLE jobs DROP COLUMN salary_annual;
+ALTER TABLE jobs DROP COLUMN seniority;
+ALTER TABLE jobs DROP COLUMN work_mode;
@@ -0,0 +1,8 @@
+ALTER TABLE jobs ADD COLUMN work_mode TEXT CHECK (work_mode IN ('remote', 'hybrid', 'onsite'));
+ALTER TABLE jobs ADD COLUMN seniority TEXT CHECK (seniority IN ('intern', 'junior', 'mid', 'senior', 'staff', 'principal'));
+ALTER TABLE jobs ADD COLUMN salary_annual INTEGER;
+
+CREATE INDEX IF NOT EXISTS jobs_location_idx ON jobs (location);
+CREATE INDEX IF NOT EXISTS jobs_work_mode_idx ON jobs (work_mode);
+CREATE INDEX IF NOT EXISTS jobs_seniority_idx ON jobs (seniority);
+CREATE INDEX IF NOT EXISTS jobs_salary_annual_idx ON jobs (salary_annual);
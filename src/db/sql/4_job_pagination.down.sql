@@ -0,0 +1 @@
+DROP INDEX IF EXISTS jobs_created_at_job_id_idx;
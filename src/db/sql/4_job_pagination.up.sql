@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS jobs_created_at_job_id_idx ON jobs (created_at DESC, job_id);
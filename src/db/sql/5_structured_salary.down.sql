@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS jobs_salary_annual_sort_idx;
+
+ALTER TABLE jobs DROP COLUMN salary_equity;
+ALTER TABLE jobs DROP COLUMN salary_period;
+ALTER TABLE jobs DROP COLUMN salary_currency;
+ALTER TABLE jobs DROP COLUMN salary_max;
+ALTER TABLE jobs DROP COLUMN salary_min;
@@ -0,0 +1,10 @@
+ALTER TABLE jobs ADD COLUMN salary_min REAL;
+ALTER TABLE jobs ADD COLUMN salary_max REAL;
+ALTER TABLE jobs ADD COLUMN salary_currency TEXT;
+ALTER TABLE jobs ADD COLUMN salary_period TEXT CHECK (salary_period IN ('hourly', 'monthly', 'yearly'));
+ALTER TABLE jobs ADD COLUMN salary_equity BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Filled in from the free-text salary by `db::jobs::backfill_salaries`
+UPDATE jobs SET salary_annual = NULL;
+
+CREATE INDEX IF NOT EXISTS jobs_salary_annual_sort_idx ON jobs (salary_annual DESC, created_at DESC, job_id);
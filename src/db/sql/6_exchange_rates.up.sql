@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    currency TEXT PRIMARY KEY NOT NULL,
+    -- Units of this currency one US dollar buys
+    per_usd REAL NOT NULL CHECK (per_usd > 0),
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS sessions;
+
+DROP INDEX IF EXISTS users_user_id_idx;
+
+ALTER TABLE users DROP COLUMN password_hash;
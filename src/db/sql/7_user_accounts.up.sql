@@ -0,0 +1,14 @@
+-- Accounts created before passwords existed can't log in until they set one
+ALTER TABLE users ADD COLUMN password_hash TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_user_id_idx ON users (user_id);
+
+CREATE TABLE IF NOT EXISTS sessions (
+    -- SHA-256 of the session token, so a leaked database can't be used to log in
+    session_id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS sessions_user_id_idx ON sessions (user_id);
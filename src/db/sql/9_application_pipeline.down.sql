@@ -0,0 +1,20 @@
+DROP INDEX IF EXISTS application_stages_user_id_job_id_idx;
+DROP TABLE IF EXISTS application_stages;
+
+CREATE TABLE IF NOT EXISTS saved_jobs (
+    user_id TEXT NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+    saved_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, job_id)
+);
+
+INSERT INTO saved_jobs (user_id, job_id, saved_at)
+SELECT user_id, job_id, created_at FROM applications WHERE stage = 'saved';
+
+DELETE FROM applications WHERE stage = 'saved';
+
+DROP INDEX IF EXISTS applications_user_id_stage_idx;
+
+ALTER TABLE applications DROP COLUMN updated_at;
+ALTER TABLE applications DROP COLUMN stage;
+ALTER TABLE applications RENAME COLUMN created_at TO applied_at;
@@ -0,0 +1,27 @@
+-- Applications become the whole pipeline, saved jobs being its first stage
+ALTER TABLE applications RENAME COLUMN applied_at TO created_at;
+ALTER TABLE applications ADD COLUMN stage TEXT NOT NULL DEFAULT 'applied' CHECK (stage IN ('saved', 'applied', 'phone_screen', 'onsite', 'offer', 'rejected', 'withdrawn'));
+ALTER TABLE applications ADD COLUMN updated_at TIMESTAMP;
+
+UPDATE applications SET updated_at = created_at;
+
+INSERT OR IGNORE INTO applications (user_id, job_id, created_at, stage, updated_at)
+SELECT user_id, job_id, saved_at, 'saved', saved_at FROM saved_jobs;
+
+DROP TABLE IF EXISTS saved_jobs;
+
+CREATE INDEX IF NOT EXISTS applications_user_id_stage_idx ON applications (user_id, stage);
+
+-- Every move of a job through a user's pipeline, NULL stages meaning outside of it
+CREATE TABLE IF NOT EXISTS application_stages (
+    user_id TEXT NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+    from_stage TEXT,
+    to_stage TEXT,
+    changed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS application_stages_user_id_job_id_idx ON application_stages (user_id, job_id, changed_at);
+
+INSERT INTO application_stages (user_id, job_id, to_stage, changed_at)
+SELECT user_id, job_id, stage, created_at FROM applications;
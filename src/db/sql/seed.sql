@@ -0,0 +1,17 @@
+INSERT OR IGNORE INTO users (user_id, username) VALUES ('5678', 'testuser');
+
+INSERT OR IGNORE INTO companies (company_id, name) VALUES
+    (1, 'Cloudflare'),
+    (2, 'GitHub'),
+    (3, 'Amazon'),
+    (4, 'Adobe'),
+    (5, 'Google'),
+    (6, 'Crowdstrike');
+
+INSERT OR IGNORE INTO jobs (job_id, company_id, title, location, salary, description, work_mode, seniority) VALUES
+    ('2', 1, 'Software Developer Intern', 'New York', '120K USD', 'You''re gonna be the coffee boy, sorry', 'onsite', 'intern'),
+    ('1', 2, 'Sr Rust Developer', 'Vancouver', '187K CAD', 'Join the up and coming hype driven development!', 'remote', 'senior'),
+    ('3', 3, 'Technical Product Manager - AI Enhancement Policy Team', 'Seattle', '278K USD', 'You are the new team SCRUMLORD and AGILEMASTER', 'hybrid', 'senior'),
+    ('alsdkjaslkdsf', 4, 'Jr Fullstack Developer', 'Seattle', '73K CAD', 'Have fun you reach andy', 'hybrid', 'junior'),
+    ('ldsfjosadjasd', 5, 'Staff QA Specialist', 'San Fransisco', '572K USD', 'You are head honcho and hate your job', 'onsite', 'staff'),
+    ('lidfsjasdlkajsd', 6, 'Software Developer', 'New York', '147K USD', 'Want to investigate Snarling Bear or Smiling Panda?', 'remote', 'mid');
@@ -0,0 +1,238 @@
+use anyhow::{Context, Result};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+
+use super::DbPool;
+
+/// How long a login lasts
+pub const SESSION_DAYS: i64 = 30;
+
+/// Checked against when there's no account to log in to, so unknown usernames
+/// take as long to reject as wrong passwords
+const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$ra//Jvux/72MvU1KxcnfuQ$FBqms8dersXG8ILs6xnIOMQZHrKIG7WH+uJtPc2O9Qk";
+
+#[derive(Clone, Debug, FromRow)]
+pub struct User {
+    pub id: String,
+    pub username: String,
+    /// Can see how the site is running, e.g. imports
+    pub is_admin: bool,
+}
+
+/// Why a username and password can't be used for an account
+pub fn validate_credentials(username: &str, password: &str) -> Result<(), &'static str> {
+    if !(3..=32).contains(&username.chars().count()) {
+        return Err("Usernames must be between 3 and 32 characters long.");
+    }
+    if !username
+        .chars()
+        .all(|c| return c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err("Usernames may only contain letters, numbers, '_' and '-'.");
+    }
+    if password.chars().count() < 8 {
+        return Err("Passwords must be at least 8 characters long.");
+    }
+    return Ok(());
+}
+
+/// Hashes `password` with argon2, off the async runtime as it is deliberately slow
+async fn hash_password(password: String) -> Result<String> {
+    return tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        return Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| return hash.to_string())
+            .map_err(|err| return anyhow::anyhow!("{err}"));
+    })
+    .await
+    .context("Could not hash password")?;
+}
+
+async fn verify_password(password: String, hash: String) -> Result<bool> {
+    return tokio::task::spawn_blocking(move || {
+        let hash = PasswordHash::new(&hash).map_err(|err| return anyhow::anyhow!("{err}"))?;
+        return Ok(Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok());
+    })
+    .await
+    .context("Could not verify password")?;
+}
+
+/// Random, URL safe identifier
+pub(super) fn random_token(bytes: usize) -> String {
+    let mut token = vec![0; bytes];
+    OsRng.fill_bytes(&mut token);
+    return URL_SAFE_NO_PAD.encode(token);
+}
+
+/// What is stored of a secret token, so a leaked database can't be used in
+/// its place
+pub(super) fn token_hash(token: &str) -> String {
+    return hex::encode(Sha256::digest(token.as_bytes()));
+}
+
+/// Creates an account, or `None` if the username is taken
+#[tracing::instrument(skip(db, password))]
+pub async fn create_user(db: &DbPool, username: &str, password: &str) -> Result<Option<User>> {
+    let password_hash = hash_password(password.to_owned()).await?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (user_id, username, password_hash) VALUES (?, ?, ?)
+        RETURNING user_id AS id, username, is_admin
+        "#,
+    )
+    .bind(random_token(16))
+    .bind(username)
+    .bind(password_hash)
+    .fetch_one(db)
+    .await;
+
+    return match user {
+        Ok(user) => Ok(Some(user)),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(None),
+        Err(err) => Err(err).context("Could not create user"),
+    };
+}
+
+/// The user with `username`, if `password` is theirs
+#[tracing::instrument(skip(db, password))]
+pub async fn authenticate(db: &DbPool, username: &str, password: &str) -> Result<Option<User>> {
+    let row: Option<(String, String, bool, Option<String>)> = sqlx::query_as(
+        "SELECT user_id, username, is_admin, password_hash FROM users WHERE username = ?",
+    )
+    .bind(username)
+    .fetch_optional(db)
+    .await
+    .context("Could not get user")?;
+
+    let Some((id, username, is_admin, Some(password_hash))) = row else {
+        verify_password(password.to_owned(), DUMMY_PASSWORD_HASH.to_owned()).await?;
+        return Ok(None);
+    };
+
+    if !verify_password(password.to_owned(), password_hash).await? {
+        return Ok(None);
+    }
+
+    return Ok(Some(User {
+        id,
+        username,
+        is_admin,
+    }));
+}
+
+/// Starts a session for `user_id`, returning the token identifying it
+#[tracing::instrument(skip(db))]
+pub async fn create_session(db: &DbPool, user_id: &str) -> Result<String> {
+    let token = random_token(32);
+
+    sqlx::query(
+        "INSERT INTO sessions (session_id, user_id, expires_at) VALUES (?, ?, datetime('now', ?))",
+    )
+    .bind(token_hash(&token))
+    .bind(user_id)
+    .bind(format!("+{SESSION_DAYS} days"))
+    .execute(db)
+    .await
+    .context("Could not create session")?;
+
+    return Ok(token);
+}
+
+/// The user logged in with the session `token`, unless it has expired
+#[tracing::instrument(skip_all)]
+pub async fn session_user(db: &DbPool, token: &str) -> Result<Option<User>> {
+    return sqlx::query_as::<_, User>(
+        r#"
+        SELECT users.user_id AS id, users.username, users.is_admin
+        FROM sessions INNER JOIN users ON users.user_id = sessions.user_id
+        WHERE sessions.session_id = ? AND sessions.expires_at > datetime('now')
+        "#,
+    )
+    .bind(token_hash(token))
+    .fetch_optional(db)
+    .await
+    .context("Could not get session");
+}
+
+/// Ends the session `token`, clearing out expired sessions along the way
+#[tracing::instrument(skip_all)]
+pub async fn delete_session(db: &DbPool, token: &str) -> Result<()> {
+    sqlx::query("DELETE FROM sessions WHERE session_id = ? OR expires_at <= datetime('now')")
+        .bind(token_hash(token))
+        .execute(db)
+        .await
+        .context("Could not delete session")?;
+
+    return Ok(());
+}
+
+/// Makes a user an admin or not, returning whether they exist
+#[tracing::instrument(skip(db))]
+pub async fn set_admin(db: &DbPool, username: &str, is_admin: bool) -> Result<bool> {
+    let result = sqlx::query("UPDATE users SET is_admin = ? WHERE username = ?")
+        .bind(is_admin)
+        .bind(username)
+        .execute(db)
+        .await
+        .context("Could not update user")?;
+
+    return Ok(result.rows_affected() > 0);
+}
+
+/// Changes a user's password, logging them out everywhere. Returns whether
+/// they exist.
+#[tracing::instrument(skip(db, password))]
+pub async fn reset_password(db: &DbPool, username: &str, password: &str) -> Result<bool> {
+    let password_hash = hash_password(password.to_owned()).await?;
+
+    let mut tx = db.begin().await.context("Could not start transaction")?;
+
+    let user_id: Option<String> =
+        sqlx::query_scalar("SELECT user_id FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Could not get user")?;
+    let Some(user_id) = user_id else {
+        return Ok(false);
+    };
+
+    sqlx::query("UPDATE users SET password_hash = ? WHERE user_id = ?")
+        .bind(password_hash)
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await
+        .context("Could not update password")?;
+    sqlx::query("DELETE FROM sessions WHERE user_id = ?")
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await
+        .context("Could not delete sessions")?;
+
+    tx.commit().await.context("Could not reset password")?;
+    return Ok(true);
+}
+
+/// Deletes a user along with everything they saved, returning whether they
+/// existed
+#[tracing::instrument(skip(db))]
+pub async fn delete_user(db: &DbPool, username: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM users WHERE username = ?")
+        .bind(username)
+        .execute(db)
+        .await
+        .context("Could not delete user")?;
+
+    return Ok(result.rows_affected() > 0);
+}
@@ -0,0 +1,245 @@
+use sha2::{Digest, Sha256};
+
+/// Hashes in a MinHash signature
+pub const SIGNATURE_LENGTH: usize = 64;
+
+/// Signature hashes per locality-sensitive band. Postings sharing a band are
+/// likely to be at least (1/16)^(1/4) = 50% similar.
+const BAND_ROWS: usize = 4;
+
+/// Words per shingle
+const SHINGLE_WORDS: usize = 3;
+
+/// Words which say nothing about which company it is
+const COMPANY_SUFFIXES: &[&str] = &[
+    "inc",
+    "incorporated",
+    "llc",
+    "ltd",
+    "limited",
+    "gmbh",
+    "ag",
+    "sa",
+    "bv",
+    "plc",
+    "corp",
+    "corporation",
+    "co",
+    "company",
+];
+
+/// Abbreviations spelled out, so titles written either way match
+const TITLE_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("sr", "senior"),
+    ("snr", "senior"),
+    ("jr", "junior"),
+    ("eng", "engineer"),
+    ("engr", "engineer"),
+    ("dev", "developer"),
+    ("mgr", "manager"),
+    ("swe", "software engineer"),
+];
+
+/// Words of `text`, lowercased and without punctuation
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    return text
+        .split(|c: char| return !c.is_alphanumeric())
+        .filter(|word| return !word.is_empty())
+        .map(str::to_lowercase);
+}
+
+pub fn normalize_company(company: &str) -> String {
+    return words(company)
+        .filter(|word| return !COMPANY_SUFFIXES.contains(&word.as_str()))
+        .collect::<Vec<_>>()
+        .join(" ");
+}
+
+/// A title without abbreviations or where the job is done, which boards
+/// tend to tack on
+pub fn normalize_title(title: &str) -> String {
+    return words(title)
+        .filter(|word| return !matches!(word.as_str(), "remote" | "hybrid" | "onsite"))
+        .map(|word| {
+            return TITLE_ABBREVIATIONS
+                .iter()
+                .find(|(short, _)| return *short == word)
+                .map(|(_, long)| return (*long).to_owned())
+                .unwrap_or(word);
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+}
+
+fn normalize_location(location: &str) -> String {
+    return words(location).collect::<Vec<_>>().join(" ");
+}
+
+/// Identifies a role at a company in a place, however it's written
+pub fn fingerprint(company: &str, title: &str, location: &str) -> String {
+    let normalized = format!(
+        "{}\n{}\n{}",
+        normalize_company(company),
+        normalize_title(title),
+        normalize_location(location)
+    );
+    return hex::encode(&Sha256::digest(normalized.as_bytes())[..8]);
+}
+
+/// Scrambles the bits of `x`, from SplitMix64
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    return x ^ (x >> 31);
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let digest = Sha256::digest(bytes);
+    return u64::from_le_bytes(digest[..8].try_into().expect("8 bytes"));
+}
+
+/// MinHash signature of the word shingles of `text`, or `None` if it's too
+/// short to say anything
+pub fn signature(text: &str) -> Option<Vec<u64>> {
+    let words = words(text).collect::<Vec<_>>();
+    if words.len() < SHINGLE_WORDS {
+        return None;
+    }
+
+    let mut signature = vec![u64::MAX; SIGNATURE_LENGTH];
+    for shingle in words.windows(SHINGLE_WORDS) {
+        let shingle = hash(shingle.join(" ").as_bytes());
+        for (i, min) in signature.iter_mut().enumerate() {
+            // Each position hashes shingles differently, seeded by its index
+            *min = (*min).min(mix(shingle ^ mix(i as u64 + 1)));
+        }
+    }
+
+    return Some(signature);
+}
+
+/// Estimated Jaccard similarity of the texts behind two signatures
+pub fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let same = a.iter().zip(b).filter(|(a, b)| return a == b).count();
+    return same as f64 / a.len() as f64;
+}
+
+/// Hashes of each band of a signature, to look up similar postings by
+pub fn bands(signature: &[u64]) -> Vec<i64> {
+    return signature
+        .chunks(BAND_ROWS)
+        .map(|band| {
+            let bytes = band
+                .iter()
+                .flat_map(|h| return h.to_le_bytes())
+                .collect::<Vec<_>>();
+            return hash(&bytes) as i64;
+        })
+        .collect();
+}
+
+/// A signature as stored in the database
+pub fn to_bytes(signature: &[u64]) -> Vec<u8> {
+    return signature
+        .iter()
+        .flat_map(|h| return h.to_le_bytes())
+        .collect();
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Vec<u64> {
+    return bytes
+        .chunks_exact(8)
+        .map(|h| return u64::from_le_bytes(h.try_into().expect("8 bytes")))
+        .collect();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const POSTING: &str =
+        "Acme is hiring a senior backend engineer to join the payments team. \
+        You will design and build the APIs that move money for millions of customers, own \
+        the reliability of our ledger, and mentor engineers across the team. We work in Rust \
+        and Go on Kubernetes, and value clear writing, small pull requests and calm on-call.";
+
+    /// The same job, reposted on another board with its own header and footer
+    pub(crate) fn near_copy() -> String {
+        return format!(
+            "About the role: {POSTING} Apply through our careers site, we reply to everyone."
+        );
+    }
+
+    /// The same title at the same company, but a different job
+    pub(crate) const OTHER_TEAM: &str =
+        "Acme is hiring a senior backend engineer to join the search \
+        team. You will scale the indexing pipeline behind job and company search, tune \
+        ranking with our data scientists, and keep query latency low as traffic grows. We \
+        work in Java and Elasticsearch, and you'll pair with product on every launch.";
+
+    #[test]
+    fn normalizes_titles() {
+        let cases = [
+            ("Sr. Software Eng (Remote)", "senior software engineer"),
+            ("SWE II - Hybrid", "software engineer ii"),
+            ("Jr Dev", "junior developer"),
+            ("Engineering Manager, Onsite", "engineering manager"),
+        ];
+        for (title, normalized) in cases {
+            assert_eq!(normalize_title(title), normalized, "{title:?}");
+        }
+        assert_eq!(
+            normalize_company("Acme, Inc."),
+            normalize_company("ACME LLC")
+        );
+    }
+
+    #[test]
+    fn fingerprints_however_written() {
+        assert_eq!(
+            fingerprint("Acme Inc.", "Sr. Backend Eng (Remote)", "Toronto, ON"),
+            fingerprint("ACME", "Senior Backend Engineer", "toronto on")
+        );
+        assert_ne!(
+            fingerprint("Acme", "Senior Backend Engineer", "Toronto"),
+            fingerprint("Acme", "Senior Backend Engineer", "Vancouver")
+        );
+    }
+
+    #[test]
+    fn signs_only_long_enough_text() {
+        assert_eq!(signature("Apply now"), None);
+        assert_eq!(signature(POSTING).unwrap().len(), SIGNATURE_LENGTH);
+        // Case and punctuation don't change the signature
+        assert_eq!(
+            signature(POSTING),
+            signature(&POSTING.to_uppercase().replace(',', ""))
+        );
+    }
+
+    #[test]
+    fn measures_similarity() {
+        let posting = signature(POSTING).unwrap();
+        let near_copy = signature(&near_copy()).unwrap();
+        let other_team = signature(OTHER_TEAM).unwrap();
+
+        assert_eq!(similarity(&posting, &posting), 1.0);
+        let copy_similarity = similarity(&posting, &near_copy);
+        assert!(copy_similarity >= 0.8, "near copy only {copy_similarity}");
+        let team_similarity = similarity(&posting, &other_team);
+        assert!(team_similarity < 0.5, "other team {team_similarity}");
+
+        assert_eq!(similarity(&posting, &[]), 0.0);
+        assert_eq!(similarity(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn stores_signatures() {
+        let signature = signature(POSTING).unwrap();
+        assert_eq!(from_bytes(&to_bytes(&signature)), signature);
+        assert_eq!(bands(&signature).len(), SIGNATURE_LENGTH / BAND_ROWS);
+    }
+}
@@ -0,0 +1,77 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, State},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use leptos::view;
+
+use super::{
+    auth::CurrentUser,
+    components::{htmlify, ImportsPage, Layout},
+    error::AppError,
+    AppState,
+};
+use crate::db::{runs, sources, users::User};
+
+/// How many import runs the admin page shows
+const RECENT_RUNS: i64 = 100;
+
+pub fn router() -> Router<AppState> {
+    return Router::new()
+        .route("/admin/imports", get(imports_handler))
+        .route("/admin/sources/:id/run", post(run_source));
+}
+
+/// The logged in admin. Admin pages don't exist as far as anyone else knows.
+pub(super) struct Admin(pub User);
+
+#[async_trait]
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Response> {
+        let CurrentUser(user) = CurrentUser::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        return match user {
+            Some(user) if user.is_admin => Ok(Admin(user)),
+            Some(_) => Err(AppError::NotFound.into_response()),
+            None => Err(Redirect::to("/login").into_response()),
+        };
+    }
+}
+
+async fn imports_handler(
+    State(state): State<AppState>,
+    Admin(user): Admin,
+) -> Result<Response, AppError> {
+    let sources = sources::list_sources(&state.db).await?;
+    let runs = runs::recent_runs(&state.db, RECENT_RUNS).await?;
+
+    let h = htmlify(move || {
+        return view! {
+            <Layout user=Some(user)>
+                <ImportsPage sources=sources runs=runs />
+            </Layout>
+        };
+    });
+
+    return Ok((StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h).into_response());
+}
+
+/// Makes a source due, for the scheduler to pick up on its next tick
+async fn run_source(
+    State(state): State<AppState>,
+    _: Admin,
+    Path(id): Path<i64>,
+) -> Result<Response, AppError> {
+    if !sources::run_now(&state.db, id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    return Ok(Redirect::to("/admin/imports").into_response());
+}
@@ -0,0 +1,177 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Router,
+};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
+use leptos::view;
+use serde::Deserialize;
+use time::Duration;
+
+use super::{
+    components::{htmlify, Layout, LoginForm, RegisterForm},
+    error::AppError,
+    AppState,
+};
+use crate::db::users::{self, User, SESSION_DAYS};
+
+/// Holds the session token of the logged in user
+const SESSION_COOKIE: &str = "session";
+
+pub fn router() -> Router<AppState> {
+    return Router::new()
+        .route("/login", get(login_page).post(login))
+        .route("/register", get(register_page).post(register))
+        .route("/logout", post(logout));
+}
+
+/// The user logged in on this request, if any
+pub struct CurrentUser(pub Option<User>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, AppError> {
+        let jar = CookieJar::from_headers(&parts.headers);
+        let Some(token) = jar.get(SESSION_COOKIE) else {
+            return Ok(CurrentUser(None));
+        };
+
+        return Ok(CurrentUser(
+            users::session_user(&state.db, token.value()).await?,
+        ));
+    }
+}
+
+#[derive(Deserialize)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+fn session_cookie(token: String) -> Cookie<'static> {
+    return Cookie::build((SESSION_COOKIE, token))
+        .path("/")
+        .max_age(Duration::days(SESSION_DAYS))
+        .same_site(SameSite::Lax)
+        .secure(true)
+        .http_only(true)
+        .build();
+}
+
+/// Logs `user` in, sending them on to the job list
+async fn start_session(
+    state: &AppState,
+    jar: CookieJar,
+    user: &User,
+) -> Result<impl IntoResponse, AppError> {
+    let token = users::create_session(&state.db, &user.id).await?;
+    return Ok((jar.add(session_cookie(token)), Redirect::to("/jobs")));
+}
+
+fn login_response(status: StatusCode, username: String, error: Option<&'static str>) -> Response {
+    let h = htmlify(move || {
+        return view! {
+            <Layout>
+                <LoginForm username=username error=error />
+            </Layout>
+        };
+    });
+
+    return (status, [(header::CONTENT_TYPE, "text/html")], h).into_response();
+}
+
+fn register_response(
+    status: StatusCode,
+    username: String,
+    error: Option<&'static str>,
+) -> Response {
+    let h = htmlify(move || {
+        return view! {
+            <Layout>
+                <RegisterForm username=username error=error />
+            </Layout>
+        };
+    });
+
+    return (status, [(header::CONTENT_TYPE, "text/html")], h).into_response();
+}
+
+async fn login_page(CurrentUser(user): CurrentUser) -> Response {
+    if user.is_some() {
+        return Redirect::to("/jobs").into_response();
+    }
+    return login_response(StatusCode::OK, String::new(), None);
+}
+
+async fn login(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Form(credentials): Form<Credentials>,
+) -> Result<Response, AppError> {
+    let Some(user) =
+        users::authenticate(&state.db, &credentials.username, &credentials.password).await?
+    else {
+        return Ok(login_response(
+            StatusCode::UNAUTHORIZED,
+            credentials.username,
+            Some("Invalid username or password."),
+        ));
+    };
+
+    return Ok(start_session(&state, jar, &user).await?.into_response());
+}
+
+async fn register_page(CurrentUser(user): CurrentUser) -> Response {
+    if user.is_some() {
+        return Redirect::to("/jobs").into_response();
+    }
+    return register_response(StatusCode::OK, String::new(), None);
+}
+
+async fn register(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Form(credentials): Form<Credentials>,
+) -> Result<Response, AppError> {
+    let username = credentials.username.trim().to_owned();
+
+    if let Err(error) = users::validate_credentials(&username, &credentials.password) {
+        return Ok(register_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            username,
+            Some(error),
+        ));
+    }
+
+    let Some(user) = users::create_user(&state.db, &username, &credentials.password).await? else {
+        return Ok(register_response(
+            StatusCode::CONFLICT,
+            username,
+            Some("That username is taken."),
+        ));
+    };
+
+    return Ok(start_session(&state, jar, &user).await?.into_response());
+}
+
+async fn logout(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(token) = jar.get(SESSION_COOKIE) {
+        users::delete_session(&state.db, token.value()).await?;
+    }
+
+    return Ok((
+        jar.remove(Cookie::build(SESSION_COOKIE).path("/")),
+        Redirect::to("/jobs"),
+    ));
+}
@@ -0,0 +1,233 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{delete, get, post},
+    Form, Router,
+};
+use leptos::view;
+use serde::Deserialize;
+use time::{macros::format_description, Date, Time};
+
+use super::{
+    auth::CurrentUser,
+    components::{htmlify, CalendarPage, JobEvents, Layout},
+    error::AppError,
+    AppState,
+};
+use crate::{
+    db::{
+        calendar::{self, EventKind, NewEvent},
+        users::User,
+    },
+    ical,
+};
+
+pub fn router() -> Router<AppState> {
+    return Router::new()
+        .route("/calendar", get(calendar_handler))
+        .route("/calendar/feed", post(calendar_feed_rotate))
+        .route("/calendar/feed/:file", get(calendar_feed))
+        .route(
+            "/htmx/jobs/:id/events",
+            get(htmx_job_events).post(htmx_job_event_add),
+        )
+        .route("/htmx/events/:id", delete(htmx_job_event_remove));
+}
+
+#[derive(Deserialize)]
+struct EventForm {
+    kind: EventKind,
+    day: String,
+    /// Empty for all day events
+    #[serde(default)]
+    start_time: String,
+    #[serde(default)]
+    duration_minutes: String,
+    #[serde(default)]
+    notes: String,
+}
+
+impl EventForm {
+    /// The event described by the form, or why it doesn't describe one
+    fn parse(self) -> Result<NewEvent, &'static str> {
+        let day = Date::parse(self.day.trim(), format_description!("[year]-[month]-[day]"))
+            .map_err(|_| return "Pick the day of the event.")?;
+
+        let start_time = match self.start_time.trim() {
+            "" => None,
+            time => Some(
+                Time::parse(time, format_description!("[hour]:[minute]"))
+                    .map_err(|_| return "Times look like 14:30.")?,
+            ),
+        };
+
+        // All day events last the day
+        let duration_minutes = match (start_time, self.duration_minutes.trim()) {
+            (None, _) | (_, "") => None,
+            (Some(_), minutes) => Some(
+                minutes
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|m| return (1..=24 * 60).contains(m))
+                    .ok_or("Durations are between 1 and 1440 minutes.")?,
+            ),
+        };
+
+        return Ok(NewEvent {
+            kind: self.kind,
+            day,
+            start_time,
+            duration_minutes,
+            notes: self.notes,
+        });
+    }
+}
+
+fn calendar_page(
+    status: StatusCode,
+    user: User,
+    events: Vec<calendar::CalendarEvent>,
+    has_feed: bool,
+    feed_url: Option<String>,
+) -> Response {
+    let h = htmlify(move || {
+        return view! {
+            <Layout user=Some(user)>
+                <CalendarPage events=events has_feed=has_feed feed_url=feed_url />
+            </Layout>
+        };
+    });
+
+    return (status, [(header::CONTENT_TYPE, "text/html")], h).into_response();
+}
+
+async fn calendar_handler(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Response, AppError> {
+    let Some(user) = user else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+
+    let events = calendar::user_events(&state.db, &user.id).await?;
+    let has_feed = calendar::has_feed(&state.db, &user.id).await?;
+
+    return Ok(calendar_page(StatusCode::OK, user, events, has_feed, None));
+}
+
+/// Replaces the user's feed URL, showing the new one this once
+async fn calendar_feed_rotate(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Response, AppError> {
+    let Some(user) = user else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+
+    let token = calendar::rotate_feed_token(&state.db, &user.id).await?;
+    let events = calendar::user_events(&state.db, &user.id).await?;
+    let feed_url = format!("/calendar/feed/{token}.ics");
+
+    return Ok(calendar_page(
+        StatusCode::OK,
+        user,
+        events,
+        true,
+        Some(feed_url),
+    ));
+}
+
+/// The user's events for calendar clients, authorized by the secret token in
+/// the file name rather than a session
+async fn calendar_feed(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let token = file.strip_suffix(".ics").ok_or(AppError::NotFound)?;
+    let user_id = calendar::feed_user_id(&state.db, token)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let events = calendar::user_events(&state.db, &user_id).await?;
+
+    return Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=300"),
+        ],
+        ical::feed("WantJob", &events),
+    ));
+}
+
+async fn job_events_fragment(
+    state: &AppState,
+    status: StatusCode,
+    user: &User,
+    job_id: String,
+    error: Option<&'static str>,
+) -> Result<impl IntoResponse, AppError> {
+    let events = calendar::job_events(&state.db, &user.id, &job_id).await?;
+
+    let h = htmlify(move || {
+        return view! {
+            <JobEvents job_id=job_id events=events error=error />
+        };
+    });
+
+    return Ok((status, [(header::CONTENT_TYPE, "text/html")], h));
+}
+
+async fn htmx_job_events(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    return job_events_fragment(&state, StatusCode::OK, &user, id, None).await;
+}
+
+async fn htmx_job_event_add(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+    Form(form): Form<EventForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    let event = match form.parse() {
+        Ok(event) => event,
+        Err(error) => {
+            return job_events_fragment(
+                &state,
+                StatusCode::UNPROCESSABLE_ENTITY,
+                &user,
+                id,
+                Some(error),
+            )
+            .await;
+        }
+    };
+
+    if !calendar::add_event(&state.db, &user.id, &id, &event).await? {
+        return Err(AppError::NotFound);
+    }
+
+    return job_events_fragment(&state, StatusCode::OK, &user, id, None).await;
+}
+
+async fn htmx_job_event_remove(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    let job_id = calendar::delete_event(&state.db, &user.id, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    return job_events_fragment(&state, StatusCode::OK, &user, job_id, None).await;
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Form, Router,
+};
+use axum_extra::extract::CookieJar;
+use leptos::view;
+use serde::Deserialize;
+
+use super::{
+    admin::Admin,
+    auth::CurrentUser,
+    components::{htmlify, CompanyPage, Layout},
+    error::AppError,
+    index::viewer,
+    AppState,
+};
+use crate::db::{
+    companies::{self, Company, CompanyProfile},
+    pipeline::{self, StageChange},
+    rates,
+    users::User,
+};
+
+pub fn router() -> Router<AppState> {
+    return Router::new().route(
+        "/companies/:slug",
+        get(company_handler).post(company_update),
+    );
+}
+
+#[derive(Deserialize)]
+struct ProfileForm {
+    domain: String,
+    description: String,
+    size: String,
+    headquarters: String,
+    careers_url: String,
+    logo_url: String,
+}
+
+/// Trimmed text, or `None` if there's none
+fn optional(text: &str) -> Option<String> {
+    return Some(text.trim().to_owned()).filter(|t| return !t.is_empty());
+}
+
+/// Whether `url` is a web page, so it's safe to link to
+fn is_web_url(url: &str) -> bool {
+    return reqwest::Url::parse(url)
+        .is_ok_and(|url| return matches!(url.scheme(), "https" | "http"));
+}
+
+impl ProfileForm {
+    /// The form as submitted, to fill it in again
+    fn values(self) -> CompanyProfile {
+        return CompanyProfile {
+            domain: Some(self.domain),
+            description: self.description,
+            size: Some(self.size),
+            headquarters: Some(self.headquarters),
+            careers_url: Some(self.careers_url),
+            logo_url: Some(self.logo_url),
+        };
+    }
+
+    fn parse(&self) -> Result<CompanyProfile, &'static str> {
+        let domain = optional(&self.domain).map(|d| return d.to_lowercase());
+        let is_domain = |domain: &str| {
+            return reqwest::Url::parse(&format!("https://{domain}/"))
+                .is_ok_and(|url| return url.host_str() == Some(domain));
+        };
+        if domain.as_deref().is_some_and(|d| return !is_domain(d)) {
+            return Err("Domains look like example.com, without https://");
+        }
+
+        let careers_url = optional(&self.careers_url);
+        let logo_url = optional(&self.logo_url);
+        if careers_url
+            .as_deref()
+            .is_some_and(|u| return !is_web_url(u))
+            || logo_url.as_deref().is_some_and(|u| return !is_web_url(u))
+        {
+            return Err("Links must start with https:// or http://");
+        }
+
+        return Ok(CompanyProfile {
+            domain,
+            description: self.description.trim().to_owned(),
+            size: optional(&self.size),
+            headquarters: optional(&self.headquarters),
+            careers_url,
+            logo_url,
+        });
+    }
+}
+
+/// The company's page, with its edit form filled in with `form` if it was
+/// just submitted
+async fn company_response(
+    state: &AppState,
+    status: StatusCode,
+    user: Option<User>,
+    jar: CookieJar,
+    company: Company,
+    form: Option<CompanyProfile>,
+    error: Option<&'static str>,
+) -> Result<Response, AppError> {
+    let currencies = rates::currencies(&state.db).await?;
+    let (viewer, _) = viewer(user.as_ref(), None, jar, &currencies);
+
+    let jobs = companies::open_jobs(&state.db, company.id, &viewer).await?;
+    let applications = companies::applications(&state.db, company.id, &viewer).await?;
+
+    let mut history: HashMap<String, Vec<StageChange>> = HashMap::new();
+    if let Some(user) = &user {
+        for change in pipeline::stage_history(&state.db, &user.id).await? {
+            history
+                .entry(change.job_id.clone())
+                .or_default()
+                .push(change);
+        }
+    }
+    let applications = applications
+        .into_iter()
+        .map(|job| {
+            let changes = history.remove(&job.id).unwrap_or_default();
+            return (job, changes);
+        })
+        .collect();
+
+    // Only admins get the form
+    let form = user
+        .as_ref()
+        .filter(|u| return u.is_admin)
+        .map(|_| return form.unwrap_or_else(|| return company.profile.clone()));
+    let h = htmlify(move || {
+        return view! {
+            <Layout user=user>
+                <CompanyPage
+                    company=company
+                    jobs=jobs
+                    applications=applications
+                    form=form
+                    error=error
+                />
+            </Layout>
+        };
+    });
+
+    return Ok((status, [(header::CONTENT_TYPE, "text/html")], h).into_response());
+}
+
+async fn company_handler(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    jar: CookieJar,
+    Path(slug): Path<String>,
+) -> Result<Response, AppError> {
+    let company = companies::get_company(&state.db, &slug)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    return company_response(&state, StatusCode::OK, user, jar, company, None, None).await;
+}
+
+async fn company_update(
+    State(state): State<AppState>,
+    Admin(user): Admin,
+    jar: CookieJar,
+    Path(slug): Path<String>,
+    Form(form): Form<ProfileForm>,
+) -> Result<Response, AppError> {
+    let company = companies::get_company(&state.db, &slug)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let profile = match form.parse() {
+        Ok(profile) => profile,
+        Err(error) => {
+            return company_response(
+                &state,
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Some(user),
+                jar,
+                company,
+                Some(form.values()),
+                Some(error),
+            )
+            .await;
+        }
+    };
+
+    companies::update_profile(&state.db, company.id, &profile).await?;
+
+    return Ok(Redirect::to(&format!("/companies/{}", company.slug)).into_response());
+}
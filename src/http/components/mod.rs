@@ -1,7 +1,26 @@
-use leptos::{component, view, Children, IntoView};
+use std::time::Instant;
+
+use axum::http::StatusCode;
+use leptos::{component, view, Children, CollectView, IntoView};
 use minify_html::{minify, Cfg};
+use opentelemetry::KeyValue;
 
-use super::index::JobDetails;
+use crate::{
+    db::{
+        calendar::{CalendarEvent, EventKind},
+        companies::{Company, CompanyProfile},
+        duplicates::{Duplicates, Posting},
+        jobs::{JobDetails, JobStatus, HIGHLIGHT_END, HIGHLIGHT_START},
+        notes::{JobNotes, MAX_TAG_LENGTH},
+        pipeline::{Stage, StageChange},
+        runs::ImportRun,
+        shortlists::Shortlist,
+        sources::Source,
+        users::User,
+    },
+    markdown,
+    metrics::RENDER,
+};
 
 #[tracing::instrument(skip_all)]
 fn minify_html(str: String) -> String {
@@ -14,9 +33,19 @@ where
     F: FnOnce() -> N + 'static,
     N: IntoView,
 {
+    let start = Instant::now();
     let ssr = leptos::ssr::render_to_string(f).to_string();
+    let rendered = Instant::now();
+    let html = minify_html(ssr);
+
+    let ms = |from: Instant, to: Instant| return (to - from).as_secs_f64() * 1000.0;
+    RENDER.record(ms(start, rendered), &[KeyValue::new("phase", "render")]);
+    RENDER.record(
+        ms(rendered, Instant::now()),
+        &[KeyValue::new("phase", "minify")],
+    );
 
-    return minify_html(ssr);
+    return html;
 }
 
 // https://fonts.googleapis.com/css?family=Zen%20Maru%20Gothic:400,700&subset=latin
@@ -84,6 +113,15 @@ tailwind.config = {
     }
 };"###;
 
+// Let htmx swap error fragments into the page instead of silently dropping them
+const HTMXCONFIG: &str = r###"
+document.addEventListener("htmx:beforeSwap", (e) => {
+    if (e.detail.xhr.status >= 400) {
+        e.detail.shouldSwap = true;
+        e.detail.isError = false;
+    }
+});"###;
+
 #[component]
 fn NavLink(#[prop(into)] title: String, #[prop(into)] url: String) -> impl IntoView {
     return view! {
@@ -92,7 +130,15 @@ fn NavLink(#[prop(into)] title: String, #[prop(into)] url: String) -> impl IntoV
 }
 
 #[component]
-pub fn Layout(children: Children) -> impl IntoView {
+pub fn Layout(
+    children: Children,
+    /// Current search, pre-filled into the search box
+    #[prop(optional, into)]
+    search: String,
+    /// Who is logged in, if anyone
+    #[prop(optional_no_strip)]
+    user: Option<User>,
+) -> impl IntoView {
     return view! {
         <html lang="en">
             <head>
@@ -101,8 +147,9 @@ pub fn Layout(children: Children) -> impl IntoView {
                 <meta name="viewport" content="width=device-width, initial-scale=1" />
                 <link rel="icon" href="/static/favicon.svg"/>
                 <script src="https://unpkg.com/htmx.org@1.9.9/dist/htmx.min.js" />
-                <script src="https://cdn.tailwindcss.com/3.3.5"></script>
+                <script src="https://cdn.tailwindcss.com/3.3.5?plugins=typography"></script>
                 <script inner_html={TAILWINDCONFIG.to_string()}></script>
+                <script inner_html={HTMXCONFIG.to_string()}></script>
                 <style inner_html={FONTSTYLE.to_string()}></style>
             </head>
 
@@ -117,18 +164,46 @@ pub fn Layout(children: Children) -> impl IntoView {
                                 /><span class="drop-shadow-colored">WantJob</span>
                             </a>
 
-                            /*
-                            <div class="flex space-x-4 ml-5">
-                                <NavLink title="Jobs" url="#" />
-                                <NavLink title="Saved" url="#" />
-                                <NavLink title="Applied" url="#" />
-                            </div>
-                            */
+                            {user.as_ref().map(|user| return view! {
+                                <div class="flex space-x-4 ml-5">
+                                    <NavLink title="Jobs" url="/jobs" />
+                                    <NavLink title="Saved" url="/saved" />
+                                    <NavLink title="Applied" url="/applied" />
+                                    <NavLink title="Pipeline" url="/pipeline" />
+                                    <NavLink title="Calendar" url="/calendar" />
+                                    <NavLink title="Import" url="/import" />
+                                    {user.is_admin.then(|| return view! { <NavLink title="Admin" url="/admin/imports" /> })}
+                                </div>
+                            })}
                         </div>
+                        <form action="/jobs" method="get" class="grow mx-6">
+                            <input
+                                type="search"
+                                name="q"
+                                value=search
+                                placeholder="Search jobs"
+                                autocomplete="off"
+                                hx-get="/htmx/jobs/list"
+                                hx-trigger="input changed delay:300ms, search"
+                                hx-target="#job-list"
+                                hx-include="#job-filters"
+                                class="w-full rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500"
+                            />
+                        </form>
                         // Spacing: 1fr
                         <div class="flex center-items space-x-4">
-                                <NavLink title="Login" url="#" />
-                                <NavLink title="Register" url="#" />
+                            {match user {
+                                Some(user) => view! {
+                                    <span class="text-gray-400">{user.username}</span>
+                                    <form action="/logout" method="post">
+                                        <button type="submit" class="text-gray-300 hover:text-gray-100">Logout</button>
+                                    </form>
+                                }.into_view(),
+                                None => view! {
+                                    <NavLink title="Login" url="/login" />
+                                    <NavLink title="Register" url="/register" />
+                                }.into_view(),
+                            }}
                         </div>
                     </nav>
                 </header>
@@ -151,39 +226,967 @@ pub fn Layout(children: Children) -> impl IntoView {
     };
 }
 
+/// The company's logo, looked up by its domain if it hasn't got one, else
+/// its initial
+#[component]
+pub fn CompanyLogo(
+    #[prop(into)] name: String,
+    domain: Option<String>,
+    logo_url: Option<String>,
+) -> impl IntoView {
+    let src =
+        logo_url.or_else(|| return domain.map(|d| return format!("https://logo.clearbit.com/{d}")));
+
+    return match src {
+        Some(src) => view! {
+            <img src=src alt="" class="h-16 w-16 shrink-0 object-contain rounded-lg mr-4" />
+        }
+        .into_view(),
+        None => view! {
+            <div class="h-16 w-16 shrink-0 flex items-center justify-center rounded-lg mr-4 bg-dark font-bold text-3xl text-gray-400">
+                {name.chars().next().map(|c| return c.to_uppercase().to_string())}
+            </div>
+        }
+        .into_view(),
+    };
+}
+
+/// Links to a company's page
+#[component]
+fn CompanyLink(name: String, slug: String) -> impl IntoView {
+    return view! {
+        <a href=format!("/companies/{slug}") class="hover:underline">{name}</a>
+    };
+}
+
+pub struct FacetOption {
+    pub label: String,
+    /// Matching jobs, for options that narrow the list down
+    pub count: Option<i64>,
+    /// Where following this option leads, toggling it on or off
+    pub href: String,
+    pub active: bool,
+}
+
+pub struct FacetSection {
+    pub title: &'static str,
+    pub options: Vec<FacetOption>,
+}
+
+#[component]
+pub fn FilterSidebar(
+    sections: Vec<FacetSection>,
+    /// Active filters, submitted alongside searches from the search box
+    fields: Vec<(String, String)>,
+    /// Whether this is an out-of-band swap replacing the current sidebar
+    #[prop(optional)]
+    oob: bool,
+) -> impl IntoView {
+    return view! {
+        <div
+            id="job-filters-sidebar"
+            hx-swap-oob=oob.then_some("true")
+            hx-boost="true"
+            class="basis-56 shrink-0 mr-2 overflow-auto h-full"
+        >
+            <form id="job-filters">
+                {fields.into_iter().map(|(name, value)| return view! {
+                    <input type="hidden" name=name value=value />
+                }).collect_view()}
+            </form>
+            {sections.into_iter().filter(|s| return !s.options.is_empty()).map(|section| return view! {
+                <div class="bg-dark-weak rounded-sm p-2 mb-1">
+                    <p class="font-bold">{section.title}</p>
+                    {section.options.into_iter().map(|o| return view! {
+                        <a
+                            href=o.href
+                            class="flex justify-between hover:text-light"
+                            class=("text-accent", o.active)
+                            class=("text-gray-500", o.count == Some(0) && !o.active)
+                        >
+                            <span>{o.label}</span>
+                            {o.count.map(|count| return view! { <span>{count}</span> })}
+                        </a>
+                    }).collect_view()}
+                </div>
+            }).collect_view()}
+        </div>
+    };
+}
+
+/// Escapes `text` for use as HTML, turning highlight markers into `<mark>`s
+fn highlight(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            HIGHLIGHT_START => html.push_str("<mark class=\"bg-accent text-light\">"),
+            HIGHLIGHT_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+
+    return html;
+}
+
+/// The job list beside the details of the selected job, after any sidebar
+/// passed as children
+#[component]
+pub fn JobPanes(
+    jobs: Vec<JobDetails>,
+    next_page: Option<String>,
+    job: Option<JobDetails>,
+    /// The viewer's notes on `job`, when they're logged in
+    #[prop(optional_no_strip)]
+    notes: Option<JobNotes>,
+    /// Shown in place of an empty job list
+    #[prop(default = "No jobs match your search")]
+    empty: &'static str,
+    #[prop(optional)] children: Option<Children>,
+) -> impl IntoView {
+    return view! {
+        <div class="h-full flex">
+            {children.map(|c| return c())}
+            <div id="job-list" class="basis-[48rem] mr-2 overflow-auto h-full">
+                <JobList jobs=jobs next_page=next_page empty=empty />
+            </div>
+            <div class="basis-full bg-dark-weak rounded-sm overflow-auto h-full">
+                {match job {
+                    Some(job) => view! {<JobPostingDetails job=job notes=notes />},
+                    None => view! {<HomePageDetails/>},
+                }}
+            </div>
+        </div>
+    };
+}
+
+#[component]
+pub fn JobList(
+    jobs: Vec<JobDetails>,
+    next_page: Option<String>,
+    /// Shown in place of an empty job list
+    #[prop(default = "No jobs match your search")]
+    empty: &'static str,
+) -> impl IntoView {
+    if jobs.is_empty() {
+        return view! {
+            <p class="p-2 text-center text-gray-400">{empty}</p>
+        }
+        .into_view();
+    }
+
+    return view! { <JobListPage jobs=jobs next_page=next_page /> }.into_view();
+}
+
+/// A batch of jobs, followed by a sentinel which loads the next batch in its
+/// place once scrolled into view
 #[component]
-pub fn CompanyLogo(#[prop(into)] name: String) -> impl IntoView {
+pub fn JobListPage(jobs: Vec<JobDetails>, next_page: Option<String>) -> impl IntoView {
     return view! {
-        <img src="https://logo.clearbit.com/{name}" class="h-16 rounded-lg mr-4" />
+        {jobs
+            .into_iter()
+            .map(|job| return view! { <JobSummary job=job /> })
+            .collect_view()}
+        {next_page.map(|url| return view! {
+            // `revealed` only watches the window scrolling, not the overflowing job list
+            <p hx-get=url hx-trigger="intersect once" hx-swap="outerHTML" class="p-2 text-center text-gray-400">
+                "Loading more jobs…"
+            </p>
+        })}
     };
 }
 
+/// Marks jobs which are no longer taking applications
+#[component]
+fn StatusBadge(status: JobStatus) -> impl IntoView {
+    return (status != JobStatus::Open).then(|| return view! {
+        <span class="shrink-0 rounded-sm px-2 text-sm border border-gray-400 text-gray-400">{status.to_string()}</span>
+    });
+}
+
 #[component]
 pub fn JobSummary(job: JobDetails) -> impl IntoView {
+    let salary = job
+        .salary_range()
+        .map(|s| return s.annualized().to_string())
+        .unwrap_or_else(|| return job.salary.clone());
+    let converted = job.salary_converted().map(|s| return format!("≈ {s}"));
+    let lists = job.saved.zip(job.applied);
+
     return view! {
-        <a hx-get=format!("/htmx/jobs/details/{}", job.id) hx-target="#job-details" class="bg-dark-weak flex p-2 rounded-sm hover:bg-white/10 cursor-pointer mb-1">
-            <CompanyLogo name={job.company.to_owned() + ".com"} />
+        <div hx-get=format!("/htmx/jobs/details/{}", job.id) hx-target="#job-details" class="bg-dark-weak flex p-2 rounded-sm hover:bg-white/10 cursor-pointer mb-1">
+            <CompanyLogo name=job.company.clone() domain=job.company_domain logo_url=job.company_logo_url />
             <div class="grow">
                 <div class="flex grow justify-between">
-                    <p class="underline text-link font-bold">{job.title}</p>
-                    <p>{job.company}</p>
+                    <p class="flex items-center gap-2">
+                        <span class="underline text-link font-bold">{job.title}</span>
+                        <StatusBadge status=job.status />
+                    </p>
+                    <p><CompanyLink name=job.company slug=job.company_slug /></p>
                 </div>
                 <div class="flex grow justify-between">
-                    <p>{job.location}</p>
-                    <p class="rounded-sm px-2 text-[#FFFCF2] bg-accent">{job.salary}</p>
+                    <p>
+                        {job.location}
+                        {job.work_mode.map(|m| return view! {
+                            <span class="text-gray-400">" · "{m.to_string()}</span>
+                        })}
+                        {job.seniority.map(|s| return view! {
+                            <span class="text-gray-400">" · "{s.to_string()}</span>
+                        })}
+                    </p>
+                    <p>
+                        {converted.map(|c| return view! {
+                            <span class="text-gray-400 mr-2">{c}</span>
+                        })}
+                        <span class="rounded-sm px-2 text-[#FFFCF2] bg-accent">{salary}</span>
+                    </p>
                     //<a href={company} class="text-gray-300 hover:text-gray-100">{title}</a>
                 </div>
+                {job.snippet.map(|s| return view! {
+                    <p class="text-sm text-gray-400" inner_html=highlight(&s)></p>
+                })}
+                {lists.map(|(saved, applied)| return view! {
+                    <div class="flex justify-end space-x-2 mt-1 text-sm">
+                        // Further along than saved, so only unmarking applied makes sense
+                        {(!applied).then(|| return view! {
+                            <ShortlistButton job_id=job.id.clone() list=Shortlist::Saved listed=saved />
+                        })}
+                        <ShortlistButton job_id=job.id.clone() list=Shortlist::Applied listed=applied />
+                    </div>
+                })}
             </div>
-        </a>
+        </div>
     };
 }
 
 #[component]
-pub fn JobPostingDetails(job: JobDetails) -> impl IntoView {
+pub fn JobPostingDetails(
+    job: JobDetails,
+    /// The viewer's notes on the job, when they're logged in
+    #[prop(optional_no_strip)]
+    notes: Option<JobNotes>,
+) -> impl IntoView {
+    let dates = [
+        job.posted_at
+            .as_deref()
+            .and_then(|at| return at.get(..10))
+            .map(|day| return format!("Posted {day}")),
+        job.valid_through
+            .as_deref()
+            .and_then(|at| return at.get(..10))
+            .map(|day| return format!("Apply by {day}")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ");
+
     return view! {
         <div id="job-details">
-            <p class="font-bold text-3xl">{job.title}</p>
-            <p>{job.description}</p>
+            <div class="flex justify-between items-start">
+                <p class="flex items-center gap-2">
+                    <span class="font-bold text-3xl">{job.title.clone()}</span>
+                    <StatusBadge status=job.status />
+                </p>
+                {job.saved.zip(job.applied).map(|(saved, applied)| return view! {
+                    <div class="flex space-x-2 shrink-0">
+                        // Further along than saved, so only unmarking applied makes sense
+                        {(!applied).then(|| return view! {
+                            <ShortlistButton job_id=job.id.clone() list=Shortlist::Saved listed=saved />
+                        })}
+                        <ShortlistButton job_id=job.id.clone() list=Shortlist::Applied listed=applied />
+                    </div>
+                })}
+            </div>
+            <p class="mb-2">
+                <CompanyLink name=job.company.clone() slug=job.company_slug.clone() />" · "{job.location.clone()}" · "
+                {job.employment_type.map(|t| return format!("{t} · "))}
+                {job.salary_range().map(|s| return s.to_string()).unwrap_or_else(|| return job.salary.clone())}
+                {job.salary_converted().map(|s| return view! {
+                    <span class="text-gray-400">" (≈ "{s.to_string()}")"</span>
+                })}
+                // Imported, so only trusted as far as being a web page
+                {job.url.clone().filter(|url| return url.starts_with("https://") || url.starts_with("http://")).map(|url| return view! {
+                    " · "<a href=url target="_blank" rel="noopener noreferrer" class="underline text-link">"View posting"</a>
+                })}
+            </p>
+            {(!dates.is_empty()).then(|| return view! {
+                <p class="mb-2 text-gray-400">{dates}</p>
+            })}
+            <p class="whitespace-pre-line">{job.description}</p>
+            <div hx-get=format!("/htmx/jobs/{}/duplicates", job.id) hx-trigger="load" hx-swap="outerHTML"></div>
+            {notes.map(|notes| return view! {
+                <div class="mt-4 pt-2 border-t border-gray-600">
+                    <JobTags job_id=notes.job_id.clone() tags=notes.tags />
+                    <JobNote job_id=notes.job_id.clone() note=notes.note />
+                    <div hx-get=format!("/htmx/jobs/{}/events", notes.job_id) hx-trigger="load" hx-swap="outerHTML"></div>
+                </div>
+            })}
+        </div>
+    };
+}
+
+/// Where a posting was found: the site it links to, else the company
+fn posting_site(posting: &Posting) -> String {
+    return posting
+        .url
+        .as_deref()
+        .and_then(|url| return reqwest::Url::parse(url).ok())
+        .filter(|url| return matches!(url.scheme(), "https" | "http"))
+        .and_then(|url| {
+            return url
+                .host_str()
+                .map(|host| return host.trim_start_matches("www.").to_owned());
+        })
+        .unwrap_or_else(|| return posting.company.clone());
+}
+
+/// Other postings of a job, and postings which may be, with buttons for
+/// logged in users to group or ungroup them for themselves
+#[component]
+pub fn JobDuplicates(duplicates: Duplicates, can_edit: bool) -> impl IntoView {
+    let url = format!("/htmx/jobs/{}/duplicates", duplicates.job_id);
+
+    return view! {
+        <div id="job-duplicates" class="mt-4">
+            {(!duplicates.group.is_empty()).then(|| return view! {
+                <p class="font-bold">"Also posted on…"</p>
+                <ul>
+                    {duplicates.group.into_iter().map(|posting| return view! {
+                        <li class="flex justify-between">
+                            <a href=format!("/jobs?currentJobId={}", posting.id) class="text-link">
+                                {posting_site(&posting)}
+                                <span class="text-gray-400">" · "{posting.title.clone()}" · "{posting.location.clone()}</span>
+                            </a>
+                            {can_edit.then(|| return view! {
+                                <button
+                                    hx-delete=url.clone()
+                                    hx-vals=serde_json::json!({ "job_id": posting.id }).to_string()
+                                    hx-target="#job-duplicates"
+                                    hx-swap="outerHTML"
+                                    class="ml-1 text-sm text-gray-400 hover:text-light"
+                                >
+                                    "Not a duplicate"
+                                </button>
+                            })}
+                        </li>
+                    }).collect_view()}
+                </ul>
+            })}
+            {(!duplicates.similar.is_empty()).then(|| return view! {
+                <p class="font-bold">"Similar postings"</p>
+                <ul>
+                    {duplicates.similar.into_iter().map(|posting| return view! {
+                        <li class="flex justify-between">
+                            <a href=format!("/jobs?currentJobId={}", posting.id) class="text-link">
+                                {posting.company.clone()}" · "{posting.title.clone()}
+                                <span class="text-gray-400">" · "{posting_site(&posting)}</span>
+                            </a>
+                            {can_edit.then(|| return view! {
+                                <button
+                                    hx-post=url.clone()
+                                    hx-vals=serde_json::json!({ "job_id": posting.id }).to_string()
+                                    hx-target="#job-duplicates"
+                                    hx-swap="outerHTML"
+                                    title="Same job, posted again"
+                                    class="ml-1 text-sm text-gray-400 hover:text-light"
+                                >
+                                    "Merge"
+                                </button>
+                            })}
+                        </li>
+                    }).collect_view()}
+                </ul>
+            })}
+        </div>
+    };
+}
+
+/// The viewer's tags on a job, each linking to the jobs sharing it
+#[component]
+pub fn JobTags(job_id: String, tags: Vec<String>) -> impl IntoView {
+    let url = format!("/htmx/jobs/{job_id}/tags");
+
+    return view! {
+        <div id="job-tags" class="flex flex-wrap items-center gap-1 mb-2 text-sm">
+            {tags.into_iter().map(|tag| return view! {
+                <span class="rounded-sm px-2 border border-link">
+                    <a
+                        href=format!("/jobs?{}", serde_urlencoded::to_string([("tag", &tag)]).expect("tag to serialize"))
+                        class="text-link"
+                    >
+                        {tag.clone()}
+                    </a>
+                    <button
+                        hx-delete=url.clone()
+                        hx-vals=serde_json::json!({ "tag": tag }).to_string()
+                        hx-target="#job-tags"
+                        hx-swap="outerHTML"
+                        title="Remove tag"
+                        class="ml-1 text-gray-400 hover:text-light"
+                    >
+                        "×"
+                    </button>
+                </span>
+            }).collect_view()}
+            <form hx-post=url hx-target="#job-tags" hx-swap="outerHTML">
+                <input
+                    type="text"
+                    name="tag"
+                    placeholder="Add tag"
+                    maxlength=MAX_TAG_LENGTH
+                    autocomplete="off"
+                    required
+                    class="w-28 rounded-sm px-2 bg-dark text-light placeholder-gray-500"
+                />
+            </form>
+        </div>
+    };
+}
+
+/// The viewer's note on a job, rendered from markdown
+#[component]
+pub fn JobNote(job_id: String, note: String) -> impl IntoView {
+    return view! {
+        <div id="job-note">
+            <div class="flex justify-between items-center">
+                <p class="font-bold">Notes</p>
+                <button
+                    hx-get=format!("/htmx/jobs/{job_id}/note/edit")
+                    hx-target="#job-note"
+                    hx-swap="outerHTML"
+                    class="rounded-sm px-2 border border-accent text-sm"
+                >
+                    Edit
+                </button>
+            </div>
+            {match note.trim().is_empty() {
+                true => view! { <p class="text-gray-400">"No notes yet"</p> }.into_view(),
+                false => view! {
+                    <div class="prose prose-invert max-w-none" inner_html=markdown::render(&note)></div>
+                }.into_view(),
+            }}
+        </div>
+    };
+}
+
+/// Edits the viewer's note on a job in place of [`JobNote`]
+#[component]
+pub fn JobNoteEditor(job_id: String, note: String) -> impl IntoView {
+    return view! {
+        <form id="job-note" hx-put=format!("/htmx/jobs/{job_id}/note") hx-target="this" hx-swap="outerHTML">
+            <p class="font-bold">Notes</p>
+            <textarea
+                name="body"
+                rows="8"
+                placeholder="Markdown is supported"
+                class="w-full rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500"
+            >
+                {note}
+            </textarea>
+            <div class="flex justify-end space-x-2 text-sm">
+                <button
+                    type="button"
+                    hx-get=format!("/htmx/jobs/{job_id}/note")
+                    hx-target="#job-note"
+                    hx-swap="outerHTML"
+                    class="rounded-sm px-2 border border-accent"
+                >
+                    Cancel
+                </button>
+                <button type="submit" class="rounded-sm px-2 text-[#FFFCF2] bg-accent">Save</button>
+            </div>
+        </form>
+    };
+}
+
+/// Adds a job to, or removes it from, one of the user's lists
+#[component]
+pub fn ShortlistButton(job_id: String, list: Shortlist, listed: bool) -> impl IntoView {
+    let url = format!("/htmx/jobs/{job_id}/{}", list.slug());
+    let label = match (list, listed) {
+        (Shortlist::Saved, false) => "Save",
+        (Shortlist::Saved, true) => "Saved",
+        (Shortlist::Applied, false) => "Mark applied",
+        (Shortlist::Applied, true) => "Applied",
+    };
+
+    return view! {
+        <button
+            hx-post=(!listed).then(|| return url.clone())
+            hx-delete=listed.then_some(url)
+            // Don't also open the job, when inside its summary
+            hx-trigger="click consume"
+            // Rather than the job details targeted by its summary
+            hx-target="this"
+            hx-swap="outerHTML"
+            class="rounded-sm px-2 border border-accent"
+            class=("bg-accent", listed)
+            class=("text-[#FFFCF2]", listed)
+        >
+            {label}
+        </button>
+    };
+}
+
+/// When an event is, in the user's own time zone
+fn describe_when(event: &CalendarEvent) -> String {
+    return match (&event.start_time, event.duration_minutes) {
+        (Some(time), Some(minutes)) => format!("{} {time} ({minutes} min)", event.day),
+        (Some(time), None) => format!("{} {time}", event.day),
+        (None, _) => format!("{} (all day)", event.day),
+    };
+}
+
+/// The user's calendar events for a job, with a form to add another
+#[component]
+pub fn JobEvents(
+    job_id: String,
+    events: Vec<CalendarEvent>,
+    /// Why the last event submitted couldn't be added
+    error: Option<&'static str>,
+) -> impl IntoView {
+    return view! {
+        <div id="job-events" class="mt-4">
+            <p class="font-bold">Calendar</p>
+            <ul>
+                {events.into_iter().map(|event| return view! {
+                    <li class="flex justify-between" class=("text-gray-400", event.past)>
+                        <span>
+                            {describe_when(&event)}" · "{event.kind.to_string()}
+                            {(!event.notes.is_empty()).then(|| return view! {
+                                <span class="text-gray-400">" · "{event.notes.clone()}</span>
+                            })}
+                        </span>
+                        <button
+                            hx-delete=format!("/htmx/events/{}", event.id)
+                            hx-target="#job-events"
+                            hx-swap="outerHTML"
+                            title="Remove event"
+                            class="ml-1 text-gray-400 hover:text-light"
+                        >
+                            "×"
+                        </button>
+                    </li>
+                }).collect_view()}
+            </ul>
+            {error.map(|e| return view! { <p class="text-accent">{e}</p> })}
+            <form
+                hx-post=format!("/htmx/jobs/{job_id}/events")
+                hx-target="#job-events"
+                hx-swap="outerHTML"
+                class="flex flex-wrap gap-1 mt-1 text-sm"
+            >
+                <select name="kind" class="rounded-sm bg-dark text-light">
+                    {EventKind::ALL.into_iter().map(|kind| return view! {
+                        <option value=kind.slug()>{kind.to_string()}</option>
+                    }).collect_view()}
+                </select>
+                <input type="date" name="day" required class="rounded-sm px-2 bg-dark text-light" />
+                <input type="time" name="start_time" title="Leave empty for all day" class="rounded-sm px-2 bg-dark text-light" />
+                <input
+                    type="number"
+                    name="duration_minutes"
+                    min="1"
+                    max="1440"
+                    placeholder="Minutes"
+                    class="w-20 rounded-sm px-2 bg-dark text-light placeholder-gray-500"
+                />
+                <input
+                    type="text"
+                    name="notes"
+                    placeholder="Notes"
+                    autocomplete="off"
+                    class="grow rounded-sm px-2 bg-dark text-light placeholder-gray-500"
+                />
+                <button type="submit" class="rounded-sm px-2 text-[#FFFCF2] bg-accent">Add</button>
+            </form>
+        </div>
+    };
+}
+
+/// Every event on the user's calendar, and how to subscribe to them
+#[component]
+pub fn CalendarPage(
+    events: Vec<CalendarEvent>,
+    /// Whether the user has created a feed URL before
+    has_feed: bool,
+    /// The feed URL, only known right after it's created
+    feed_url: Option<String>,
+) -> impl IntoView {
+    let (past, upcoming): (Vec<_>, Vec<_>) = events.into_iter().partition(|e| return e.past);
+
+    let event_list = |events: Vec<CalendarEvent>, empty: &'static str| {
+        if events.is_empty() {
+            return view! { <p class="text-gray-400">{empty}</p> }.into_view();
+        }
+
+        return events.into_iter().map(|event| return view! {
+            <div class="flex bg-dark rounded-sm p-2 mb-1">
+                <p class="basis-64 shrink-0">{describe_when(&event)}</p>
+                <div class="grow">
+                    <p>
+                        <span class="rounded-sm px-2 text-[#FFFCF2] bg-accent mr-2">{event.kind.to_string()}</span>
+                        <a href=format!("/jobs?currentJobId={}", event.job_id) class="underline text-link font-bold">{event.title}</a>
+                        " · "{event.company}
+                    </p>
+                    {(!event.notes.is_empty()).then(|| return view! {
+                        <p class="text-gray-400">{event.notes}</p>
+                    })}
+                </div>
+            </div>
+        }).collect_view();
+    };
+
+    return view! {
+        <div class="flex h-full">
+            <div class="basis-full mr-2 overflow-auto h-full">
+                <div class="bg-dark-weak rounded-sm p-2 mb-2">
+                    <p class="font-bold text-2xl mb-1">Upcoming</p>
+                    {event_list(upcoming, "Nothing coming up. Add interviews and deadlines from a job's details.")}
+                </div>
+                <div class="bg-dark-weak rounded-sm p-2 text-gray-400">
+                    <p class="font-bold text-2xl mb-1">Past</p>
+                    {event_list(past, "Nothing yet")}
+                </div>
+            </div>
+            <div class="basis-80 shrink-0 bg-dark-weak rounded-sm p-2 h-fit">
+                <p class="font-bold">Subscribe</p>
+                {match feed_url {
+                    Some(url) => view! {
+                        <p class="text-sm">"Add this link to your calendar app. It won't be shown again, so keep it somewhere safe:"</p>
+                        <a href=url.clone() class="underline text-link break-all">{url}</a>
+                    }.into_view(),
+                    None => view! {
+                        <p class="text-sm text-gray-400">
+                            {match has_feed {
+                                true => "Lost your calendar link? Creating a new one stops the old one from working.",
+                                false => "Get a private link to see these events in your calendar app.",
+                            }}
+                        </p>
+                    }.into_view(),
+                }}
+                <form action="/calendar/feed" method="post" class="mt-2">
+                    <button type="submit" class="rounded-sm px-2 border border-accent">
+                        {match has_feed {
+                            true => "Create new link",
+                            false => "Create link",
+                        }}
+                    </button>
+                </form>
+            </div>
+        </div>
+    };
+}
+
+pub struct PipelineColumn {
+    pub stage: Stage,
+    /// The jobs in this stage, each with its stage history
+    pub jobs: Vec<(JobDetails, Vec<StageChange>)>,
+}
+
+/// Kanban board of a user's applications, one column per stage
+#[component]
+pub fn PipelineBoard(columns: Vec<PipelineColumn>) -> impl IntoView {
+    return view! {
+        <div id="pipeline" class="grid grid-cols-7 gap-2 h-full">
+            {columns.into_iter().map(|column| return view! {
+                <div class="bg-dark-weak rounded-sm p-2 overflow-auto h-full">
+                    <p class="font-bold flex justify-between mb-1">
+                        <span>{column.stage.to_string()}</span>
+                        <span class="text-gray-400">{column.jobs.len()}</span>
+                    </p>
+                    {column.jobs.into_iter().map(|(job, history)| return view! {
+                        <PipelineCard job=job history=history />
+                    }).collect_view()}
+                </div>
+            }).collect_view()}
+        </div>
+    };
+}
+
+fn describe_change(change: &StageChange) -> String {
+    return match (change.from_stage, change.to_stage) {
+        (None, Some(to)) => format!("Added as {to}"),
+        (Some(from), Some(to)) => format!("{from} → {to}"),
+        (Some(from), None) => format!("Removed from {from}"),
+        (None, None) => String::new(),
+    };
+}
+
+#[component]
+fn PipelineCard(job: JobDetails, history: Vec<StageChange>) -> impl IntoView {
+    return view! {
+        <div class="bg-dark rounded-sm p-2 mb-1 text-sm">
+            <a href=format!("/jobs?currentJobId={}", job.id) class="underline text-link font-bold">{job.title}</a>
+            <p class="flex justify-between">
+                <CompanyLink name=job.company slug=job.company_slug />
+                <StatusBadge status=job.status />
+            </p>
+            <select
+                name="stage"
+                hx-post=format!("/htmx/pipeline/{}", job.id)
+                hx-trigger="change"
+                hx-target="#pipeline"
+                hx-swap="outerHTML"
+                class="w-full mt-1 rounded-sm bg-dark-weak text-light"
+            >
+                {Stage::ALL.into_iter().map(|stage| return view! {
+                    <option value=stage.slug() selected=job.stage == Some(stage)>{stage.to_string()}</option>
+                }).collect_view()}
+            </select>
+            <details class="mt-1 text-gray-400">
+                <summary class="cursor-pointer">History</summary>
+                <ul>
+                    {history.into_iter().map(|change| return view! {
+                        <li>{change.changed_at.clone()}": "{describe_change(&change)}</li>
+                    }).collect_view()}
+                </ul>
+            </details>
+        </div>
+    };
+}
+
+#[component]
+fn AuthForm(
+    title: &'static str,
+    /// Where the form is submitted
+    action: &'static str,
+    /// Autocomplete hint for the password field
+    password_autocomplete: &'static str,
+    /// Previously submitted username, kept when the form is shown again
+    username: String,
+    error: Option<&'static str>,
+    children: Children,
+) -> impl IntoView {
+    return view! {
+        <div class="h-full flex justify-center">
+            <form action=action method="post" class="w-80 mt-8 bg-dark-weak rounded-sm p-4 flex flex-col space-y-3">
+                <p class="font-bold text-3xl">{title}</p>
+                {error.map(|e| return view! { <p class="text-accent">{e}</p> })}
+                <input
+                    type="text"
+                    name="username"
+                    value=username
+                    placeholder="Username"
+                    autocomplete="username"
+                    required
+                    class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500"
+                />
+                <input
+                    type="password"
+                    name="password"
+                    placeholder="Password"
+                    autocomplete=password_autocomplete
+                    required
+                    class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500"
+                />
+                <button type="submit" class="rounded-sm px-3 py-1 text-[#FFFCF2] bg-accent">{title}</button>
+                <p class="text-sm text-gray-400">{children()}</p>
+            </form>
+        </div>
+    };
+}
+
+#[component]
+pub fn LoginForm(username: String, error: Option<&'static str>) -> impl IntoView {
+    return view! {
+        <AuthForm title="Login" action="/login" password_autocomplete="current-password" username=username error=error>
+            "No account yet? "<a href="/register" class="underline text-link">Register</a>
+        </AuthForm>
+    };
+}
+
+#[component]
+pub fn RegisterForm(username: String, error: Option<&'static str>) -> impl IntoView {
+    return view! {
+        <AuthForm title="Register" action="/register" password_autocomplete="new-password" username=username error=error>
+            "Already have an account? "<a href="/login" class="underline text-link">Login</a>
+        </AuthForm>
+    };
+}
+
+/// Form to track jobs from a career page's link or a saved copy of it
+#[component]
+pub fn ImportPage(
+    /// Previously submitted link, kept when the form is shown again
+    url: String,
+    error: Option<String>,
+) -> impl IntoView {
+    return view! {
+        <div class="h-full flex justify-center">
+            <form action="/import" method="post" enctype="multipart/form-data" class="w-[32rem] mt-8 bg-dark-weak rounded-sm p-4 flex flex-col space-y-3">
+                <p class="font-bold text-3xl">Import a job</p>
+                <p class="text-sm text-gray-400">
+                    "Paste the link to a job posting, or upload the page saved from your browser. "
+                    "Jobs described with schema.org JobPosting data are added to your saved jobs."
+                </p>
+                {error.map(|e| return view! { <p class="text-accent">{e}</p> })}
+                <input
+                    type="url"
+                    name="url"
+                    value=url
+                    placeholder="https://example.com/careers/1234"
+                    autocomplete="off"
+                    class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500"
+                />
+                <input type="file" name="file" accept=".html,.htm,text/html" class="text-sm text-gray-400" />
+                <button type="submit" class="rounded-sm px-3 py-1 text-[#FFFCF2] bg-accent">Import</button>
+            </form>
+        </div>
+    };
+}
+
+/// A company's profile, its open jobs and the viewer's applications there
+#[component]
+pub fn CompanyPage(
+    company: Company,
+    jobs: Vec<JobDetails>,
+    /// The viewer's jobs at the company in their pipeline, with how they moved
+    applications: Vec<(JobDetails, Vec<StageChange>)>,
+    /// What the edit form is filled in with, for admins
+    form: Option<CompanyProfile>,
+    /// Why the profile last submitted couldn't be saved
+    error: Option<&'static str>,
+) -> impl IntoView {
+    let profile = company.profile;
+    let action = format!("/companies/{}", company.slug);
+
+    return view! {
+        <div class="overflow-auto h-full">
+            <div class="bg-dark-weak rounded-sm p-2 mb-2 flex">
+                <CompanyLogo name=company.name.clone() domain=profile.domain.clone() logo_url=profile.logo_url.clone() />
+                <div class="grow">
+                    <p class="font-bold text-3xl">{company.name.clone()}</p>
+                    <p class="flex flex-wrap gap-x-3 mb-2 text-gray-400">
+                        {profile.headquarters.clone().map(|hq| return view! { <span>{hq}</span> })}
+                        {profile.size.clone().map(|size| return view! { <span>{size}</span> })}
+                        {profile.domain.clone().map(|domain| return view! {
+                            <a href=format!("https://{domain}") target="_blank" rel="noopener noreferrer" class="underline text-link">{domain.clone()}</a>
+                        })}
+                        {profile.careers_url.clone().map(|url| return view! {
+                            <a href=url target="_blank" rel="noopener noreferrer" class="underline text-link">"Careers"</a>
+                        })}
+                    </p>
+                    <p class="whitespace-pre-line">{profile.description.clone()}</p>
+                </div>
+            </div>
+            <div class="bg-dark-weak rounded-sm p-2 mb-2">
+                <p class="font-bold text-2xl mb-1">Open jobs</p>
+                {jobs.is_empty().then(|| return view! {
+                    <p class="text-gray-400">"No open jobs right now."</p>
+                })}
+                <ul>
+                    {jobs.into_iter().map(|job| return view! {
+                        <li class="py-1 border-t border-gray-600">
+                            <a href=format!("/jobs?currentJobId={}", job.id) class="underline text-link font-bold">{job.title.clone()}</a>
+                            <span class="text-gray-400">
+                                " · "{job.location.clone()}
+                                {job.salary_range().map(|s| return format!(" · {}", s.annualized()))}
+                            </span>
+                        </li>
+                    }).collect_view()}
+                </ul>
+            </div>
+            {(!applications.is_empty()).then(|| return view! {
+                <div class="bg-dark-weak rounded-sm p-2 mb-2">
+                    <p class="font-bold text-2xl mb-1">Your applications</p>
+                    <ul>
+                        {applications.into_iter().map(|(job, history)| return view! {
+                            <li class="py-1 border-t border-gray-600">
+                                <p class="flex items-center gap-2">
+                                    <a href=format!("/jobs?currentJobId={}", job.id) class="underline text-link font-bold">{job.title.clone()}</a>
+                                    <StatusBadge status=job.status />
+                                    <span class="grow text-right">{job.stage.map(|stage| return stage.to_string())}</span>
+                                </p>
+                                <ul class="text-sm text-gray-400">
+                                    {history.into_iter().map(|change| return view! {
+                                        <li>{change.changed_at.clone()}": "{describe_change(&change)}</li>
+                                    }).collect_view()}
+                                </ul>
+                            </li>
+                        }).collect_view()}
+                    </ul>
+                </div>
+            })}
+            {form.map(|form| return view! {
+                <form action=action method="post" class="bg-dark-weak rounded-sm p-2 mb-2 flex flex-col space-y-2">
+                    <p class="font-bold text-2xl">Edit profile</p>
+                    {error.map(|e| return view! { <p class="text-accent">{e}</p> })}
+                    <input type="text" name="domain" value=form.domain placeholder="Domain, e.g. example.com" autocomplete="off" class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500" />
+                    <input type="text" name="headquarters" value=form.headquarters placeholder="Headquarters" autocomplete="off" class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500" />
+                    <input type="text" name="size" value=form.size placeholder="Size, e.g. 51-200 employees" autocomplete="off" class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500" />
+                    <input type="url" name="careers_url" value=form.careers_url placeholder="Careers page" autocomplete="off" class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500" />
+                    <input type="url" name="logo_url" value=form.logo_url placeholder="Logo" autocomplete="off" class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500" />
+                    <textarea name="description" rows="5" placeholder="Description" class="rounded-sm px-3 py-1 bg-dark text-light placeholder-gray-500">{form.description}</textarea>
+                    <button type="submit" class="self-start rounded-sm px-3 py-1 text-[#FFFCF2] bg-accent">Save</button>
+                </form>
+            })}
+        </div>
+    };
+}
+
+/// The sources imported in the background and how their latest runs went
+#[component]
+pub fn ImportsPage(sources: Vec<Source>, runs: Vec<ImportRun>) -> impl IntoView {
+    return view! {
+        <div class="overflow-auto h-full">
+            <div class="bg-dark-weak rounded-sm p-2 mb-2">
+                <p class="font-bold text-2xl mb-1">Sources</p>
+                {sources.is_empty().then(|| return view! {
+                    <p class="text-gray-400">"No sources yet. Add them with "<code>"wantjob sources add"</code>.</p>
+                })}
+                <table class="w-full text-left">
+                    {sources.into_iter().map(|source| return view! {
+                        <tr class="border-t border-gray-600">
+                            <td class="py-1">{source.kind.to_string()}</td>
+                            <td class="break-all">{source.board}</td>
+                            <td>{source.company}</td>
+                            <td>{format!("Every {} min", source.interval_minutes)}</td>
+                            <td class:text-gray-400=!source.enabled>
+                                {match (source.enabled, source.next_run_at) {
+                                    (false, _) => "Disabled".to_owned(),
+                                    (true, Some(at)) => format!("Next {at}"),
+                                    (true, None) => "Due".to_owned(),
+                                }}
+                            </td>
+                            <td class:text-accent={source.failures > 0}>
+                                {(source.failures > 0).then(|| return format!("{} failed in a row", source.failures))}
+                            </td>
+                            <td class="text-right">
+                                <form action=format!("/admin/sources/{}/run", source.id) method="post">
+                                    <button type="submit" class="rounded-sm px-2 border border-accent">Run now</button>
+                                </form>
+                            </td>
+                        </tr>
+                    }).collect_view()}
+                </table>
+            </div>
+            <div class="bg-dark-weak rounded-sm p-2">
+                <p class="font-bold text-2xl mb-1">Recent runs</p>
+                {runs.is_empty().then(|| return view! { <p class="text-gray-400">Nothing yet</p> })}
+                <table class="w-full text-left">
+                    {runs.into_iter().map(|run| return view! {
+                        <tr class="border-t border-gray-600 align-top">
+                            <td class="py-1">{run.started_at}</td>
+                            <td>{format!("{} {}", run.kind, run.board)}</td>
+                            <td class="text-gray-400">{run.finished_at.map(|at| return format!("Finished {at}"))}</td>
+                            <td>
+                                {match (run.jobs_imported, run.error) {
+                                    (_, Some(error)) => view! { <span class="text-accent">{error}</span> }.into_view(),
+                                    (Some(count), None) => format!("{count} jobs").into_view(),
+                                    (None, None) => "Running".into_view(),
+                                }}
+                            </td>
+                        </tr>
+                    }).collect_view()}
+                </table>
+            </div>
         </div>
     };
 }
@@ -194,3 +1197,14 @@ pub fn HomePageDetails() -> impl IntoView {
         <p class="font-bold text-4xl text-center">Welcome to WantJob!</p>
     };
 }
+
+#[component]
+pub fn ErrorDetails(status: StatusCode, message: &'static str) -> impl IntoView {
+    return view! {
+        <div id="job-details" class="p-4 text-center">
+            <p class="font-bold text-4xl">{status.as_u16()}" "{status.canonical_reason()}</p>
+            <p class="mt-2">{message}</p>
+            <a href="/jobs" class="underline text-link">Back to jobs</a>
+        </div>
+    };
+}
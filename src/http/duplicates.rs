@@ -0,0 +1,90 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Form, Router,
+};
+use leptos::view;
+use serde::Deserialize;
+
+use super::{
+    auth::CurrentUser,
+    components::{htmlify, JobDuplicates},
+    error::AppError,
+    AppState,
+};
+use crate::db::{duplicates, users::User};
+
+pub fn router() -> Router<AppState> {
+    return Router::new().route(
+        "/htmx/jobs/:id/duplicates",
+        get(htmx_job_duplicates)
+            .post(htmx_job_duplicate_merge)
+            .delete(htmx_job_duplicate_split),
+    );
+}
+
+#[derive(Deserialize)]
+struct DuplicateForm {
+    job_id: String,
+}
+
+/// The job's duplicates as the user grouped them, which they can regroup
+async fn job_duplicates_fragment(
+    state: &AppState,
+    user: Option<&User>,
+    job_id: &str,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = user.map(|user| return user.id.as_str());
+    let duplicates = duplicates::duplicates(&state.db, user_id, job_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let can_edit = user.is_some();
+
+    let h = htmlify(move || {
+        return view! {
+            <JobDuplicates duplicates=duplicates can_edit=can_edit />
+        };
+    });
+
+    return Ok((StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h));
+}
+
+async fn htmx_job_duplicates(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    return job_duplicates_fragment(&state, user.as_ref(), &id).await;
+}
+
+async fn htmx_job_duplicate_merge(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+    Form(form): Form<DuplicateForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    if !duplicates::merge(&state.db, &user.id, &id, &form.job_id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    return job_duplicates_fragment(&state, Some(&user), &id).await;
+}
+
+async fn htmx_job_duplicate_split(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+    Query(form): Query<DuplicateForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    if !duplicates::split(&state.db, &user.id, &form.job_id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    return job_duplicates_fragment(&state, Some(&user), &id).await;
+}
@@ -0,0 +1,124 @@
+use std::any::Any;
+
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_htmx::HX_REQUEST;
+use leptos::view;
+use tracing::{error, Span};
+
+use super::components::{htmlify, ErrorDetails, Layout};
+
+pub enum AppError {
+    BadRequest,
+    Unauthorized,
+    NotFound,
+    Internal(anyhow::Error),
+}
+
+impl AppError {
+    fn status(&self) -> StatusCode {
+        return match self {
+            AppError::BadRequest => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+    }
+
+    fn error_type(&self) -> &'static str {
+        return match self {
+            AppError::BadRequest => "bad_request",
+            AppError::Unauthorized => "unauthorized",
+            AppError::NotFound => "not_found",
+            AppError::Internal(_) => "internal_error",
+        };
+    }
+
+    fn message(&self) -> &'static str {
+        return match self {
+            AppError::BadRequest => "That request didn't make sense to us.",
+            AppError::Unauthorized => "You need to log in to do that.",
+            AppError::NotFound => "We couldn't find what you were looking for.",
+            AppError::Internal(_) => "Something went wrong on our end.",
+        };
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        return AppError::Internal(err);
+    }
+}
+
+/// Marker left on error responses so [`render_errors`] knows to render them
+/// once it can see whether the request came from htmx.
+#[derive(Clone)]
+struct ErrorPage {
+    status: StatusCode,
+    message: &'static str,
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let span = Span::current();
+        span.record("error.type", self.error_type());
+
+        if let AppError::Internal(err) = &self {
+            span.record("otel.status_code", "ERROR");
+            error!("{:#}", err);
+        }
+
+        let mut response = self.status().into_response();
+        response.extensions_mut().insert(ErrorPage {
+            status: self.status(),
+            message: self.message(),
+        });
+
+        return response;
+    }
+}
+
+pub fn handle_panic(_err: Box<dyn Any + Send + 'static>) -> Response {
+    let response = AppError::Internal(anyhow::anyhow!("handler panicked")).into_response();
+    Span::current().record("error.type", "panic");
+
+    return response;
+}
+
+pub async fn render_errors(request: Request, next: Next) -> Response {
+    let is_htmx = request.uri().path().starts_with("/htmx/")
+        || request
+            .headers()
+            .get(HX_REQUEST)
+            .is_some_and(|v| return v == "true");
+
+    let response = next.run(request).await;
+
+    let Some(page) = response.extensions().get::<ErrorPage>().cloned() else {
+        return response;
+    };
+
+    let h = if is_htmx {
+        htmlify(move || {
+            return view! {
+                <ErrorDetails status=page.status message=page.message />
+            };
+        })
+    } else {
+        htmlify(move || {
+            return view! {
+                <Layout>
+                    <div class="h-full flex justify-center">
+                        <ErrorDetails status=page.status message=page.message />
+                    </div>
+                </Layout>
+            };
+        })
+    };
+
+    return (page.status, [(header::CONTENT_TYPE, "text/html")], h).into_response();
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::Serialize;
+use tracing::warn;
+
+use super::AppState;
+use crate::{
+    build_info::{BuildInfo, BUILD_INFO},
+    db::{self, migrate},
+};
+
+pub fn router() -> Router<AppState> {
+    return Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version));
+}
+
+#[derive(Serialize)]
+struct Health {
+    status: &'static str,
+    /// What was checked and how it went, `ok` if it's fine
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    checks: BTreeMap<&'static str, String>,
+}
+
+/// Whether the process is up at all
+async fn healthz() -> Json<Health> {
+    return Json(Health {
+        status: "ok",
+        checks: BTreeMap::new(),
+    });
+}
+
+/// Whether the site can serve requests: its database is reachable and up to
+/// date, its background workers are running and it isn't shutting down
+async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Health>) {
+    let mut checks = BTreeMap::new();
+
+    let database = match db::ping(&state.db).await {
+        Ok(()) => "ok".to_owned(),
+        Err(err) => {
+            warn!("Not ready: {err:#}");
+            "unreachable".to_owned()
+        }
+    };
+    checks.insert("database", database);
+
+    let migrations = match migrate::pending(&state.db).await {
+        Ok(0) => "ok".to_owned(),
+        Ok(pending) => format!("{pending} pending"),
+        Err(err) => {
+            warn!("Not ready: {err:#}");
+            "unknown".to_owned()
+        }
+    };
+    checks.insert("migrations", migrations);
+
+    for (name, worker) in state.workers.iter() {
+        let status = match worker.is_finished() {
+            true => "stopped",
+            false => "ok",
+        };
+        checks.insert(name, status.to_owned());
+    }
+
+    if state.stop.is_cancelled() {
+        checks.insert("shutdown", "shutting down".to_owned());
+    }
+
+    let ready = checks.values().all(|status| return status == "ok");
+    let (status, code) = match ready {
+        true => ("ok", StatusCode::OK),
+        false => ("unavailable", StatusCode::SERVICE_UNAVAILABLE),
+    };
+
+    return (code, Json(Health { status, checks }));
+}
+
+async fn version() -> Json<BuildInfo> {
+    return Json(BUILD_INFO);
+}
@@ -0,0 +1,160 @@
+use axum::{
+    extract::{Multipart, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use leptos::view;
+use tracing::warn;
+
+use super::{
+    auth::CurrentUser,
+    components::{htmlify, ImportPage, Layout},
+    error::AppError,
+    AppState,
+};
+use crate::{
+    db::{
+        jobs::{self, NewJob},
+        shortlists::{self, Shortlist},
+        users::User,
+    },
+    ingest::json_ld,
+};
+
+pub fn router() -> Router<AppState> {
+    return Router::new().route("/import", get(import_page).post(import));
+}
+
+fn import_response(status: StatusCode, user: User, url: String, error: Option<String>) -> Response {
+    let h = htmlify(move || {
+        return view! {
+            <Layout user=Some(user)>
+                <ImportPage url=url error=error />
+            </Layout>
+        };
+    });
+
+    return (status, [(header::CONTENT_TYPE, "text/html")], h).into_response();
+}
+
+async fn import_page(CurrentUser(user): CurrentUser) -> Response {
+    let Some(user) = user else {
+        return Redirect::to("/login").into_response();
+    };
+    return import_response(StatusCode::OK, user, String::new(), None);
+}
+
+/// The form's link and uploaded page, either of which may be empty
+async fn read_form(mut multipart: Multipart) -> Result<(String, Vec<u8>), AppError> {
+    let mut url = String::new();
+    let mut file = Vec::new();
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| return AppError::BadRequest)?
+    {
+        match field.name() {
+            Some("url") => {
+                url = field
+                    .text()
+                    .await
+                    .map_err(|_| return AppError::BadRequest)?;
+            }
+            Some("file") => {
+                file = field
+                    .bytes()
+                    .await
+                    .map_err(|_| return AppError::BadRequest)?
+                    .to_vec();
+            }
+            _ => {}
+        }
+    }
+
+    return Ok((url.trim().to_owned(), file));
+}
+
+/// Tracks the postings on an uploaded or linked page as saved jobs
+async fn import(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    multipart: Multipart,
+) -> Result<Response, AppError> {
+    let Some(user) = user else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+    let (url, file) = read_form(multipart).await?;
+
+    let postings = if !file.is_empty() {
+        json_ld::extract(&String::from_utf8_lossy(&file), None)
+    } else if url.is_empty() {
+        return Ok(import_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            user,
+            url,
+            Some("Paste a link or pick a file to import.".to_owned()),
+        ));
+    } else if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Ok(import_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            user,
+            url,
+            Some("Links start with http:// or https://.".to_owned()),
+        ));
+    } else {
+        match json_ld::fetch(state.http.as_ref(), &url).await {
+            Ok(postings) => postings,
+            Err(err) => {
+                warn!("Could not fetch page to import: {err:#}");
+                return Ok(import_response(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    user,
+                    url,
+                    Some(
+                        "We couldn't load that page. Try saving it and uploading the file."
+                            .to_owned(),
+                    ),
+                ));
+            }
+        }
+    };
+
+    let postings = postings
+        .into_iter()
+        .map(|posting| {
+            return NewJob {
+                id: json_ld::uploaded_id(&posting.id, &user.id),
+                ..posting
+            };
+        })
+        .collect::<Vec<_>>();
+
+    if postings.is_empty() {
+        return Ok(import_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            user,
+            url,
+            Some("We didn't find a job posting on that page.".to_owned()),
+        ));
+    }
+
+    for posting in &postings {
+        if !jobs::upsert_user_job(&state.db, posting).await? {
+            warn!(
+                job.id = posting.id,
+                "Not replacing a job imported from a source"
+            );
+            continue;
+        }
+        shortlists::add(&state.db, Shortlist::Saved, &user.id, &posting.id).await?;
+    }
+
+    return Ok(match postings.as_slice() {
+        [posting] => Redirect::to(&format!("/saved?currentJobId={}", posting.id)),
+        _ => Redirect::to("/saved"),
+    }
+    .into_response());
+}
@@ -1,160 +1,659 @@
 use axum::{
     extract::{Path, Query, State},
     http::{header, StatusCode},
-    response::{IntoResponse, Redirect},
-    routing::get,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
     Router,
 };
-use leptos::{view, CollectView};
-use serde::Deserialize;
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
+use axum_htmx::HxPushUrl;
+use leptos::view;
+use serde::{Deserialize, Serialize};
 
 use super::{
-    components::{htmlify, JobPostingDetails, JobSummary, Layout},
+    auth::CurrentUser,
+    components::{
+        htmlify, FacetOption, FacetSection, FilterSidebar, JobList, JobListPage, JobPanes,
+        JobPostingDetails, Layout, ShortlistButton,
+    },
+    error::AppError,
     AppState,
 };
-use crate::http::components::HomePageDetails;
+use crate::{
+    currency::DEFAULT_CURRENCY,
+    db::{
+        jobs::{
+            self, JobCursor, JobDetails, JobFacets, JobFilter, JobPage, JobSort, Seniority, Viewer,
+            WorkMode,
+        },
+        notes::{self, JobNotes},
+        rates,
+        shortlists::{self, Shortlist},
+        users::User,
+    },
+};
+
+/// Jobs rendered per page of the job list
+const PAGE_SIZE: i64 = 30;
+
+/// Remembers the display currency last picked
+const CURRENCY_COOKIE: &str = "currency";
 
 pub fn router() -> Router<AppState> {
     return Router::new()
         .route("/", get(|| async { Redirect::temporary("/jobs") }))
         .route("/jobs", get(job_handler))
-        .route("/htmx/jobs/details/:id", get(htmx_jobs_details));
-}
-
-#[derive(Clone)]
-pub struct JobDetails {
-    pub id: String,
-    pub title: String,
-    pub company: String,
-    pub location: String,
-    pub salary: String,
-    pub description: String,
+        .route("/htmx/jobs/list", get(htmx_jobs_list))
+        .route("/htmx/jobs/page", get(htmx_jobs_page))
+        .route("/htmx/jobs/details/:id", get(htmx_jobs_details))
+        .route("/saved", get(saved_handler))
+        .route("/applied", get(applied_handler))
+        .route(
+            "/htmx/jobs/:id/:list",
+            post(htmx_shortlist_add).delete(htmx_shortlist_remove),
+        );
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct JobQueryParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
     current_job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    q: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    company: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_salary: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    work_mode: Option<WorkMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seniority: Option<Seniority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    posted_within: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    closed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort: Option<JobSort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    list: Option<Shortlist>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cursor: Option<String>,
 }
 
-async fn job_handler(
-    State(_state): State<AppState>,
-    query_params: Query<JobQueryParams>,
-) -> impl IntoResponse {
-    let jobs = mock_jobs(30);
-
-    let job = query_params.current_job_id.is_some().then(|| {
-        get_mock_job(
-            query_params
-                .current_job_id
-                .clone()
-                .expect("the current job id to be some"),
+impl JobQueryParams {
+    /// Drops empty values submitted by the search form
+    fn normalized(mut self) -> Self {
+        self.q = self.q.filter(|q| return !q.trim().is_empty());
+        return self;
+    }
+
+    /// These params without any per-view state, i.e. just the filters
+    fn listing(&self) -> JobQueryParams {
+        return JobQueryParams {
+            current_job_id: None,
+            cursor: None,
+            ..self.clone()
+        };
+    }
+
+    fn filter(&self, viewer: Viewer) -> JobFilter {
+        return JobFilter {
+            viewer,
+            shortlist: self.list,
+            tag: self.tag.clone(),
+            query: self.q.clone(),
+            location: self.location.clone(),
+            company: self.company.clone(),
+            min_salary: self.min_salary,
+            work_mode: self.work_mode,
+            seniority: self.seniority,
+            posted_within_days: self.posted_within,
+            include_closed: self.closed.unwrap_or(false),
+            sort: self.sort,
+        };
+    }
+
+    /// The active filters (everything but the search and selected job) as
+    /// form fields, so the search box can submit them alongside `q`
+    fn filter_fields(&self) -> Vec<(String, String)> {
+        let params = JobQueryParams {
+            q: None,
+            ..self.listing()
+        };
+
+        return serde_urlencoded::from_str(
+            &serde_urlencoded::to_string(params).expect("query params to serialize"),
         )
-    });
+        .expect("serialized query params to parse");
+    }
+
+    /// A link toggling `field` to `value`, leaving every other filter as is
+    fn facet_option<T: PartialEq>(
+        &self,
+        label: String,
+        count: Option<i64>,
+        value: T,
+        field: impl Fn(&mut JobQueryParams) -> &mut Option<T>,
+    ) -> FacetOption {
+        let mut params = self.listing();
+
+        let slot = field(&mut params);
+        let active = slot.as_ref() == Some(&value);
+        *slot = (!active).then_some(value);
+
+        return FacetOption {
+            label,
+            count,
+            href: params.jobs_url(),
+            active,
+        };
+    }
+
+    fn facet_sections(
+        &self,
+        facets: JobFacets,
+        currencies: &[String],
+        currency: &str,
+    ) -> Vec<FacetSection> {
+        return vec![
+            FacetSection {
+                title: "Sort by",
+                options: [
+                    (JobSort::Newest, "Newest"),
+                    (JobSort::Salary, "Highest salary"),
+                ]
+                .into_iter()
+                .map(|(sort, label)| {
+                    return self.facet_option(label.to_owned(), None, sort, |p| {
+                        return &mut p.sort;
+                    });
+                })
+                .collect(),
+            },
+            FacetSection {
+                title: "Currency",
+                options: currencies
+                    .iter()
+                    .map(|c| {
+                        let params = JobQueryParams {
+                            currency: Some(c.clone()),
+                            ..self.listing()
+                        };
+                        return FacetOption {
+                            label: c.clone(),
+                            count: None,
+                            href: params.jobs_url(),
+                            active: c == currency,
+                        };
+                    })
+                    .collect(),
+            },
+            FacetSection {
+                title: "Tags",
+                options: facets
+                    .tags
+                    .into_iter()
+                    .map(|f| {
+                        return self.facet_option(f.value.clone(), Some(f.count), f.value, |p| {
+                            return &mut p.tag;
+                        });
+                    })
+                    .collect(),
+            },
+            FacetSection {
+                title: "Posted",
+                options: facets
+                    .posted_within
+                    .into_iter()
+                    .map(|f| {
+                        let label = match f.value {
+                            1 => "Past day".to_owned(),
+                            7 => "Past week".to_owned(),
+                            30 => "Past month".to_owned(),
+                            days => format!("Past {days} days"),
+                        };
+                        return self.facet_option(label, Some(f.count), f.value, |p| {
+                            return &mut p.posted_within;
+                        });
+                    })
+                    .collect(),
+            },
+            FacetSection {
+                title: "Status",
+                options: vec![
+                    self.facet_option("Include closed".to_owned(), None, true, |p| {
+                        return &mut p.closed;
+                    }),
+                ],
+            },
+            FacetSection {
+                title: "Work mode",
+                options: facets
+                    .work_modes
+                    .into_iter()
+                    .map(|f| {
+                        return self.facet_option(
+                            f.value.to_string(),
+                            Some(f.count),
+                            f.value,
+                            |p| {
+                                return &mut p.work_mode;
+                            },
+                        );
+                    })
+                    .collect(),
+            },
+            FacetSection {
+                title: "Seniority",
+                options: facets
+                    .seniorities
+                    .into_iter()
+                    .map(|f| {
+                        return self.facet_option(
+                            f.value.to_string(),
+                            Some(f.count),
+                            f.value,
+                            |p| {
+                                return &mut p.seniority;
+                            },
+                        );
+                    })
+                    .collect(),
+            },
+            FacetSection {
+                title: "Salary",
+                options: facets
+                    .min_salaries
+                    .into_iter()
+                    .map(|f| {
+                        let label = format!("{}K+ {currency}", f.value / 1000);
+                        return self.facet_option(label, Some(f.count), f.value, |p| {
+                            return &mut p.min_salary;
+                        });
+                    })
+                    .collect(),
+            },
+            FacetSection {
+                title: "Location",
+                options: facets
+                    .locations
+                    .into_iter()
+                    .map(|f| {
+                        return self.facet_option(f.value.clone(), Some(f.count), f.value, |p| {
+                            return &mut p.location;
+                        });
+                    })
+                    .collect(),
+            },
+            FacetSection {
+                title: "Company",
+                options: facets
+                    .companies
+                    .into_iter()
+                    .map(|f| {
+                        return self.facet_option(f.value.clone(), Some(f.count), f.value, |p| {
+                            return &mut p.company;
+                        });
+                    })
+                    .collect(),
+            },
+        ];
+    }
+
+    /// The shareable `/jobs` URL for this list
+    fn jobs_url(&self) -> String {
+        let query = serde_urlencoded::to_string(self.listing()).expect("query params to serialize");
+        if query.is_empty() {
+            return "/jobs".to_owned();
+        }
+        return format!("/jobs?{query}");
+    }
+
+    /// Where the job list fetches the page starting at `cursor`
+    fn page_url(&self, cursor: &JobCursor) -> String {
+        let params = JobQueryParams {
+            cursor: Some(cursor.encode()),
+            ..self.listing()
+        };
+        return format!(
+            "/htmx/jobs/page?{}",
+            serde_urlencoded::to_string(params).expect("query params to serialize")
+        );
+    }
+}
+
+/// The currency to show salaries in: the one `requested` (remembered in a
+/// cookie for next time), else the last one picked, else the default
+fn display_currency(
+    requested: Option<&str>,
+    jar: CookieJar,
+    currencies: &[String],
+) -> (String, CookieJar) {
+    let known = |c: String| return currencies.contains(&c).then_some(c);
+
+    if let Some(currency) = requested.map(str::to_uppercase).and_then(known) {
+        let cookie = Cookie::build((CURRENCY_COOKIE, currency.clone()))
+            .path("/")
+            .permanent()
+            .same_site(SameSite::Lax)
+            .http_only(true);
+        return (currency, jar.add(cookie));
+    }
+
+    let currency = jar
+        .get(CURRENCY_COOKIE)
+        .map(|c| return c.value().to_uppercase())
+        .and_then(known)
+        .unwrap_or_else(|| return DEFAULT_CURRENCY.to_owned());
+
+    return (currency, jar);
+}
+
+/// Who jobs are shown to, remembering the display currency if one was `requested`
+pub(super) fn viewer(
+    user: Option<&User>,
+    requested: Option<&str>,
+    jar: CookieJar,
+    currencies: &[String],
+) -> (Viewer, CookieJar) {
+    let (currency, jar) = display_currency(requested, jar, currencies);
+    let viewer = Viewer {
+        currency,
+        user_id: user.map(|u| return u.id.clone()),
+    };
+
+    return (viewer, jar);
+}
+
+/// The viewer's notes on a job, when they're logged in
+async fn viewer_notes(
+    state: &AppState,
+    viewer: &Viewer,
+    job_id: &str,
+) -> Result<Option<JobNotes>, AppError> {
+    let Some(user_id) = &viewer.user_id else {
+        return Ok(None);
+    };
+
+    return Ok(Some(notes::job_notes(&state.db, user_id, job_id).await?));
+}
+
+/// The job selected through `currentJobId`, if any, along with the viewer's
+/// notes on it
+async fn current_job(
+    state: &AppState,
+    query_params: &JobQueryParams,
+    viewer: &Viewer,
+) -> Result<(Option<JobDetails>, Option<JobNotes>), AppError> {
+    let Some(id) = &query_params.current_job_id else {
+        return Ok((None, None));
+    };
+
+    let job = jobs::get_job(&state.db, id, viewer)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let notes = viewer_notes(state, viewer, id).await?;
+
+    return Ok((Some(job), notes));
+}
+
+async fn job_handler(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    jar: CookieJar,
+    Query(query_params): Query<JobQueryParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let query_params = query_params.normalized();
+    let currencies = rates::currencies(&state.db).await?;
+    let (viewer, jar) = viewer(
+        user.as_ref(),
+        query_params.currency.as_deref(),
+        jar,
+        &currencies,
+    );
+    let filter = query_params.filter(viewer.clone());
+    let JobPage { jobs, next } = jobs::list_jobs(&state.db, &filter, None, PAGE_SIZE).await?;
+    let next_page = next.map(|cursor| return query_params.page_url(&cursor));
+    let facets = jobs::facet_counts(&state.db, &filter).await?;
+    let sections = query_params.facet_sections(facets, &currencies, &viewer.currency);
+    let fields = query_params.filter_fields();
+    let (job, notes) = current_job(&state, &query_params, &viewer).await?;
+
+    let search = query_params.q.unwrap_or_default();
 
     let h = htmlify(|| {
         return view! {
-            <Layout>
-                <div class="h-full flex">
-                    <div class="basis-[48rem] mr-2 overflow-auto h-full">
-                        {jobs.into_iter().map(|n| view! {
-                            <JobSummary job=n />
-                        }).collect_view()}
-                    </div>
-                    <div class="basis-full bg-dark-weak rounded-sm overflow-auto h-full">
-                        {if job.is_some() {
-                            view! {<JobPostingDetails job=job.unwrap() />}
-                        } else {view! {
-                            <HomePageDetails/>
-                        }}}
-                    </div>
-                </div>
+            <Layout search=search user=user>
+                <JobPanes jobs=jobs next_page=next_page job=job notes=notes>
+                    <FilterSidebar sections=sections fields=fields />
+                </JobPanes>
             </Layout>
         };
     });
 
-    return (StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h);
+    return Ok((
+        StatusCode::OK,
+        jar,
+        [(header::CONTENT_TYPE, "text/html")],
+        h,
+    ));
+}
+
+async fn htmx_jobs_list(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    jar: CookieJar,
+    Query(query_params): Query<JobQueryParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let query_params = query_params.normalized().listing();
+    let currencies = rates::currencies(&state.db).await?;
+    let (viewer, jar) = viewer(
+        user.as_ref(),
+        query_params.currency.as_deref(),
+        jar,
+        &currencies,
+    );
+    let filter = query_params.filter(viewer.clone());
+    let JobPage { jobs, next } = jobs::list_jobs(&state.db, &filter, None, PAGE_SIZE).await?;
+    let next_page = next.map(|cursor| return query_params.page_url(&cursor));
+    let facets = jobs::facet_counts(&state.db, &filter).await?;
+    let sections = query_params.facet_sections(facets, &currencies, &viewer.currency);
+    let fields = query_params.filter_fields();
+
+    let push_url = HxPushUrl(
+        query_params
+            .jobs_url()
+            .parse()
+            .expect("the jobs url to be a valid uri"),
+    );
+
+    let h = htmlify(|| {
+        return view! {
+            <JobList jobs=jobs next_page=next_page />
+            <FilterSidebar sections=sections fields=fields oob=true />
+        };
+    });
+
+    return Ok((
+        StatusCode::OK,
+        push_url,
+        jar,
+        [(header::CONTENT_TYPE, "text/html")],
+        h,
+    ));
+}
+
+async fn htmx_jobs_page(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    jar: CookieJar,
+    Query(query_params): Query<JobQueryParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let query_params = query_params.normalized();
+    let currencies = rates::currencies(&state.db).await?;
+    let (viewer, jar) = viewer(
+        user.as_ref(),
+        query_params.currency.as_deref(),
+        jar,
+        &currencies,
+    );
+    let cursor = query_params
+        .cursor
+        .as_deref()
+        .and_then(JobCursor::decode)
+        .ok_or(AppError::BadRequest)?;
+
+    let filter = query_params.filter(viewer);
+    let JobPage { jobs, next } =
+        jobs::list_jobs(&state.db, &filter, Some(&cursor), PAGE_SIZE).await?;
+    let next_page = next.map(|cursor| return query_params.page_url(&cursor));
+
+    let h = htmlify(|| {
+        return view! {
+            <JobListPage jobs=jobs next_page=next_page />
+        };
+    });
+
+    return Ok((
+        StatusCode::OK,
+        jar,
+        [(header::CONTENT_TYPE, "text/html")],
+        h,
+    ));
 }
 
 async fn htmx_jobs_details(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    jar: CookieJar,
     Path(id): Path<String>,
-) -> impl IntoResponse {
-    let job = get_mock_job(id);
+) -> Result<impl IntoResponse, AppError> {
+    let currencies = rates::currencies(&state.db).await?;
+    let (viewer, _) = viewer(user.as_ref(), None, jar, &currencies);
+    let job = jobs::get_job(&state.db, &id, &viewer)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let notes = viewer_notes(&state, &viewer, &id).await?;
 
     let h = htmlify(|| {
         return view! {
-            <JobPostingDetails job=job />
+            <JobPostingDetails job=job notes=notes />
+        };
+    });
+
+    return Ok((StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h));
+}
+
+async fn saved_handler(
+    state: State<AppState>,
+    user: CurrentUser,
+    jar: CookieJar,
+    query_params: Query<JobQueryParams>,
+) -> Result<Response, AppError> {
+    return shortlist_page(Shortlist::Saved, state, user, jar, query_params).await;
+}
+
+async fn applied_handler(
+    state: State<AppState>,
+    user: CurrentUser,
+    jar: CookieJar,
+    query_params: Query<JobQueryParams>,
+) -> Result<Response, AppError> {
+    return shortlist_page(Shortlist::Applied, state, user, jar, query_params).await;
+}
+
+/// The jobs on one of the user's lists, laid out like the job list minus filters
+async fn shortlist_page(
+    list: Shortlist,
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    jar: CookieJar,
+    Query(query_params): Query<JobQueryParams>,
+) -> Result<Response, AppError> {
+    let Some(user) = user else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+
+    let query_params = JobQueryParams {
+        list: Some(list),
+        ..query_params.normalized()
+    };
+    let currencies = rates::currencies(&state.db).await?;
+    let (viewer, jar) = viewer(
+        Some(&user),
+        query_params.currency.as_deref(),
+        jar,
+        &currencies,
+    );
+    let filter = query_params.filter(viewer.clone());
+    let JobPage { jobs, next } = jobs::list_jobs(&state.db, &filter, None, PAGE_SIZE).await?;
+    let next_page = next.map(|cursor| return query_params.page_url(&cursor));
+    let (job, notes) = current_job(&state, &query_params, &viewer).await?;
+
+    let empty = match list {
+        Shortlist::Saved => "You haven't saved any jobs yet",
+        Shortlist::Applied => "You haven't applied to any jobs yet",
+    };
+
+    let h = htmlify(move || {
+        return view! {
+            <Layout user=Some(user)>
+                <JobPanes jobs=jobs next_page=next_page job=job notes=notes empty=empty />
+            </Layout>
+        };
+    });
+
+    return Ok((
+        StatusCode::OK,
+        jar,
+        [(header::CONTENT_TYPE, "text/html")],
+        h,
+    )
+        .into_response());
+}
+
+fn shortlist_button(job_id: String, list: Shortlist, listed: bool) -> impl IntoResponse {
+    let h = htmlify(move || {
+        return view! {
+            <ShortlistButton job_id=job_id list=list listed=listed />
         };
     });
 
     return (StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h);
 }
-/* --------------------------------------------------------------------------------------- */
-
-fn mock_jobs(num: usize) -> Vec<JobDetails> {
-    let jobs: Vec<JobDetails> = vec![
-        JobDetails {
-            id: "2".to_string(),
-            title: "Software Developer Intern".to_string(),
-            company: "Cloudflare".to_string(),
-            location: "New York".to_string(),
-            salary: "120K USD".to_string(),
-            description: "You're gonna be the coffee boy, sorry".to_string(),
-        },
-        JobDetails {
-            id: "1".to_string(),
-            title: "Sr Rust Developer".to_string(),
-            company: "GitHub".to_string(),
-            location: "Vancouver".to_string(),
-            salary: "187K CAD".to_string(),
-            description: "Join the up and coming hype driven development!".to_string(),
-        },
-        JobDetails {
-            id: "3".to_string(),
-            title: "Technical Product Manager - AI Enhancement Policy Team".to_string(),
-            company: "Amazon".to_string(),
-            location: "Seattle".to_string(),
-            salary: "278K USD".to_string(),
-            description: "You are the new team SCRUMLORD and AGILEMASTER".to_string(),
-        },
-        JobDetails {
-            id: "alsdkjaslkdsf".to_string(),
-            title: "Jr Fullstack Developer".to_string(),
-            company: "Adobe".to_string(),
-            location: "Seattle".to_string(),
-            salary: "73K CAD".to_string(),
-            description: "Have fun you reach andy".to_string(),
-        },
-        JobDetails {
-            id: "ldsfjosadjasd".to_string(),
-            title: "Staff QA Specialist".to_string(),
-            company: "Google".to_string(),
-            location: "San Fransisco".to_string(),
-            salary: "572K USD".to_string(),
-            description: "You are head honcho and hate your job".to_string(),
-        },
-        JobDetails {
-            id: "lidfsjasdlkajsd".to_string(),
-            title: "Software Developer".to_string(),
-            company: "Crowdstrike".to_string(),
-            location: "New York".to_string(),
-            salary: "147K USD".to_string(),
-            description: "Want to investigate Snarling Bear or Smiling Panda?".to_string(),
-        },
-    ]
-    .into_iter()
-    .cycle()
-    .take(num)
-    .collect();
 
-    return jobs;
+async fn htmx_shortlist_add(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path((id, list)): Path<(String, Shortlist)>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    if !shortlists::add(&state.db, list, &user.id, &id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    return Ok(shortlist_button(id, list, true));
 }
 
-fn get_mock_job(id: String) -> JobDetails {
-    return mock_jobs(6)
-        .iter()
-        .find(|j| return j.id.eq(&id))
-        .unwrap()
-        .clone();
+async fn htmx_shortlist_remove(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path((id, list)): Path<(String, Shortlist)>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    shortlists::remove(&state.db, list, &user.id, &id).await?;
+
+    return Ok(shortlist_button(id, list, false));
 }
@@ -0,0 +1,43 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::KeyValue;
+
+use crate::metrics::HTTP;
+
+/// Records each request's duration with the same route, method and status as
+/// its span
+pub async fn record_request(request: Request, next: Next) -> Response {
+    let mut attributes = vec![KeyValue::new(
+        "http.request.method",
+        request.method().to_string(),
+    )];
+    // Requests which matched no route are left without one, to keep the
+    // number of routes reported down
+    if let Some(route) = request.extensions().get::<MatchedPath>() {
+        attributes.push(KeyValue::new("http.route", route.as_str().to_owned()));
+    }
+
+    HTTP.active.add(1, &attributes);
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = start.elapsed();
+    HTTP.active.add(-1, &attributes);
+
+    let status = response.status();
+    attributes.push(KeyValue::new(
+        "http.response.status_code",
+        i64::from(status.as_u16()),
+    ));
+    if status.is_server_error() {
+        attributes.push(KeyValue::new("error.type", status.as_str().to_owned()));
+    }
+    HTTP.duration
+        .record(elapsed.as_secs_f64() * 1000.0, &attributes);
+
+    return response;
+}
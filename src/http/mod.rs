@@ -1,86 +1,226 @@
-use std::time::Duration;
+use std::{future::IntoFuture, net::SocketAddr, sync::Arc, time::Duration};
 
 use anyhow::{Context, Result};
 use axum::{
     extract::MatchedPath,
     http::{header, Request, Version},
+    middleware,
     response::Response,
     Router,
 };
 use opentelemetry::trace::SpanKind;
-use tokio::net::TcpListener;
+use tokio::{net::TcpListener, task::AbortHandle};
+use tokio_util::sync::CancellationToken;
 use tower_http::{
     catch_panic::CatchPanicLayer, classify::ServerErrorsFailureClass, trace::TraceLayer,
 };
-use tracing::{debug, info, info_span, Span};
+use tracing::{debug, info, info_span, warn, Span};
 
+mod admin;
+mod auth;
+mod calendar;
+mod companies;
 mod components;
+mod duplicates;
+mod error;
+mod health;
+mod import;
 mod index;
+mod metrics;
+mod notes;
+mod pipeline;
 mod staticfiles;
-use crate::db::DbPool;
+use crate::{db::DbPool, ingest::HttpClient};
 
 #[derive(Clone)]
 pub struct AppState {
     db: DbPool,
+    /// How pages pasted in to import are fetched
+    http: Arc<dyn HttpClient>,
+    /// Background tasks the site relies on, by name
+    workers: Arc<[(&'static str, AbortHandle)]>,
+    /// Cancelled once the server is shutting down
+    stop: CancellationToken,
 }
 
-pub async fn serve(db: DbPool) -> Result<()> {
-    let app = api_router().with_state(AppState { db }).layer(
-        TraceLayer::new_for_http()
-            .make_span_with(|request: &Request<_>| {
-                // https://github.com/open-telemetry/semantic-conventions/blob/v1.23.0/docs/http/http-spans.md
-                let matched_route = request
-                        .extensions()
-                        .get::<MatchedPath>()
-                        .map(MatchedPath::as_str);
-                return info_span!(
-                    "http_request",
-                    "otel.name" = format!("{} {}", request.method(), matched_route.unwrap_or("UnknownRoute")),
-                    "otel.kind" = format!("{:?}", SpanKind::Server),
-                    "otel.status_code" = tracing::field::Empty,
-                    "http.request.method" = ?request.method(),
-                    "http.route" = matched_route,
-                    "url.path" = request.uri().path(),
-                    "url.query" = request.uri().query(),
-                    "user_agent.original" = request.headers().get(header::USER_AGENT).and_then(|val| val.to_str().ok()),
-                    "http.flavor" = match request.version() {
-                        Version::HTTP_09 => "0.9",
-                        Version::HTTP_10 => "1.0",
-                        Version::HTTP_11 => "1.1",
-                        Version::HTTP_2 => "2.0",
-                        Version::HTTP_3 => "3.0",
-                        _ => "Unknown",
+/// Serves the site until `stop` is cancelled, then lets requests underway
+/// finish for up to `drain`. `workers` are reported on by `/readyz`.
+pub async fn serve(
+    db: DbPool,
+    http: Arc<dyn HttpClient>,
+    workers: Vec<(&'static str, AbortHandle)>,
+    bind: SocketAddr,
+    stop: CancellationToken,
+    drain: Duration,
+) -> Result<()> {
+    let state = AppState {
+        db,
+        http,
+        workers: workers.into(),
+        stop: stop.clone(),
+    };
+    let app = api_router()
+        .with_state(state)
+        .layer(middleware::from_fn(metrics::record_request))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_span)
+                .on_request(|_request: &Request<_>, _span: &Span| {})
+                .on_response(|_response: &Response, _latency: Duration, _span: &Span| {
+                    _span.record(
+                        "http.response.status_code",
+                        _response.status().as_u16() as i64,
+                    );
+                    debug!("Request served");
+                })
+                .on_failure(
+                    |_error: ServerErrorsFailureClass, _latency: Duration, _span: &Span| {
+                        // Error responses from handlers record their own, more specific, error.type
+                        if let ServerErrorsFailureClass::Error(_) = _error {
+                            _span.record("error.type", _error.to_string());
+                        }
+                        _span.record("otel.status_code", "ERROR");
+                        debug!("Request errored");
                     },
-                    "http.request.content_length" = request.headers().get(header::CONTENT_LENGTH).and_then(|val| val.to_str().ok()),
-                    "http.response.status_code" = tracing::field::Empty,
-                    "error.type" = tracing::field::Empty,
-                );
-            })
-            .on_request(|_request: &Request<_>, _span: &Span| {})
-            .on_response(|_response: &Response, _latency: Duration, _span: &Span| {
-                _span.record("http.response.status_code", _response.status().as_u16() as i64);
-                debug!("Request served");
-            })
-            .on_failure(
-                |_error: ServerErrorsFailureClass, _latency: Duration, _span: &Span| {
-                    _span.record("error.type", _error.to_string());
-                    _span.record("otel.status_code", "ERROR");
-                    debug!("Request errored");
-                }
-            )
-    );
+                ),
+        );
 
-    let listener = TcpListener::bind("127.0.0.1:4321").await.unwrap();
+    let listener = TcpListener::bind(bind)
+        .await
+        .with_context(|| return format!("Could not listen on {bind}"))?;
     info!("listening on {}", listener.local_addr().unwrap());
 
-    axum::serve(listener, app)
-        .await
-        .context("error running HTTP server")
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(stop.clone().cancelled_owned())
+        .into_future();
+    let drained = async {
+        stop.cancelled().await;
+        tokio::time::sleep(drain).await;
+    };
+
+    tokio::select! {
+        served = server => served.context("error running HTTP server")?,
+        _ = drained => warn!("Requests still running after {}s were dropped", drain.as_secs()),
+    }
+
+    return Ok(());
+}
+
+/// Routes with a secret in the path, which spans record instead of the path
+const SECRET_ROUTES: &[&str] = &["/calendar/feed/:file"];
+
+fn request_span<B>(request: &Request<B>) -> Span {
+    // https://github.com/open-telemetry/semantic-conventions/blob/v1.23.0/docs/http/http-spans.md
+    let matched_route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str);
+    let path = match matched_route {
+        Some(route) if SECRET_ROUTES.contains(&route) => route,
+        _ => request.uri().path(),
+    };
+    return info_span!(
+        "http_request",
+        "otel.name" = format!("{} {}", request.method(), matched_route.unwrap_or("UnknownRoute")),
+        "otel.kind" = format!("{:?}", SpanKind::Server),
+        "otel.status_code" = tracing::field::Empty,
+        "http.request.method" = ?request.method(),
+        "http.route" = matched_route,
+        "url.path" = path,
+        "url.query" = request.uri().query(),
+        "user_agent.original" = request.headers().get(header::USER_AGENT).and_then(|val| val.to_str().ok()),
+        "http.flavor" = match request.version() {
+            Version::HTTP_09 => "0.9",
+            Version::HTTP_10 => "1.0",
+            Version::HTTP_11 => "1.1",
+            Version::HTTP_2 => "2.0",
+            Version::HTTP_3 => "3.0",
+            _ => "Unknown",
+        },
+        "http.request.content_length" = request.headers().get(header::CONTENT_LENGTH).and_then(|val| val.to_str().ok()),
+        "http.response.status_code" = tracing::field::Empty,
+        "error.type" = tracing::field::Empty,
+    );
 }
 
 fn api_router() -> Router<AppState> {
     return Router::new()
         .merge(index::router())
+        .merge(admin::router())
+        .merge(auth::router())
+        .merge(calendar::router())
+        .merge(companies::router())
+        .merge(duplicates::router())
+        .merge(health::router())
+        .merge(import::router())
+        .merge(notes::router())
+        .merge(pipeline::router())
         .merge(staticfiles::router())
-        .layer(CatchPanicLayer::new());
+        .fallback(|| async { return error::AppError::NotFound })
+        .layer(CatchPanicLayer::custom(error::handle_panic))
+        .layer(middleware::from_fn(error::render_errors));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use axum::routing::get;
+    use tracing::{
+        field::{Field, Visit},
+        span::Attributes,
+        Id, Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+    use super::*;
+
+    /// Keeps the fields of every span opened
+    #[derive(Clone, Default)]
+    struct SpanFields(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl Visit for SpanFields {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            let value = format!("{value:?}");
+            self.0
+                .lock()
+                .unwrap()
+                .push((field.name().to_owned(), value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanFields {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_calendar_tokens_out_of_spans() {
+        let fields = SpanFields::default();
+        let _guard = tracing_subscriber::registry()
+            .with(fields.clone())
+            .set_default();
+
+        let app = Router::new()
+            .route("/calendar/feed/:file", get(|| async { return "feed" }))
+            .layer(TraceLayer::new_for_http().make_span_with(request_span));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(axum::serve(listener, app).into_future());
+
+        let url = format!("http://{addr}/calendar/feed/s3cr3t-token.ics");
+        let response = reqwest::get(url).await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "feed");
+
+        let fields = fields.0.lock().unwrap();
+        let path = fields.iter().find(|(name, _)| return name == "url.path");
+        assert_eq!(
+            path.map(|(_, value)| return value.as_str()),
+            Some("\"/calendar/feed/:file\"")
+        );
+        assert!(fields
+            .iter()
+            .all(|(_, value)| return !value.contains("s3cr3t")));
+    }
 }
@@ -0,0 +1,137 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Form, Router,
+};
+use leptos::view;
+use serde::Deserialize;
+
+use super::{
+    auth::CurrentUser,
+    components::{htmlify, JobNote, JobNoteEditor, JobTags},
+    error::AppError,
+    AppState,
+};
+use crate::db::notes::{self, JobNotes};
+
+pub fn router() -> Router<AppState> {
+    return Router::new()
+        .route("/htmx/jobs/:id/note", get(htmx_note).put(htmx_note_save))
+        .route("/htmx/jobs/:id/note/edit", get(htmx_note_edit))
+        .route(
+            "/htmx/jobs/:id/tags",
+            post(htmx_tag_add).delete(htmx_tag_remove),
+        );
+}
+
+#[derive(Deserialize)]
+struct NoteForm {
+    body: String,
+}
+
+#[derive(Deserialize)]
+struct TagForm {
+    tag: String,
+}
+
+fn note_fragment(notes: JobNotes) -> impl IntoResponse {
+    let h = htmlify(move || {
+        return view! {
+            <JobNote job_id=notes.job_id note=notes.note />
+        };
+    });
+
+    return (StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h);
+}
+
+fn tags_fragment(notes: JobNotes) -> impl IntoResponse {
+    let h = htmlify(move || {
+        return view! {
+            <JobTags job_id=notes.job_id tags=notes.tags />
+        };
+    });
+
+    return (StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h);
+}
+
+async fn htmx_note(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    let notes = notes::job_notes(&state.db, &user.id, &id).await?;
+
+    return Ok(note_fragment(notes));
+}
+
+async fn htmx_note_edit(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    let notes = notes::job_notes(&state.db, &user.id, &id).await?;
+
+    let h = htmlify(move || {
+        return view! {
+            <JobNoteEditor job_id=notes.job_id note=notes.note />
+        };
+    });
+
+    return Ok((StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h));
+}
+
+async fn htmx_note_save(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+    Form(form): Form<NoteForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    if !notes::save_note(&state.db, &user.id, &id, &form.body).await? {
+        return Err(AppError::NotFound);
+    }
+
+    let notes = notes::job_notes(&state.db, &user.id, &id).await?;
+
+    return Ok(note_fragment(notes));
+}
+
+async fn htmx_tag_add(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+    Form(form): Form<TagForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+    let tag = notes::normalize_tag(&form.tag).ok_or(AppError::BadRequest)?;
+
+    if !notes::add_tag(&state.db, &user.id, &id, &tag).await? {
+        return Err(AppError::NotFound);
+    }
+
+    let notes = notes::job_notes(&state.db, &user.id, &id).await?;
+
+    return Ok(tags_fragment(notes));
+}
+
+async fn htmx_tag_remove(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<String>,
+    Query(form): Query<TagForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    notes::remove_tag(&state.db, &user.id, &id, &form.tag).await?;
+
+    let notes = notes::job_notes(&state.db, &user.id, &id).await?;
+
+    return Ok(tags_fragment(notes));
+}
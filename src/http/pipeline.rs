@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Router,
+};
+use axum_extra::extract::CookieJar;
+use leptos::view;
+use serde::Deserialize;
+
+use super::{
+    auth::CurrentUser,
+    components::{htmlify, Layout, PipelineBoard, PipelineColumn},
+    error::AppError,
+    index::viewer,
+    AppState,
+};
+use crate::db::{
+    jobs::Viewer,
+    pipeline::{self, Stage, StageChange},
+    rates,
+    users::User,
+};
+
+pub fn router() -> Router<AppState> {
+    return Router::new()
+        .route("/pipeline", get(pipeline_handler))
+        .route("/htmx/pipeline/:id", post(htmx_pipeline_move));
+}
+
+#[derive(Deserialize)]
+struct MoveForm {
+    stage: Stage,
+}
+
+/// The user's pipeline jobs grouped by stage, along with their history
+async fn pipeline_columns(
+    state: &AppState,
+    user: &User,
+    viewer: &Viewer,
+) -> Result<Vec<PipelineColumn>, AppError> {
+    let jobs = pipeline::pipeline_jobs(&state.db, viewer).await?;
+
+    let mut history: HashMap<String, Vec<StageChange>> = HashMap::new();
+    for change in pipeline::stage_history(&state.db, &user.id).await? {
+        history
+            .entry(change.job_id.clone())
+            .or_default()
+            .push(change);
+    }
+
+    return Ok(Stage::ALL
+        .into_iter()
+        .map(|stage| {
+            return PipelineColumn {
+                stage,
+                jobs: jobs
+                    .iter()
+                    .filter(|job| return job.stage == Some(stage))
+                    .map(|job| return (job.clone(), history.remove(&job.id).unwrap_or_default()))
+                    .collect(),
+            };
+        })
+        .collect());
+}
+
+async fn pipeline_handler(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    jar: CookieJar,
+) -> Result<Response, AppError> {
+    let Some(user) = user else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+
+    let currencies = rates::currencies(&state.db).await?;
+    let (viewer, _) = viewer(Some(&user), None, jar, &currencies);
+    let columns = pipeline_columns(&state, &user, &viewer).await?;
+
+    let h = htmlify(move || {
+        return view! {
+            <Layout user=Some(user)>
+                <PipelineBoard columns=columns />
+            </Layout>
+        };
+    });
+
+    return Ok((StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h).into_response());
+}
+
+async fn htmx_pipeline_move(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    jar: CookieJar,
+    Path(id): Path<String>,
+    Form(form): Form<MoveForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user.ok_or(AppError::Unauthorized)?;
+
+    if !pipeline::move_job(&state.db, &user.id, &id, form.stage).await? {
+        return Err(AppError::NotFound);
+    }
+
+    let currencies = rates::currencies(&state.db).await?;
+    let (viewer, _) = viewer(Some(&user), None, jar, &currencies);
+    let columns = pipeline_columns(&state, &user, &viewer).await?;
+
+    let h = htmlify(move || {
+        return view! {
+            <PipelineBoard columns=columns />
+        };
+    });
+
+    return Ok((StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], h));
+}
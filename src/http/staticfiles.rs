@@ -1,12 +1,12 @@
 use axum::{
-    http::{header, StatusCode, Uri},
+    http::{header, Uri},
     response::{IntoResponse, Response},
     routing::{get, Router},
 };
 use mime_guess::from_path;
 use rust_embed::RustEmbed;
 
-use crate::http::AppState;
+use crate::http::{error::AppError, AppState};
 
 pub fn router() -> Router<AppState> {
     return Router::new().route("/static/*file", get(static_handler));
@@ -40,7 +40,7 @@ where
                 let mime = from_path(path).first_or_octet_stream();
                 return ([(header::CONTENT_TYPE, mime.as_ref())], content.data).into_response();
             }
-            None => return (StatusCode::NOT_FOUND, "404 Not Found").into_response(),
+            None => return AppError::NotFound.into_response(),
         }
     }
 }
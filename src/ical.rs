@@ -0,0 +1,216 @@
+use time::{macros::format_description, OffsetDateTime, PrimitiveDateTime};
+
+use crate::db::calendar::{CalendarEvent, EventKind};
+
+/// Longest a content line may be before it's folded, in octets
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Escapes `text` for use as a TEXT property value
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+
+    return escaped;
+}
+
+/// Writes a content line, folding it onto continuation lines so none is
+/// longer than [`MAX_LINE_OCTETS`], without splitting a UTF-8 character
+fn push_line(out: &mut String, line: &str) {
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            octets = 1;
+        }
+        out.push(c);
+        octets += c.len_utf8();
+    }
+
+    out.push_str("\r\n");
+}
+
+/// `YYYY-MM-DD` and `HH:MM[:SS]` as an iCalendar DATE or DATE-TIME
+fn compact(text: &str) -> String {
+    return text.chars().filter(|c| return c.is_ascii_digit()).collect();
+}
+
+fn push_event(out: &mut String, event: &CalendarEvent) {
+    push_line(out, "BEGIN:VEVENT");
+    push_line(out, &format!("UID:{}@wantjob", event.id));
+    // Stored as `YYYY-MM-DD HH:MM:SS` in UTC, and the property is required
+    let updated_at = PrimitiveDateTime::parse(
+        &event.updated_at,
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+    )
+    .map(PrimitiveDateTime::assume_utc)
+    .unwrap_or_else(|_| return OffsetDateTime::now_utc());
+    let stamp = updated_at
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .unwrap_or_default();
+    push_line(out, &format!("DTSTAMP:{stamp}"));
+
+    match &event.start_time {
+        // Floating, i.e. at that time wherever the calendar is shown, as the
+        // time zone it was entered in isn't known. Without an end the event
+        // lasts the day, or is an instant.
+        Some(time) => {
+            push_line(
+                out,
+                &format!("DTSTART:{}T{}00", compact(&event.day), compact(time)),
+            );
+            if let Some(minutes) = event.duration_minutes {
+                push_line(out, &format!("DURATION:PT{minutes}M"));
+            }
+        }
+        None => push_line(out, &format!("DTSTART;VALUE=DATE:{}", compact(&event.day))),
+    }
+
+    push_line(
+        out,
+        &format!(
+            "SUMMARY:{}",
+            escape(&format!(
+                "{}: {} at {}",
+                event.kind, event.title, event.company
+            ))
+        ),
+    );
+    if !event.notes.is_empty() {
+        push_line(out, &format!("DESCRIPTION:{}", escape(&event.notes)));
+    }
+    // Only interviews take up time
+    if event.kind != EventKind::Interview {
+        push_line(out, "TRANSP:TRANSPARENT");
+    }
+    push_line(out, "END:VEVENT");
+}
+
+/// An iCalendar (RFC 5545) object publishing every one of `events`
+pub fn feed(name: &str, events: &[CalendarEvent]) -> String {
+    let mut out = String::new();
+
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//WantJob//Calendar//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "METHOD:PUBLISH");
+    push_line(&mut out, &format!("X-WR-CALNAME:{}", escape(name)));
+    for event in events {
+        push_event(&mut out, event);
+    }
+    push_line(&mut out, "END:VCALENDAR");
+
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(title: &str, notes: &str, updated_at: &str) -> CalendarEvent {
+        return CalendarEvent {
+            id: "e1".to_owned(),
+            job_id: "j1".to_owned(),
+            title: title.to_owned(),
+            company: "Acme".to_owned(),
+            kind: EventKind::Interview,
+            day: "2024-03-05".to_owned(),
+            start_time: Some("14:30".to_owned()),
+            duration_minutes: Some(45),
+            notes: notes.to_owned(),
+            updated_at: updated_at.to_owned(),
+            past: false,
+        };
+    }
+
+    /// The content lines of `ics`, with folded lines joined back up
+    fn unfold(ics: &str) -> Vec<String> {
+        return ics
+            .replace("\r\n ", "")
+            .split("\r\n")
+            .filter(|line| return !line.is_empty())
+            .map(str::to_owned)
+            .collect();
+    }
+
+    #[test]
+    fn writes_event() {
+        let ics = feed("Jobs", &[event("Engineer", "", "2024-03-01 09:15:00")]);
+        let lines = unfold(&ics);
+
+        assert!(
+            lines.contains(&"DTSTAMP:20240301T091500Z".to_owned()),
+            "{lines:?}"
+        );
+        assert!(
+            lines.contains(&"DTSTART:20240305T143000".to_owned()),
+            "{lines:?}"
+        );
+        assert!(lines.contains(&"DURATION:PT45M".to_owned()), "{lines:?}");
+        assert!(!lines.iter().any(|l| return l.starts_with("DESCRIPTION")));
+    }
+
+    #[test]
+    fn survives_malformed_timestamps() {
+        for updated_at in [
+            "",
+            "2024",
+            "2024-03-01",
+            "2024-03-01 9é",
+            "é2024-03-01 09:15:00",
+        ] {
+            let ics = feed("Jobs", &[event("Engineer", "", updated_at)]);
+            let stamp = unfold(&ics)
+                .into_iter()
+                .find(|l| return l.starts_with("DTSTAMP:"))
+                .unwrap();
+            assert_eq!(stamp.len(), "DTSTAMP:20240301T091500Z".len(), "{stamp:?}");
+        }
+    }
+
+    #[test]
+    fn folds_long_multibyte_lines() {
+        let title = "Ingénieur logiciel senior — équipe paiements 🚀 ".repeat(4);
+        let ics = feed("Jobs", &[event(&title, "", "2024-03-01 09:15:00")]);
+
+        for line in ics.split("\r\n") {
+            assert!(
+                line.len() <= MAX_LINE_OCTETS,
+                "{line:?} is {} octets",
+                line.len()
+            );
+        }
+        assert!(ics.contains("\r\n "), "nothing was folded");
+        // No character was split, so unfolding gives back the whole summary
+        let summary = format!("SUMMARY:{}", escape(&format!("Interview: {title} at Acme")));
+        assert!(unfold(&ics).contains(&summary));
+    }
+
+    #[test]
+    fn escapes_notes() {
+        let notes = "Bring: ID; laptop, charger\nAsk about C:\\ drive\r\nParking, level 2";
+        let ics = feed("Jobs", &[event("Engineer", notes, "2024-03-01 09:15:00")]);
+
+        assert!(
+            unfold(&ics).contains(
+                &"DESCRIPTION:Bring: ID\\; laptop\\, charger\\nAsk about C:\\\\ drive\\nParking\\, level 2"
+                    .to_owned()
+            ),
+            "{ics}"
+        );
+    }
+}
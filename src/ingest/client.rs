@@ -0,0 +1,380 @@
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use axum::async_trait;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header, redirect, StatusCode, Url,
+};
+
+use crate::db::sources::FetchState;
+
+/// A response to a conditional request
+pub enum Fetched {
+    NotModified,
+    Modified { body: String, state: FetchState },
+}
+
+/// How importers reach job boards, so they can be pointed at saved fixtures
+/// instead of the live services
+#[async_trait]
+pub trait HttpClient: Send + Sync {
+    /// GETs `url`, returning the body of a successful response
+    async fn get(&self, url: &str) -> Result<String>;
+
+    /// GETs `url` unless it's unchanged since the response `state` was saved from
+    async fn get_if_modified(&self, url: &str, _state: &FetchState) -> Result<Fetched> {
+        return Ok(Fetched::Modified {
+            body: self.get(url).await?,
+            state: FetchState::default(),
+        });
+    }
+}
+
+/// Whether `ip` is reachable from the internet, rather than e.g. loopback,
+/// the local network or a cloud metadata service
+fn is_public(ip: IpAddr) -> bool {
+    return match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    || ip.is_multicast())
+            }
+        },
+    };
+}
+
+/// Whether `url` may be fetched for a user. Hostnames are checked once
+/// resolved, by [`PublicResolver`].
+fn is_public_url(url: &Url) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    return match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => is_public(ip),
+        Err(_) => true,
+    };
+}
+
+/// Resolves hostnames as the system does, leaving out addresses that aren't
+/// public. The client connects to what this returns, so a name can't be
+/// pointed somewhere else between checking and connecting.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        return Box::pin(async move {
+            let host = name.as_str();
+            let addrs = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|addr| return is_public(addr.ip()))
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                return Err(format!("{host} has no public address").into());
+            }
+            return Ok(Box::new(addrs.into_iter()) as Addrs);
+        });
+    }
+}
+
+/// Fetches over the network
+pub struct LiveClient {
+    client: reqwest::Client,
+    public_only: bool,
+}
+
+impl LiveClient {
+    fn builder() -> reqwest::ClientBuilder {
+        return reqwest::Client::builder()
+            .user_agent(concat!("wantjob/", env!("CARGO_PKG_VERSION")))
+            .timeout(Duration::from_secs(30));
+    }
+
+    pub fn new() -> Result<Self> {
+        let client = Self::builder()
+            .build()
+            .context("Could not build HTTP client")?;
+
+        return Ok(LiveClient {
+            client,
+            public_only: false,
+        });
+    }
+
+    /// For fetching links users give us: only public addresses are reached,
+    /// including through redirects, so links can't be used to probe the
+    /// network the server is on
+    pub fn public_only() -> Result<Self> {
+        let redirects = redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= 5 {
+                return attempt.error("Too many redirects");
+            }
+            if !is_public_url(attempt.url()) {
+                let error = format!("Redirected to {}, which isn't public", attempt.url());
+                return attempt.error(error);
+            }
+            return attempt.follow();
+        });
+        let client = Self::builder()
+            .redirect(redirects)
+            .dns_resolver(Arc::new(PublicResolver))
+            // A proxy would resolve names itself
+            .no_proxy()
+            .build()
+            .context("Could not build HTTP client")?;
+
+        return Ok(LiveClient {
+            client,
+            public_only: true,
+        });
+    }
+}
+
+#[async_trait]
+impl HttpClient for LiveClient {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, url: &str) -> Result<String> {
+        if self.public_only {
+            let parsed = Url::parse(url).with_context(|| return format!("Invalid URL {url}"))?;
+            if !is_public_url(&parsed) {
+                bail!("{url} isn't public");
+            }
+        }
+
+        return self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| return format!("Could not fetch {url}"))?
+            .text()
+            .await
+            .with_context(|| return format!("Could not read {url}"));
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_if_modified(&self, url: &str, state: &FetchState) -> Result<Fetched> {
+        let mut request = self.client.get(url);
+        if let Some(etag) = &state.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &state.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| return format!("Could not fetch {url}"))?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Fetched::NotModified);
+        }
+
+        let validator = |name| {
+            return response
+                .headers()
+                .get(name)
+                .and_then(|v| return v.to_str().ok())
+                .map(str::to_owned);
+        };
+        let state = FetchState {
+            etag: validator(header::ETAG),
+            last_modified: validator(header::LAST_MODIFIED),
+        };
+
+        return Ok(Fetched::Modified {
+            body: response
+                .text()
+                .await
+                .with_context(|| return format!("Could not read {url}"))?,
+            state,
+        });
+    }
+}
+
+/// Serves responses saved in a directory, named after the URL's host and path
+/// (ignoring the query), e.g. `https://api.lever.co/v0/postings/acme?mode=json`
+/// is read from `{dir}/api.lever.co/v0/postings/acme.json`. Paths with an
+/// extension keep it, so `/feed.rss` is read from `feed.rss`.
+pub struct FixtureClient {
+    dir: PathBuf,
+}
+
+impl FixtureClient {
+    pub fn new(dir: PathBuf) -> Self {
+        return FixtureClient { dir };
+    }
+
+    fn path(&self, url: &str) -> Result<PathBuf> {
+        let url = Url::parse(url).with_context(|| return format!("Invalid URL {url}"))?;
+        let host = url.host_str().unwrap_or_default();
+
+        let mut path = self.dir.join(host);
+        let mut extension = None;
+        for segment in url.path_segments().into_iter().flatten() {
+            // Keep requests inside the fixture directory
+            if segment.is_empty() || segment == ".." || segment == "." {
+                continue;
+            }
+            path.push(segment);
+            extension = Path::new(segment).extension();
+        }
+
+        if extension.is_some() {
+            return Ok(path);
+        }
+        // Appended rather than set, as the host has dots in it
+        let mut path = path.into_os_string();
+        path.push(".json");
+
+        return Ok(PathBuf::from(path));
+    }
+}
+
+#[async_trait]
+impl HttpClient for FixtureClient {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, url: &str) -> Result<String> {
+        let path = self.path(url)?;
+
+        return tokio::fs::read_to_string(&path).await.with_context(|| {
+            return format!("Could not read fixture {} for {url}", path.display());
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_addresses() {
+        let public = [
+            "https://example.com/jobs",
+            "http://93.184.215.14/",
+            "https://[2606:4700::1]/",
+        ];
+        for url in public {
+            assert!(is_public_url(&Url::parse(url).unwrap()), "{url}");
+        }
+
+        let private = [
+            "http://127.0.0.1:4321/",
+            "http://10.1.2.3/",
+            "http://172.16.0.1/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://0x7f.1/",
+            "http://2130706433/",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "file:///etc/passwd",
+            "ftp://example.com/",
+        ];
+        for url in private {
+            assert!(!is_public_url(&Url::parse(url).unwrap()), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_only_public_addresses() {
+        let resolved = PublicResolver.resolve("localhost".parse().unwrap()).await;
+        assert!(resolved.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetches_only_public_addresses() {
+        let client = LiveClient::public_only().unwrap();
+        for url in [
+            "http://127.0.0.1:4321/",
+            "http://localhost:4321/",
+            "http://[::1]:4321/",
+        ] {
+            assert!(client.get(url).await.is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn fixture_paths() {
+        let client = FixtureClient::new(PathBuf::from("fixtures"));
+        let cases = [
+            (
+                "https://api.lever.co/v0/postings/acme?mode=json",
+                "fixtures/api.lever.co/v0/postings/acme.json",
+            ),
+            (
+                "https://boards-api.greenhouse.io/v1/boards/acme/jobs?content=true",
+                "fixtures/boards-api.greenhouse.io/v1/boards/acme/jobs.json",
+            ),
+            (
+                "https://jobs.example.org/feed.rss",
+                "fixtures/jobs.example.org/feed.rss",
+            ),
+            (
+                "https://careers.example.com/jobs/backend-engineer.html#apply",
+                "fixtures/careers.example.com/jobs/backend-engineer.html",
+            ),
+            ("https://example.com/a//b/", "fixtures/example.com/a/b.json"),
+            ("https://example.com", "fixtures/example.com.json"),
+        ];
+        for (url, path) in cases {
+            assert_eq!(client.path(url).unwrap(), PathBuf::from(path), "{url}");
+        }
+
+        assert!(client.path("not a url").is_err());
+    }
+
+    #[test]
+    fn fixture_paths_stay_in_dir() {
+        let client = FixtureClient::new(PathBuf::from("fixtures"));
+        // Most dot segments are resolved away when the URL is parsed, the rest
+        // are skipped
+        let cases = [
+            "https://example.com/../../etc/passwd",
+            "https://example.com/a/%2e%2e/%2e%2e/%2e%2e/etc/passwd",
+            "https://example.com/./../a/../../etc/passwd",
+        ];
+        for url in cases {
+            let path = client.path(url).unwrap();
+            assert!(
+                path.starts_with("fixtures/example.com"),
+                "{url} read from {}",
+                path.display()
+            );
+            assert!(!path
+                .components()
+                .any(|c| return c == std::path::Component::ParentDir));
+        }
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Context, Result};
+use roxmltree::{Document, Node, ParsingOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+use super::{format_timestamp, html, utc_timestamp, work_mode, HttpClient};
+use crate::{
+    db::{
+        jobs::{EmploymentType, NewJob, Seniority},
+        sources::{FetchState, Source},
+    },
+    ingest::client::Fetched,
+    salary::Salary,
+};
+
+/// Which item fields hold each part of a job, for feeds which don't keep it
+/// in the standard ones. RSS and Atom fields are element names, prefixed as
+/// in the feed (`job:location`); JSON Feed fields are keys, with dots between
+/// nested ones (`_job.location`).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FieldMapping {
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub location: Option<String>,
+    pub salary: Option<String>,
+    pub description: Option<String>,
+    pub employment_type: Option<String>,
+    pub posted_at: Option<String>,
+    pub valid_through: Option<String>,
+}
+
+impl FieldMapping {
+    /// The parts of a job which can be mapped
+    pub const FIELDS: &'static [&'static str] = &[
+        "title",
+        "company",
+        "location",
+        "salary",
+        "description",
+        "employment_type",
+        "posted_at",
+        "valid_through",
+    ];
+
+    /// Maps a part of a job to an item field from a `part=field` pair, or
+    /// back to the standard field with an empty one
+    pub fn set(&mut self, pair: &str) -> Result<()> {
+        let Some((part, field)) = pair.split_once('=') else {
+            bail!("Field mappings look like location=job:location");
+        };
+
+        let mapped = match part.trim() {
+            "title" => &mut self.title,
+            "company" => &mut self.company,
+            "location" => &mut self.location,
+            "salary" => &mut self.salary,
+            "description" => &mut self.description,
+            "employment_type" => &mut self.employment_type,
+            "posted_at" => &mut self.posted_at,
+            "valid_through" => &mut self.valid_through,
+            part => bail!("Can't map {part}, only {}", FieldMapping::FIELDS.join(", ")),
+        };
+        *mapped = Some(field.trim().to_owned()).filter(|f| return !f.is_empty());
+
+        return Ok(());
+    }
+}
+
+/// An item of a feed, whichever format it's in
+#[derive(Clone, Copy)]
+enum Item<'a, 'input> {
+    Xml(Node<'a, 'input>),
+    Json(&'a Value),
+}
+
+/// Children of `node` called `name`, which may be prefixed with one of the
+/// document's namespace prefixes. Unprefixed names are in the node's own
+/// namespace.
+fn xml_children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    let (namespace, local) = match name.split_once(':') {
+        Some((prefix, local)) => (
+            node.lookup_namespace_uri(Some(prefix)).map(Some).ok_or(()),
+            local,
+        ),
+        None => (Ok(node.tag_name().namespace()), name),
+    };
+
+    return node.children().filter(move |child| {
+        return child.is_element()
+            && child.tag_name().name() == local
+            // An undeclared prefix matches nothing
+            && namespace == Ok(child.tag_name().namespace());
+    });
+}
+
+impl Item<'_, '_> {
+    /// The text of one of the item's fields, by element name or key path
+    fn field(&self, name: &str) -> Option<String> {
+        let text = match self {
+            Item::Xml(node) => xml_children(*node, name).next().map(|field| {
+                return field
+                    .descendants()
+                    .filter(Node::is_text)
+                    .filter_map(|text| return text.text())
+                    .collect::<String>();
+            }),
+            Item::Json(value) => name
+                .split('.')
+                .try_fold(*value, |value, key| return value.get(key))
+                .and_then(|value| {
+                    return match value {
+                        Value::String(s) => Some(s.clone()),
+                        Value::Number(n) => Some(n.to_string()),
+                        _ => None,
+                    };
+                }),
+        };
+
+        return text
+            .map(|text| return text.trim().to_owned())
+            .filter(|text| return !text.is_empty());
+    }
+
+    /// The first of `names` the item has
+    fn first(&self, names: &[&str]) -> Option<String> {
+        return names.iter().find_map(|name| return self.field(name));
+    }
+
+    /// Identifies the item from one poll to the next
+    fn guid(&self) -> Option<String> {
+        return match self {
+            Item::Xml(_) => self.first(&["guid", "id"]),
+            Item::Json(_) => self.field("id"),
+        };
+    }
+
+    /// The item's web page
+    fn link(&self) -> Option<String> {
+        return match self {
+            // Atom links are attributes, RSS ones text
+            Item::Xml(node) => xml_children(*node, "link").find_map(|link| {
+                return match link.attribute("href") {
+                    Some(href) => matches!(link.attribute("rel"), None | Some("alternate"))
+                        .then(|| return href.trim().to_owned()),
+                    None => link.text().map(|text| return text.trim().to_owned()),
+                };
+            }),
+            Item::Json(_) => self.first(&["url", "external_url"]),
+        };
+    }
+
+    /// The item's standard description, as plain text
+    fn description(&self) -> Option<String> {
+        return match self {
+            Item::Xml(_) => self
+                .first(&["content:encoded", "description", "content", "summary"])
+                .map(|d| return html::to_text(&d)),
+            Item::Json(_) => self
+                .field("content_html")
+                .map(|d| return html::to_text(&d))
+                .or_else(|| return self.first(&["content_text", "summary"])),
+        };
+    }
+
+    fn posted_at(&self) -> Option<String> {
+        return match self {
+            Item::Xml(_) => self.first(&["pubDate", "published", "updated", "dc:date"]),
+            Item::Json(_) => self.first(&["date_published", "date_modified"]),
+        };
+    }
+}
+
+/// A timestamp in either RSS's RFC 2822 or the RFC 3339 everything else uses
+fn feed_timestamp(text: &str) -> Option<String> {
+    return match OffsetDateTime::parse(text.trim(), &Rfc2822) {
+        Ok(at) => Some(format_timestamp(at)),
+        Err(_) => utc_timestamp(text),
+    };
+}
+
+fn job(source: &Source, mapping: &FieldMapping, item: Item) -> Option<NewJob> {
+    let mapped =
+        |field: &Option<String>| return field.as_deref().and_then(|f| return item.field(f));
+
+    let title = match &mapping.title {
+        Some(field) => item.field(field),
+        None => item.field("title"),
+    }?;
+    let location = mapped(&mapping.location).unwrap_or_default();
+    let salary = mapped(&mapping.salary).unwrap_or_default();
+    let employment_type = mapped(&mapping.employment_type)
+        .as_deref()
+        .and_then(EmploymentType::parse);
+    let url = item.link();
+
+    // Items with neither can't be told apart from one poll to the next
+    let key = item.guid().or_else(|| return url.clone())?;
+
+    return Some(NewJob {
+        id: format!(
+            "feed-{}-{}",
+            source.id,
+            &hex::encode(Sha256::digest(key.as_bytes()))[..16]
+        ),
+        seniority: match employment_type {
+            Some(EmploymentType::Intern) => Some(Seniority::Intern),
+            _ => Seniority::from_title(&title),
+        },
+        work_mode: work_mode(&location).or_else(|| return work_mode(&title)),
+        company: mapped(&mapping.company).unwrap_or_else(|| return source.company.clone()),
+        salary_range: Salary::parse(&salary),
+        salary,
+        description: match &mapping.description {
+            Some(field) => item.field(field).map(|d| return html::to_text(&d)),
+            None => item.description(),
+        }
+        .unwrap_or_default(),
+        employment_type,
+        posted_at: match &mapping.posted_at {
+            Some(field) => item.field(field),
+            None => item.posted_at(),
+        }
+        .as_deref()
+        .and_then(feed_timestamp),
+        valid_through: mapped(&mapping.valid_through)
+            .as_deref()
+            .and_then(feed_timestamp),
+        title,
+        location,
+        url,
+    });
+}
+
+/// The jobs in an RSS 2.0, Atom or JSON Feed document, once each
+pub fn parse(source: &Source, body: &str) -> Result<Vec<NewJob>> {
+    let mapping: FieldMapping = match &source.field_mapping {
+        Some(mapping) => {
+            serde_json::from_str(mapping).context("Could not parse feed field mapping")?
+        }
+        None => FieldMapping::default(),
+    };
+    let body = body.trim_start_matches('\u{feff}').trim_start();
+
+    let json;
+    let document;
+    let items: Vec<Item> = if body.starts_with('{') {
+        json = serde_json::from_str::<Value>(body).context("Could not parse JSON Feed")?;
+        json.get("items")
+            .and_then(Value::as_array)
+            .context("JSON Feeds have a list of items")?
+            .iter()
+            .map(Item::Json)
+            .collect()
+    } else {
+        document = Document::parse_with_options(
+            body,
+            ParsingOptions {
+                allow_dtd: true,
+                ..ParsingOptions::default()
+            },
+        )
+        .context("Could not parse feed")?;
+
+        let root = document.root_element();
+        let item = match root.tag_name().name() {
+            // RSS 2.0 items are in the channel, RSS 1.0 ones beside it
+            "rss" | "RDF" => "item",
+            "feed" => "entry",
+            other => bail!("Expected an RSS or Atom feed, found <{other}>"),
+        };
+        root.descendants()
+            .filter(|node| return node.is_element() && node.tag_name().name() == item)
+            .map(Item::Xml)
+            .collect()
+    };
+
+    let mut seen = HashSet::new();
+    return Ok(items
+        .into_iter()
+        .filter_map(|item| return job(source, &mapping, item))
+        .filter(|job| return seen.insert(job.id.clone()))
+        .collect());
+}
+
+/// The jobs in the source's feed, or `None` if it hasn't changed since `state`
+pub async fn fetch(
+    client: &dyn HttpClient,
+    source: &Source,
+    state: &FetchState,
+) -> Result<Option<(Vec<NewJob>, FetchState)>> {
+    return match client.get_if_modified(&source.board, state).await? {
+        Fetched::NotModified => Ok(None),
+        Fetched::Modified { body, state } => Ok(Some((parse(source, &body)?, state))),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{jobs::WorkMode, sources::SourceKind};
+
+    fn source(board: &str, field_mapping: Option<&str>) -> Source {
+        return Source {
+            field_mapping: field_mapping.map(str::to_owned),
+            ..super::super::test_source(SourceKind::Feed, board)
+        };
+    }
+
+    fn id(key: &str) -> String {
+        return format!(
+            "feed-1-{}",
+            &hex::encode(Sha256::digest(key.as_bytes()))[..16]
+        );
+    }
+
+    #[test]
+    fn parses_rss() {
+        let source = source(
+            "https://jobs.example.org/feed.rss",
+            Some(
+                r#"{"company": "job:company", "location": "job:location",
+                    "salary": "job:salary", "employment_type": "job:type"}"#,
+            ),
+        );
+        let jobs = parse(
+            &source,
+            include_str!("../../fixtures/jobs.example.org/feed.rss"),
+        )
+        .unwrap();
+        // The repeated item is only imported once
+        assert_eq!(jobs.len(), 2);
+
+        let job = &jobs[0];
+        assert_eq!(job.id, id("example-jobs-101"));
+        assert_eq!(job.title, "Platform Engineer (Remote)");
+        assert_eq!(job.company, "Northwind");
+        assert_eq!(job.location, "Remote, EU");
+        assert_eq!(job.work_mode, Some(WorkMode::Remote));
+        assert_eq!(job.salary, "€70k–€90k");
+        let salary = job.salary_range.as_ref().unwrap();
+        assert_eq!(
+            (salary.min, salary.max, salary.currency.as_str()),
+            (70_000.0, 90_000.0, "EUR")
+        );
+        assert_eq!(job.employment_type, Some(EmploymentType::FullTime));
+        assert_eq!(
+            job.url.as_deref(),
+            Some("https://jobs.example.org/jobs/101")
+        );
+        assert_eq!(job.posted_at.as_deref(), Some("2024-05-07 09:30:00"));
+        assert_eq!(job.description, "Keep our Kubernetes clusters happy.");
+
+        let job = &jobs[1];
+        // Without a GUID the link identifies it
+        assert_eq!(job.id, id("https://jobs.example.org/jobs/102"));
+        assert_eq!(job.company, "Contoso");
+        assert_eq!(job.seniority, Some(Seniority::Junior));
+        assert_eq!(job.employment_type, Some(EmploymentType::Contractor));
+        assert_eq!(job.posted_at.as_deref(), Some("2024-05-06 12:00:00"));
+        // From content:encoded, in its own namespace
+        assert!(
+            job.description.contains("SQL and dashboards."),
+            "{}",
+            job.description
+        );
+        assert!(job.description.contains("Looker"), "{}", job.description);
+    }
+
+    #[test]
+    fn parses_atom() {
+        let source = source("https://weworkremotely.example.com/feed.atom", None);
+        let jobs = parse(
+            &source,
+            include_str!("../../fixtures/weworkremotely.example.com/feed.atom"),
+        )
+        .unwrap();
+        assert_eq!(jobs.len(), 1);
+
+        let job = &jobs[0];
+        assert_eq!(job.id, id("tag:weworkremotely.example.com,2024:jobs/55"));
+        assert_eq!(job.title, "Acme: Senior iOS Developer");
+        assert_eq!(job.company, "Acme");
+        assert_eq!(job.seniority, Some(Seniority::Senior));
+        // The alternate link, not the API one
+        assert_eq!(
+            job.url.as_deref(),
+            Some("https://weworkremotely.example.com/jobs/55")
+        );
+        assert_eq!(job.posted_at.as_deref(), Some("2024-05-08 14:00:00"));
+        assert_eq!(job.description, "Swift, SwiftUI & a love of detail.");
+    }
+
+    #[test]
+    fn parses_json_feed() {
+        let source = source(
+            "https://boards.example.net/feed.json",
+            Some(
+                r#"{"location": "_job.location", "salary": "_job.salary",
+                    "employment_type": "_job.type", "valid_through": "_job.closes"}"#,
+            ),
+        );
+        let jobs = parse(
+            &source,
+            include_str!("../../fixtures/boards.example.net/feed.json"),
+        )
+        .unwrap();
+        // The item with neither an id nor a link is skipped
+        assert_eq!(jobs.len(), 2);
+
+        let job = &jobs[0];
+        assert_eq!(job.id, id("2024-0042"));
+        assert_eq!(job.title, "Backend Developer (Hybrid)");
+        assert_eq!(job.location, "Amsterdam");
+        assert_eq!(job.work_mode, Some(WorkMode::Hybrid));
+        let salary = job.salary_range.as_ref().unwrap();
+        assert_eq!((salary.min, salary.max), (120_000.0, 140_000.0));
+        assert_eq!(job.employment_type, Some(EmploymentType::FullTime));
+        assert_eq!(job.posted_at.as_deref(), Some("2024-05-01 08:00:00"));
+        assert_eq!(job.valid_through.as_deref(), Some("2024-06-01 00:00:00"));
+        assert_eq!(job.description, "Go services & Postgres.");
+
+        let job = &jobs[1];
+        assert_eq!(job.id, id("https://boards.example.net/jobs/43"));
+        assert_eq!(job.seniority, Some(Seniority::Intern));
+        assert_eq!(job.description, "Figma < everything else, apparently.");
+    }
+
+    #[test]
+    fn imports_items_once() {
+        let source = source("https://example.com/feed", None);
+        let rss = r#"<rss version="2.0"><channel>
+            <item><title>A</title><guid>1</guid><link>https://example.com/a</link></item>
+            <item><title>A again</title><guid>1</guid><link>https://example.com/a2</link></item>
+            <item><title>B</title><link>https://example.com/b</link></item>
+            <item><title>B again</title><link>https://example.com/b</link></item>
+            <item><title>No key</title></item>
+        </channel></rss>"#;
+
+        let jobs = parse(&source, rss).unwrap();
+        let titles = jobs
+            .iter()
+            .map(|j| return j.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["A", "B"]);
+        assert_eq!(jobs[0].id, id("1"));
+        assert_eq!(jobs[1].id, id("https://example.com/b"));
+
+        // Ids are per source, so two feeds can list the same item
+        let other = Source {
+            id: 2,
+            ..source.clone()
+        };
+        assert_ne!(parse(&other, rss).unwrap()[0].id, jobs[0].id);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        let source = source("https://example.com/feed", None);
+        assert!(parse(&source, "<html><body></body></html>").is_err());
+        assert!(parse(&source, r#"{"title": "No items"}"#).is_err());
+        assert!(parse(&source, "not a feed").is_err());
+    }
+
+    #[test]
+    fn sets_field_mappings() {
+        let mut mapping = FieldMapping::default();
+
+        mapping.set("location=job:location").unwrap();
+        mapping.set(" salary = _job.salary ").unwrap();
+        assert_eq!(mapping.location.as_deref(), Some("job:location"));
+        assert_eq!(mapping.salary.as_deref(), Some("_job.salary"));
+
+        // Back to the standard field
+        mapping.set("location=").unwrap();
+        assert_eq!(mapping.location, None);
+
+        assert!(mapping.set("location").is_err());
+        assert!(mapping.set("url=link").is_err());
+        for field in FieldMapping::FIELDS {
+            mapping.set(&format!("{field}=x")).unwrap();
+        }
+    }
+
+    #[test]
+    fn parses_feed_timestamps() {
+        let cases = [
+            // RFC 2822, as in RSS
+            ("Tue, 07 May 2024 09:30:00 GMT", Some("2024-05-07 09:30:00")),
+            (
+                "Mon, 06 May 2024 14:00:00 +0200",
+                Some("2024-05-06 12:00:00"),
+            ),
+            // RFC 3339, as in Atom and JSON Feed
+            ("2024-05-08T10:00:00-04:00", Some("2024-05-08 14:00:00")),
+            (" 2024-05-01T08:00:00Z ", Some("2024-05-01 08:00:00")),
+            ("2024-06-01", Some("2024-06-01 00:00:00")),
+            ("next week", None),
+            ("", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(feed_timestamp(text).as_deref(), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn finds_prefixed_children() {
+        let xml = r#"<item xmlns:job="urn:job" xmlns:j="urn:job" xmlns:other="urn:other">
+            <location>Plain</location>
+            <job:location>Job</job:location>
+            <other:location>Other</other:location>
+        </item>"#;
+        let document = Document::parse(xml).unwrap();
+        let item = document.root_element();
+
+        let texts = |name| {
+            return xml_children(item, name)
+                .filter_map(|n| return n.text())
+                .collect::<Vec<_>>();
+        };
+        // Unprefixed names are in the item's own (here no) namespace
+        assert_eq!(texts("location"), ["Plain"]);
+        assert_eq!(texts("job:location"), ["Job"]);
+        // Prefixes are matched by the namespace they stand for
+        assert_eq!(texts("j:location"), ["Job"]);
+        assert_eq!(texts("other:location"), ["Other"]);
+        assert!(texts("undeclared:location").is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{html, job_id, utc_timestamp, work_mode, HttpClient};
+use crate::db::{
+    jobs::{NewJob, Seniority},
+    sources::Source,
+};
+
+/// Greenhouse's public job board API
+const API_URL: &str = "https://boards-api.greenhouse.io/v1";
+
+#[derive(Deserialize)]
+struct Board {
+    jobs: Vec<Job>,
+}
+
+#[derive(Deserialize)]
+struct Job {
+    id: i64,
+    title: String,
+    updated_at: Option<String>,
+    first_published: Option<String>,
+    location: Option<Location>,
+    absolute_url: Option<String>,
+    /// HTML, escaped a second time
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Location {
+    name: String,
+}
+
+/// Every job on the source's board, from `/boards/{token}/jobs?content=true`
+pub async fn fetch(client: &dyn HttpClient, source: &Source) -> Result<Vec<NewJob>> {
+    let api_url = source.api_url.as_deref().unwrap_or(API_URL);
+    let url = format!(
+        "{}/boards/{}/jobs?content=true",
+        api_url.trim_end_matches('/'),
+        source.board
+    );
+
+    return parse(source, &client.get(&url).await?);
+}
+
+pub fn parse(source: &Source, body: &str) -> Result<Vec<NewJob>> {
+    let board: Board =
+        serde_json::from_str(body).context("Could not parse Greenhouse job board")?;
+
+    return Ok(board
+        .jobs
+        .into_iter()
+        .map(|job| {
+            let location = job.location.map(|l| return l.name).unwrap_or_default();
+            let description = job
+                .content
+                .as_deref()
+                .map(|c| return html::to_text(&html::decode_entities(c)))
+                .unwrap_or_default();
+
+            return NewJob {
+                id: job_id(source, &job.id.to_string()),
+                seniority: Seniority::from_title(&job.title),
+                work_mode: work_mode(&location),
+                title: job.title,
+                company: source.company.clone(),
+                location,
+                // Only shared by the (per job) pay transparency API
+                salary: String::new(),
+                salary_range: None,
+                description,
+                employment_type: None,
+                url: job.absolute_url,
+                posted_at: job
+                    .first_published
+                    .or(job.updated_at)
+                    .as_deref()
+                    .and_then(utc_timestamp),
+                valid_through: None,
+            };
+        })
+        .collect());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{jobs::WorkMode, sources::SourceKind};
+
+    #[test]
+    fn parses_board() {
+        let source = super::super::test_source(SourceKind::Greenhouse, "acme");
+        let jobs = parse(
+            &source,
+            include_str!("../../fixtures/boards-api.greenhouse.io/v1/boards/acme/jobs.json"),
+        )
+        .unwrap();
+        assert_eq!(jobs.len(), 2);
+
+        let job = &jobs[0];
+        assert_eq!(job.id, "greenhouse-acme-4012345");
+        assert_eq!(job.title, "Senior Backend Engineer, Payments");
+        assert_eq!(job.company, "Acme");
+        assert_eq!(job.location, "Toronto, ON (Hybrid)");
+        assert_eq!(job.work_mode, Some(WorkMode::Hybrid));
+        assert_eq!(job.seniority, Some(Seniority::Senior));
+        assert_eq!(job.salary_range, None);
+        // First published at noon in Toronto
+        assert_eq!(job.posted_at.as_deref(), Some("2024-02-28 17:00:00"));
+        assert_eq!(
+            job.url.as_deref(),
+            Some("https://boards.greenhouse.io/acme/jobs/4012345")
+        );
+        // The doubly escaped HTML is decoded to text
+        assert!(
+            job.description.contains("Design APIs in Rust & Go"),
+            "{}",
+            job.description
+        );
+        assert!(!job.description.contains('<'), "{}", job.description);
+
+        let job = &jobs[1];
+        assert_eq!(job.id, "greenhouse-acme-4012346");
+        assert_eq!(job.location, "Remote - Canada");
+        assert_eq!(job.work_mode, Some(WorkMode::Remote));
+        // Never published, so when it was last updated
+        assert_eq!(job.posted_at.as_deref(), Some("2024-03-05 16:40:00"));
+    }
+
+    #[test]
+    fn rejects_other_json() {
+        let source = super::super::test_source(SourceKind::Greenhouse, "acme");
+        assert!(parse(&source, "[]").is_err());
+    }
+}
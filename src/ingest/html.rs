@@ -0,0 +1,159 @@
+/// Elements which start a new line of text
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "br",
+    "div",
+    "dl",
+    "dd",
+    "dt",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Named character references worth decoding, beyond those HTML escaping produces
+const ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", " "),
+    ("lsquo", "‘"),
+    ("rsquo", "’"),
+    ("ldquo", "“"),
+    ("rdquo", "”"),
+    ("ndash", "–"),
+    ("mdash", "—"),
+    ("hellip", "…"),
+    ("bull", "•"),
+    ("middot", "·"),
+    ("euro", "€"),
+    ("pound", "£"),
+    ("yen", "¥"),
+];
+
+/// Decodes the character references in `text`, leaving unknown ones as is
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest[1..]
+            .find(';')
+            .filter(|end| return *end <= 10)
+            .map(|end| return &rest[1..=end]);
+        let character = reference.and_then(|reference| {
+            return match reference.strip_prefix('#') {
+                Some(number) => match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => number.parse().ok(),
+                }
+                .and_then(char::from_u32)
+                .map(String::from),
+                None => ENTITIES
+                    .iter()
+                    .find(|(name, _)| return *name == reference)
+                    .map(|(_, c)| return (*c).to_owned()),
+            };
+        });
+
+        match (reference, character) {
+            (Some(reference), Some(character)) => {
+                decoded.push_str(&character);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    return decoded;
+}
+
+/// Plain text out of an HTML fragment: tags dropped, entities decoded and
+/// block elements put on lines of their own
+pub fn to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+
+        let tag = &rest[1..end];
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| return c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if BLOCK_TAGS.contains(&name.as_str()) {
+            text.push('\n');
+        }
+        if name == "li" && !tag.starts_with('/') {
+            text.push_str("• ");
+        }
+
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+
+    return decode_entities(&text)
+        .lines()
+        .map(|line| return line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| return !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+}
+
+/// The contents of every `<script type="application/ld+json">` in a page
+pub fn json_ld_scripts(html: &str) -> Vec<&str> {
+    // ASCII lowercasing keeps byte offsets the same, so they index `html` too
+    let lowercase = html.to_ascii_lowercase();
+    let mut scripts = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = lowercase[offset..].find("<script") {
+        let start = offset + start;
+        let Some(tag_end) = lowercase[start..].find('>').map(|end| return start + end) else {
+            break;
+        };
+        let Some(end) = lowercase[tag_end..]
+            .find("</script")
+            .map(|end| return tag_end + end)
+        else {
+            break;
+        };
+
+        if lowercase[start..tag_end].contains("application/ld+json") {
+            scripts.push(&html[tag_end + 1..end]);
+        }
+        offset = end;
+    }
+
+    return scripts;
+}
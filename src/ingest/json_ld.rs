@@ -0,0 +1,271 @@
+use anyhow::Result;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{html, utc_timestamp, work_mode, HttpClient};
+use crate::{
+    db::jobs::{EmploymentType, NewJob, Seniority, WorkMode},
+    salary::{Salary, SalaryPeriod},
+};
+
+/// Whether `value` is a schema.org object of type `name`
+fn is_type(value: &Value, name: &str) -> bool {
+    let matches = |t: &Value| {
+        return t.as_str().is_some_and(|t| {
+            return t == name || t.ends_with(&format!("schema.org/{name}"));
+        });
+    };
+
+    return match value.get("@type") {
+        Some(Value::Array(types)) => types.iter().any(matches),
+        Some(t) => matches(t),
+        None => false,
+    };
+}
+
+/// Every JobPosting in a JSON-LD document, wherever it's nested: at the
+/// root, in `@graph`, as a page's `mainEntity`, ...
+fn collect_postings<'a>(value: &'a Value, postings: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_postings(value, postings);
+            }
+        }
+        Value::Object(_) if is_type(value, "JobPosting") => postings.push(value),
+        Value::Object(object) => {
+            for value in object.values() {
+                collect_postings(value, postings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Text out of a property which may be a string, number, named object or a
+/// list of them
+fn text(value: &Value) -> Option<String> {
+    return match value {
+        Value::String(s) => Some(s.trim().to_owned()).filter(|s| return !s.is_empty()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Object(object) => object.get("name").and_then(text),
+        Value::Array(values) => values.iter().find_map(text),
+        _ => None,
+    };
+}
+
+/// A number out of a property which may be a number or text like "120,000"
+fn number(value: &Value) -> Option<f64> {
+    return match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.replace([',', ' '], "").parse().ok(),
+        _ => None,
+    };
+}
+
+fn field<'a>(posting: &'a Value, name: &str) -> Option<&'a Value> {
+    return posting.get(name).filter(|v| return !v.is_null());
+}
+
+/// A list property, which may also be given as a single value
+fn list<'a>(posting: &'a Value, name: &str) -> Vec<&'a Value> {
+    return match field(posting, name) {
+        Some(Value::Array(values)) => values.iter().collect(),
+        Some(value) => vec![value],
+        None => Vec::new(),
+    };
+}
+
+/// A Place's address as "City, Region, Country"
+fn place(place: &Value) -> Option<String> {
+    let Some(address) = place.get("address") else {
+        return text(place);
+    };
+    if let Value::String(address) = address {
+        return Some(address.trim().to_owned());
+    }
+
+    let parts = ["addressLocality", "addressRegion", "addressCountry"]
+        .into_iter()
+        .filter_map(|part| return address.get(part).and_then(text))
+        .collect::<Vec<_>>();
+
+    return (!parts.is_empty()).then(|| return parts.join(", "));
+}
+
+fn is_remote(posting: &Value) -> bool {
+    return list(posting, "jobLocationType")
+        .into_iter()
+        .any(|t| return t.as_str() == Some("TELECOMMUTE"));
+}
+
+fn location(posting: &Value) -> String {
+    let places = list(posting, "jobLocation")
+        .into_iter()
+        .filter_map(place)
+        .collect::<Vec<_>>();
+    if !places.is_empty() {
+        return places.join("; ");
+    }
+
+    if is_remote(posting) {
+        return match list(posting, "applicantLocationRequirements")
+            .into_iter()
+            .find_map(text)
+        {
+            Some(region) => format!("Remote ({region})"),
+            None => "Remote".to_owned(),
+        };
+    }
+
+    return String::new();
+}
+
+/// `baseSalary`, a MonetaryAmount holding a QuantitativeValue or a number
+fn salary(posting: &Value) -> Option<Salary> {
+    let base = field(posting, "baseSalary")?;
+    let currency = base
+        .get("currency")
+        .or_else(|| return field(posting, "salaryCurrency"))
+        .and_then(Value::as_str)?
+        .trim()
+        .to_uppercase();
+
+    let value = base.get("value").unwrap_or(base);
+    let (min, max) = match value {
+        Value::Object(_) => {
+            let exact = value.get("value").and_then(number);
+            let min = value.get("minValue").and_then(number).or(exact);
+            let max = value.get("maxValue").and_then(number).or(exact).or(min);
+            (min.or(max)?, max?)
+        }
+        value => (number(value)?, number(value)?),
+    };
+
+    let period = match value.get("unitText").and_then(Value::as_str) {
+        Some("HOUR") => SalaryPeriod::Hourly,
+        Some("MONTH") => SalaryPeriod::Monthly,
+        Some("YEAR") | None => SalaryPeriod::Yearly,
+        // Daily and weekly pay don't fit a salary
+        Some(_) => return None,
+    };
+
+    return Some(Salary {
+        min: min.min(max),
+        max: max.max(min),
+        currency,
+        period,
+        equity: false,
+    });
+}
+
+/// A stable id for a posting, so importing it again updates it
+fn posting_id(
+    company: &str,
+    identifier: Option<&str>,
+    url: Option<&str>,
+    title: &str,
+    location: &str,
+) -> String {
+    let key = match (identifier, url) {
+        (Some(identifier), _) => format!("{company}\n{identifier}"),
+        (None, Some(url)) => url.to_owned(),
+        (None, None) => format!("{company}\n{title}\n{location}"),
+    };
+
+    return format!(
+        "jsonld-{}",
+        &hex::encode(Sha256::digest(key.as_bytes()))[..16]
+    );
+}
+
+/// The id of a posting uploaded or linked to by `user_id`. Anyone can write
+/// anything in a page, so user imports only ever update the importer's own
+/// copy of a posting.
+pub fn uploaded_id(posting_id: &str, user_id: &str) -> String {
+    let key = format!("{user_id}\n{posting_id}");
+    return format!(
+        "jsonld-{}",
+        &hex::encode(Sha256::digest(key.as_bytes()))[..16]
+    );
+}
+
+fn posting(posting: &Value, page_url: Option<&str>) -> Option<NewJob> {
+    let title = field(posting, "title")
+        .or_else(|| return field(posting, "name"))
+        .and_then(text)?;
+    let company = field(posting, "hiringOrganization")
+        .and_then(text)
+        .unwrap_or_else(|| return "Unknown company".to_owned());
+    let location = location(posting);
+    let url = field(posting, "url")
+        .and_then(Value::as_str)
+        .or(page_url)
+        .map(str::to_owned);
+    let identifier = field(posting, "identifier").and_then(|i| {
+        return i.get("value").and_then(text).or_else(|| return text(i));
+    });
+    let employment_type = list(posting, "employmentType")
+        .into_iter()
+        .find_map(|t| return t.as_str().and_then(EmploymentType::parse));
+
+    return Some(NewJob {
+        id: posting_id(
+            &company,
+            identifier.as_deref(),
+            url.as_deref(),
+            &title,
+            &location,
+        ),
+        seniority: match employment_type {
+            Some(EmploymentType::Intern) => Some(Seniority::Intern),
+            _ => Seniority::from_title(&title),
+        },
+        work_mode: match is_remote(posting) {
+            true => Some(WorkMode::Remote),
+            false => work_mode(&location),
+        },
+        salary: String::new(),
+        salary_range: salary(posting),
+        description: field(posting, "description")
+            .and_then(Value::as_str)
+            .map(|d| return html::to_text(&html::decode_entities(d)))
+            .unwrap_or_default(),
+        employment_type,
+        posted_at: field(posting, "datePosted")
+            .and_then(Value::as_str)
+            .and_then(utc_timestamp),
+        valid_through: field(posting, "validThrough")
+            .and_then(Value::as_str)
+            .and_then(utc_timestamp),
+        title,
+        company,
+        location,
+        url,
+    });
+}
+
+/// Every schema.org JobPosting embedded in a page as JSON-LD, skipping
+/// scripts which aren't valid JSON
+pub fn extract(html: &str, page_url: Option<&str>) -> Vec<NewJob> {
+    let documents = html::json_ld_scripts(html)
+        .into_iter()
+        .filter_map(|script| return serde_json::from_str::<Value>(script.trim()).ok())
+        .collect::<Vec<_>>();
+
+    let mut postings = Vec::new();
+    for document in &documents {
+        collect_postings(document, &mut postings);
+    }
+
+    return postings
+        .into_iter()
+        .filter_map(|p| return posting(p, page_url))
+        .collect();
+}
+
+/// Every JobPosting on the page at `url`
+pub async fn fetch(client: &dyn HttpClient, url: &str) -> Result<Vec<NewJob>> {
+    let page = client.get(url).await?;
+    return Ok(extract(&page, Some(url)));
+}
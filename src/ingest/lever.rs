@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use super::{format_timestamp, html, job_id, work_mode, HttpClient};
+use crate::{
+    db::{
+        jobs::{EmploymentType, NewJob, Seniority, WorkMode},
+        sources::Source,
+    },
+    salary::{Salary, SalaryPeriod},
+};
+
+/// Lever's public postings API
+const API_URL: &str = "https://api.lever.co/v0";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Posting {
+    id: String,
+    text: String,
+    #[serde(default)]
+    categories: Categories,
+    /// Milliseconds since the epoch
+    created_at: Option<i64>,
+    description_plain: Option<String>,
+    #[serde(default)]
+    lists: Vec<List>,
+    additional_plain: Option<String>,
+    hosted_url: Option<String>,
+    workplace_type: Option<String>,
+    salary_range: Option<SalaryRange>,
+}
+
+#[derive(Default, Deserialize)]
+struct Categories {
+    location: Option<String>,
+    /// Full-time, Contract, ...
+    commitment: Option<String>,
+}
+
+/// A titled section of the description, e.g. requirements
+#[derive(Deserialize)]
+struct List {
+    text: String,
+    /// HTML list items
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct SalaryRange {
+    currency: String,
+    /// `per-year-salary`, `per-month-salary`, `per-hour-wage`, ...
+    interval: String,
+    min: f64,
+    max: f64,
+}
+
+impl SalaryRange {
+    fn salary(self) -> Option<Salary> {
+        let period = match self.interval.as_str() {
+            "per-year-salary" => SalaryPeriod::Yearly,
+            "per-month-salary" => SalaryPeriod::Monthly,
+            "per-hour-wage" => SalaryPeriod::Hourly,
+            // Weekly, daily and one-off pay don't fit a salary
+            _ => return None,
+        };
+
+        return Some(Salary {
+            min: self.min.min(self.max),
+            max: self.max.max(self.min),
+            currency: self.currency.to_uppercase(),
+            period,
+            equity: false,
+        });
+    }
+}
+
+/// Every posting on the source's site, from `/postings/{site}?mode=json`
+pub async fn fetch(client: &dyn HttpClient, source: &Source) -> Result<Vec<NewJob>> {
+    let api_url = source.api_url.as_deref().unwrap_or(API_URL);
+    let url = format!(
+        "{}/postings/{}?mode=json",
+        api_url.trim_end_matches('/'),
+        source.board
+    );
+
+    return parse(source, &client.get(&url).await?);
+}
+
+pub fn parse(source: &Source, body: &str) -> Result<Vec<NewJob>> {
+    let postings: Vec<Posting> =
+        serde_json::from_str(body).context("Could not parse Lever postings")?;
+
+    return Ok(postings
+        .into_iter()
+        .map(|posting| {
+            let location = posting.categories.location.unwrap_or_default();
+
+            let mut description = posting.description_plain.unwrap_or_default();
+            for list in posting.lists {
+                description.push_str(&format!(
+                    "\n\n{}\n{}",
+                    list.text,
+                    html::to_text(&list.content)
+                ));
+            }
+            if let Some(additional) = posting.additional_plain {
+                description.push_str(&format!("\n\n{additional}"));
+            }
+
+            return NewJob {
+                id: job_id(source, &posting.id),
+                seniority: Seniority::from_title(&posting.text),
+                work_mode: match posting.workplace_type.as_deref() {
+                    Some("remote") => Some(WorkMode::Remote),
+                    Some("hybrid") => Some(WorkMode::Hybrid),
+                    Some("onsite") => Some(WorkMode::Onsite),
+                    _ => work_mode(&location),
+                },
+                title: posting.text,
+                company: source.company.clone(),
+                location,
+                salary: String::new(),
+                salary_range: posting.salary_range.and_then(SalaryRange::salary),
+                description: description.trim().to_owned(),
+                employment_type: posting
+                    .categories
+                    .commitment
+                    .as_deref()
+                    .and_then(EmploymentType::parse),
+                url: posting.hosted_url,
+                posted_at: posting
+                    .created_at
+                    .and_then(|ms| {
+                        return OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000)
+                            .ok();
+                    })
+                    .map(format_timestamp),
+                valid_through: None,
+            };
+        })
+        .collect());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sources::SourceKind;
+
+    #[test]
+    fn parses_postings() {
+        let source = super::super::test_source(SourceKind::Lever, "acme");
+        let jobs = parse(
+            &source,
+            include_str!("../../fixtures/api.lever.co/v0/postings/acme.json"),
+        )
+        .unwrap();
+        assert_eq!(jobs.len(), 2);
+
+        let job = &jobs[0];
+        assert_eq!(job.id, "lever-acme-5f1c2a3b-8d4e-4f60-9a7b-0c1d2e3f4a5b");
+        assert_eq!(job.title, "Staff Site Reliability Engineer");
+        assert_eq!(job.location, "Vancouver, BC");
+        // From the workplace type, as the location doesn't say
+        assert_eq!(job.work_mode, Some(WorkMode::Onsite));
+        assert_eq!(job.employment_type, Some(EmploymentType::FullTime));
+        assert_eq!(
+            job.salary_range,
+            Some(Salary {
+                min: 180_000.0,
+                max: 220_000.0,
+                currency: "CAD".to_owned(),
+                period: SalaryPeriod::Yearly,
+                equity: false,
+            })
+        );
+        assert_eq!(job.posted_at.as_deref(), Some("2024-03-01 00:00:00"));
+        // Lists and the closing text follow the description
+        assert!(job.description.starts_with("Join the platform team"));
+        assert!(
+            job.description.contains("Requirements\n"),
+            "{}",
+            job.description
+        );
+        assert!(job.description.ends_with("equal opportunity employer."));
+
+        let job = &jobs[1];
+        assert_eq!(job.id, "lever-acme-9a8b7c6d-5e4f-4321-8765-43210fedcba9");
+        assert_eq!(job.work_mode, Some(WorkMode::Remote));
+        assert_eq!(job.employment_type, Some(EmploymentType::Contractor));
+        let salary = job.salary_range.as_ref().unwrap();
+        assert_eq!(
+            (
+                salary.min,
+                salary.max,
+                salary.currency.as_str(),
+                salary.period
+            ),
+            (90.0, 110.0, "USD", SalaryPeriod::Hourly)
+        );
+        assert_eq!(job.posted_at.as_deref(), Some("2024-03-02 00:00:00"));
+    }
+}
@@ -0,0 +1,210 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime,
+    UtcOffset,
+};
+use tracing::{error, info};
+
+use crate::db::{
+    jobs::{self, NewJob, WorkMode},
+    lifecycle,
+    sources::{self, Source, SourceKind},
+    DbPool,
+};
+
+mod client;
+pub mod feed;
+mod greenhouse;
+mod html;
+pub mod json_ld;
+mod lever;
+
+pub use client::{FixtureClient, HttpClient, LiveClient};
+
+/// Our id for a job imported from `source`, stable across imports
+fn job_id(source: &Source, external_id: &str) -> String {
+    return format!("{}-{}-{external_id}", source.kind.slug(), source.board);
+}
+
+/// A timestamp as SQLite's `CURRENT_TIMESTAMP` would have it
+fn format_timestamp(at: OffsetDateTime) -> String {
+    return at
+        .to_offset(UtcOffset::UTC)
+        .format(format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second]"
+        ))
+        .expect("timestamps to format");
+}
+
+/// An RFC 3339 timestamp, or the day of one, in UTC as SQLite's
+/// `CURRENT_TIMESTAMP` would have it
+fn utc_timestamp(text: &str) -> Option<String> {
+    let text = text.trim();
+    if let Ok(at) = OffsetDateTime::parse(text, &Rfc3339) {
+        return Some(format_timestamp(at));
+    }
+
+    // Without an offset the time is ambiguous, so only the day is kept
+    let day = Date::parse(text.get(..10)?, format_description!("[year]-[month]-[day]")).ok()?;
+    return Some(format_timestamp(day.midnight().assume_utc()));
+}
+
+/// A source as it would be stored, for parsing fixtures
+#[cfg(test)]
+fn test_source(kind: SourceKind, board: &str) -> Source {
+    return Source {
+        id: 1,
+        kind,
+        board: board.to_owned(),
+        company: "Acme".to_owned(),
+        api_url: None,
+        enabled: true,
+        field_mapping: None,
+        interval_minutes: 60,
+        next_run_at: None,
+        failures: 0,
+    };
+}
+
+/// Guesses the work mode from how a location is described
+fn work_mode(location: &str) -> Option<WorkMode> {
+    let location = location.to_lowercase();
+
+    if location.contains("remote") {
+        return Some(WorkMode::Remote);
+    }
+    if location.contains("hybrid") {
+        return Some(WorkMode::Hybrid);
+    }
+    return None;
+}
+
+/// Whether `board` can be put in a job board API URL as is, or for feeds
+/// whether it's a web URL
+pub fn is_valid_board(kind: SourceKind, board: &str) -> bool {
+    if kind == SourceKind::Feed {
+        return reqwest::Url::parse(board)
+            .is_ok_and(|url| return matches!(url.scheme(), "http" | "https"));
+    }
+
+    return !board.is_empty()
+        && board
+            .chars()
+            .all(|c| return c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+}
+
+/// Imports every job currently on a source's board, returning how many there were
+#[tracing::instrument(skip(db, client, source), fields(source.id = source.id, source.kind = %source.kind, source.board = source.board))]
+pub async fn import_source(db: &DbPool, client: &dyn HttpClient, source: &Source) -> Result<usize> {
+    let (jobs, state) = match source.kind {
+        SourceKind::Greenhouse => (greenhouse::fetch(client, source).await?, None),
+        SourceKind::Lever => (lever::fetch(client, source).await?, None),
+        SourceKind::Feed => {
+            let state = sources::fetch_state(db, source.id).await?;
+            match feed::fetch(client, source, &state).await? {
+                Some((jobs, state)) => (jobs, Some(state)),
+                None => {
+                    info!("feed unchanged");
+                    lifecycle::mark_seen_again(db, source.id).await?;
+                    return Ok(0);
+                }
+            }
+        }
+    };
+
+    for job in &jobs {
+        jobs::upsert_job(db, Some(source.id), job).await?;
+    }
+    let ids = jobs
+        .iter()
+        .map(|job| return job.id.as_str())
+        .collect::<Vec<_>>();
+    lifecycle::mark_seen(db, source.id, &ids).await?;
+    // Only once imported, so a failed import is retried in full
+    if let Some(state) = state {
+        sources::save_fetch_state(db, source.id, &state).await?;
+    }
+
+    info!(count = jobs.len(), "imported jobs");
+
+    return Ok(jobs.len());
+}
+
+/// The jobs in an imported file: JSON as written by `wantjob export`, or else
+/// a saved page with JSON-LD postings, whatever the file is called
+fn parse_file(contents: &str) -> Result<Vec<NewJob>> {
+    let contents = contents.trim_start_matches('\u{feff}').trim_start();
+    if contents.starts_with(['[', '{']) {
+        return serde_json::from_str(contents).context("Could not parse exported jobs");
+    }
+    return Ok(json_ld::extract(contents, None));
+}
+
+/// Imports the jobs in a file, as exported or a saved page with JSON-LD
+/// postings. Returns how many there were.
+#[tracing::instrument(skip(db))]
+pub async fn import_file(db: &DbPool, path: &Path) -> Result<usize> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| return format!("Could not read {}", path.display()))?;
+
+    let jobs = parse_file(&contents)
+        .with_context(|| return format!("Could not import jobs from {}", path.display()))?;
+
+    for job in &jobs {
+        jobs::upsert_job(db, None, job).await?;
+    }
+
+    info!(count = jobs.len(), "imported jobs");
+
+    return Ok(jobs.len());
+}
+
+/// Imports every enabled source, carrying on past those which fail. Returns
+/// how many failed.
+#[tracing::instrument(skip_all)]
+pub async fn import_all(db: &DbPool, client: &dyn HttpClient) -> Result<usize> {
+    let mut failed = 0;
+
+    for source in sources::list_sources(db).await? {
+        if !source.enabled {
+            continue;
+        }
+
+        if let Err(err) = import_source(db, client, &source).await {
+            error!(source.id = source.id, "Could not import jobs: {err:#}");
+            failed += 1;
+        }
+    }
+
+    return Ok(failed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_files_by_content() {
+        let job = greenhouse::parse(
+            &test_source(SourceKind::Greenhouse, "acme"),
+            include_str!("../../fixtures/boards-api.greenhouse.io/v1/boards/acme/jobs.json"),
+        )
+        .unwrap()
+        .remove(0);
+        let exported = serde_json::to_string_pretty(&[&job]).unwrap();
+
+        let jobs = parse_file(&format!("\u{feff}\n  {exported}")).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, job.id);
+
+        let page = include_str!("../../fixtures/careers.example.com/jobs/backend-engineer.html");
+        assert!(!parse_file(page).unwrap().is_empty());
+
+        assert!(parse_file("<html><body>No postings</body></html>")
+            .unwrap()
+            .is_empty());
+        assert!(parse_file(r#"{"jobs": []}"#).is_err());
+    }
+}
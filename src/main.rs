@@ -1,23 +1,316 @@
 #![allow(clippy::needless_return)]
 
+use std::{
+    io::{BufRead, Write},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use config::{Config, DatabaseConfig};
+use db::{
+    sources::{self, SourceKind},
+    users, DbPool,
+};
+use ingest::{feed::FieldMapping, FixtureClient, HttpClient, LiveClient};
+use tokio_util::sync::CancellationToken;
+
+mod build_info;
+mod config;
+mod currency;
 mod db;
+mod dedupe;
 mod http;
+mod ical;
+mod ingest;
+mod markdown;
+mod metrics;
+mod salary;
+mod scheduler;
+mod shutdown;
+mod sweeper;
 mod telemetry;
 mod utils;
 
+/// How importers reach job boards: saved responses in `http_fixtures` if
+/// set, else the network
+fn http_client(config: &Config) -> anyhow::Result<Box<dyn HttpClient>> {
+    return Ok(match &config.http_fixtures {
+        Some(dir) => Box::new(FixtureClient::new(dir.clone())),
+        None => Box::new(LiveClient::new()?),
+    });
+}
+
+/// How pages users link to are fetched: like [`http_client`], but only
+/// reaching public addresses
+fn page_client(config: &Config) -> anyhow::Result<Box<dyn HttpClient>> {
+    return Ok(match &config.http_fixtures {
+        Some(dir) => Box::new(FixtureClient::new(dir.clone())),
+        None => Box::new(LiveClient::public_only()?),
+    });
+}
+
+/// A password for `username`, read from stdin so it stays out of the shell's
+/// history
+fn read_password(username: &str) -> anyhow::Result<String> {
+    eprint!("Password for {username}: ");
+    std::io::stderr().flush()?;
+
+    let mut password = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .context("Could not read password")?;
+    let password = password.trim_end_matches(['\r', '\n']).to_owned();
+
+    if let Err(err) = users::validate_credentials(username, &password) {
+        bail!("{err}");
+    }
+    return Ok(password);
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    if cfg!(not(debug_assertions)) {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let config = Config::load(&mut args)?;
+
+    if (args.is_empty() || args == ["serve"]) && cfg!(not(debug_assertions)) {
         utils::print_banner();
     }
 
-    telemetry::init_telemetry();
+    let telemetry = telemetry::init_telemetry(&config.telemetry);
+
+    let db = db::init_dbpool(&config.database).await?;
+
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let result = run(&config, &db, &args).await;
+
+    db.close().await;
+    telemetry::shutdown_telemetry(telemetry).await;
+
+    return result;
+}
 
-    let db = db::init_dbpool().await?;
+async fn run(config: &Config, db: &DbPool, args: &[&str]) -> anyhow::Result<()> {
+    match args {
+        [] | ["serve"] => {
+            db::setup_database(db, &config.database).await?;
 
-    db::setup_database(&db).await;
+            let rates = currency::load_rates(config.exchange_rates.as_deref())?;
+            db::rates::replace_rates(db, &rates).await?;
 
-    http::serve(db).await?;
+            let stop = CancellationToken::new();
+            tokio::spawn(shutdown::on_signal(stop.clone()));
+            let scheduler = scheduler::spawn(db.clone(), http_client(config)?.into(), stop.clone());
+            let sweeper = sweeper::spawn(db.clone(), config.jobs.clone(), stop.clone());
+            let counter = metrics::spawn(db.clone(), stop.clone());
+            metrics::observe_pool(db.clone(), config.database.max_connections);
+            let workers = vec![
+                ("scheduler", scheduler.abort_handle()),
+                ("sweeper", sweeper.abort_handle()),
+                ("metrics", counter.abort_handle()),
+            ];
+
+            let drain = Duration::from_secs(config.drain_timeout_secs);
+            let served = http::serve(
+                db.clone(),
+                page_client(config)?.into(),
+                workers,
+                config.bind,
+                stop.clone(),
+                drain,
+            )
+            .await;
+            // Also when the server failed, rather than was stopped
+            stop.cancel();
+            shutdown::join(vec![scheduler, sweeper, counter], drain).await;
+            served?;
+        }
+        ["migrate", "up"] => db::migrate::up(db).await?,
+        ["migrate", "down", "--to", to] => {
+            db::migrate::down(db, to.parse().context("--to must be a migration number")?).await?
+        }
+        ["migrate", "status"] => {
+            for m in db::migrate::status(db).await? {
+                println!(
+                    "{:>4} {:<24} {}",
+                    m.version,
+                    m.name,
+                    m.applied_at.as_deref().unwrap_or("pending")
+                );
+            }
+        }
+        ["seed"] => {
+            let database = DatabaseConfig {
+                seed: true,
+                ..config.database.clone()
+            };
+            db::setup_database(db, &database).await?;
+        }
+        ["sources", "list"] => {
+            db::migrate::up(db).await?;
+            for s in sources::list_sources(db).await? {
+                println!(
+                    "{:>4} {:<10} {:<24} {:<24} {:<8} every {}m, next {}",
+                    s.id,
+                    s.kind.slug(),
+                    s.board,
+                    s.company,
+                    if s.enabled { "enabled" } else { "disabled" },
+                    s.interval_minutes,
+                    s.next_run_at.as_deref().unwrap_or("now")
+                );
+            }
+        }
+        ["sources", "add", kind, board, company, rest @ ..] => {
+            let kind = SourceKind::from_slug(kind)
+                .context("Sources are either greenhouse, lever or feed")?;
+            if !ingest::is_valid_board(kind, board) {
+                match kind {
+                    SourceKind::Feed => bail!("Feeds are added by their http(s) URL"),
+                    _ => bail!("Boards may only contain letters, numbers, '-', '_' and '.'"),
+                }
+            }
+
+            let mut api_url = None;
+            let mut mapping = FieldMapping::default();
+            let mut options = rest.iter();
+            while let Some(option) = options.next() {
+                match (*option, options.next(), kind) {
+                    ("--api-url", Some(url), SourceKind::Greenhouse | SourceKind::Lever) => {
+                        api_url = Some(*url)
+                    }
+                    ("--map", Some(pair), SourceKind::Feed) => mapping.set(pair)?,
+                    _ => bail!(
+                        "usage: wantjob sources add <greenhouse|lever> <board> <company> [--api-url <URL>]\n       \
+                         wantjob sources add feed <URL> <company> [--map <field>=<item field>]..."
+                    ),
+                }
+            }
+            let mapping = match kind {
+                SourceKind::Feed => Some(serde_json::to_string(&mapping)?),
+                _ => None,
+            };
+
+            db::migrate::up(db).await?;
+            match sources::add_source(db, kind, board, company, api_url, mapping.as_deref()).await?
+            {
+                Some(source) => println!("Added source {}", source.id),
+                None => bail!("That board is already a source"),
+            }
+        }
+        ["sources", "map", id, pairs @ ..] => {
+            let id = id.parse().context("Source ids are numbers")?;
+            db::migrate::up(db).await?;
+            let source = sources::get_source(db, id)
+                .await?
+                .with_context(|| return format!("No source {id}"))?;
+            if source.kind != SourceKind::Feed {
+                bail!("Only feeds have field mappings");
+            }
+
+            let mut mapping: FieldMapping = match &source.field_mapping {
+                Some(mapping) => serde_json::from_str(mapping)?,
+                None => FieldMapping::default(),
+            };
+            for pair in pairs {
+                mapping.set(pair)?;
+            }
+            let mapping = serde_json::to_string(&mapping)?;
+            sources::set_field_mapping(db, id, Some(&mapping)).await?;
+            println!("{mapping}");
+        }
+        ["sources", "interval", id, minutes] => {
+            let id = id.parse().context("Source ids are numbers")?;
+            let minutes = minutes
+                .parse()
+                .ok()
+                .filter(|m| return *m > 0)
+                .context("Intervals are a number of minutes")?;
+            db::migrate::up(db).await?;
+            if !sources::set_interval(db, id, minutes).await? {
+                bail!("No source {id}");
+            }
+        }
+        ["sources", toggle @ ("enable" | "disable"), id] => {
+            let id = id.parse().context("Source ids are numbers")?;
+            db::migrate::up(db).await?;
+            if !sources::set_enabled(db, id, *toggle == "enable").await? {
+                bail!("No source {id}");
+            }
+        }
+        ["user", "create", username] => {
+            let password = read_password(username)?;
+            db::migrate::up(db).await?;
+            match users::create_user(db, username, &password).await? {
+                Some(user) => println!("Created user {}", user.username),
+                None => bail!("That username is taken"),
+            }
+        }
+        ["user", "reset-password", username] => {
+            let password = read_password(username)?;
+            db::migrate::up(db).await?;
+            if !users::reset_password(db, username, &password).await? {
+                bail!("No user {username}");
+            }
+        }
+        ["user", "delete", username] => {
+            db::migrate::up(db).await?;
+            if !users::delete_user(db, username).await? {
+                bail!("No user {username}");
+            }
+        }
+        ["admin", grant @ ("grant" | "revoke"), username] => {
+            db::migrate::up(db).await?;
+            if !db::users::set_admin(db, username, *grant == "grant").await? {
+                bail!("No user {username}");
+            }
+        }
+        ["import"] => {
+            db::migrate::up(db).await?;
+            let failed = ingest::import_all(db, http_client(config)?.as_ref()).await?;
+            if failed > 0 {
+                bail!("{failed} source(s) could not be imported");
+            }
+        }
+        ["import", file] if file.parse::<i64>().is_err() => {
+            db::migrate::up(db).await?;
+            let count = ingest::import_file(db, Path::new(file)).await?;
+            println!("Imported {count} jobs");
+        }
+        ["import", id] => {
+            let id = id.parse().context("Source ids are numbers")?;
+            db::migrate::up(db).await?;
+            let source = sources::get_source(db, id)
+                .await?
+                .with_context(|| return format!("No source {id}"))?;
+            let count = ingest::import_source(db, http_client(config)?.as_ref(), &source).await?;
+            println!("Imported {count} jobs");
+        }
+        ["export", rest @ ..] if rest.len() <= 1 => {
+            db::migrate::up(db).await?;
+            let jobs = db::jobs::export_jobs(db).await?;
+            match rest.first() {
+                Some(path) => {
+                    let file = std::fs::File::create(path)
+                        .with_context(|| return format!("Could not create {path}"))?;
+                    serde_json::to_writer_pretty(file, &jobs)?;
+                    println!("Exported {} jobs", jobs.len());
+                }
+                None => serde_json::to_writer_pretty(std::io::stdout().lock(), &jobs)?,
+            }
+        }
+        _ => bail!(
+            "usage: wantjob [--config <FILE>] [--<setting> <value>]... [serve | seed | \
+             migrate up | migrate down --to <N> | migrate status | \
+             sources list | sources add <greenhouse|lever> <board> <company> [--api-url <URL>] | \
+             sources add feed <URL> <company> [--map <field>=<item field>]... | \
+             sources map <id> <field>=<item field>... | \
+             sources interval <id> <minutes> | sources enable|disable <id> | \
+             user create|reset-password|delete <username> | admin grant|revoke <username> | \
+             import [<source id> | <file>] | export [<file>]]"
+        ),
+    }
 
     Ok(())
 }
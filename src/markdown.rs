@@ -0,0 +1,140 @@
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+
+/// URL schemes links and images may use, anything else (`javascript:`,
+/// `data:`, ...) is dropped
+const ALLOWED_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Whether `url` is relative or uses an allowed scheme. Browsers ignore tabs
+/// and newlines in URLs, so they can't be used to sneak a scheme past.
+fn is_safe_url(url: &str) -> bool {
+    let url = url
+        .chars()
+        .filter(|c| return !c.is_ascii_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_lowercase();
+
+    return match url.find([':', '/', '?', '#']) {
+        Some(i) if url[i..].starts_with(':') => ALLOWED_SCHEMES.contains(&&url[..i]),
+        _ => true,
+    };
+}
+
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    if is_safe_url(&url) {
+        return url;
+    }
+    return CowStr::Borrowed("");
+}
+
+/// Renders user written markdown to HTML which is safe to embed: raw HTML is
+/// shown as text and links can't run scripts
+pub fn render(markdown: &str) -> String {
+    let options =
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS;
+
+    let events = Parser::new_ext(markdown, options).map(|event| {
+        return match event {
+            Event::Html(text) | Event::InlineHtml(text) => Event::Text(text),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Image {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            event => event,
+        };
+    });
+
+    let mut out = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut out, events);
+
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_script_links() {
+        let cases = [
+            "[x](javascript:alert(1))",
+            "[x](JaVaScRiPt:alert(1))",
+            "[x](  javascript:alert(1))",
+            "[x](<java\tscript:alert(1)>)",
+            "[x](java&#10;script:alert(1))",
+            "[x](&#106;avascript:alert(1))",
+            "[x](javascript&#58;alert(1))",
+            "[x](java&#x09;script:alert(1))",
+            "[x][evil]\n\n[evil]: javascript:alert(1)",
+            "[x]\n\n[x]: JAVASCRIPT:alert(1) \"title\"",
+            "<javascript:alert(1)>",
+            "[x](vbscript:msgbox(1))",
+        ];
+        for markdown in cases {
+            let out = render(markdown);
+            assert!(
+                out.contains(r#"<a href="""#),
+                "{markdown:?} rendered {out:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn strips_data_images() {
+        let cases = [
+            "![x](data:image/svg+xml;base64,PHN2Zz48L3N2Zz4=)",
+            "![x](DATA:text/html,<script>alert(1)</script>)",
+            "![x][img]\n\n[img]: data:image/png;base64,AAAA",
+        ];
+        for markdown in cases {
+            let out = render(markdown);
+            assert!(
+                out.contains(r#"<img src="""#),
+                "{markdown:?} rendered {out:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn shows_raw_html_as_text() {
+        let out = render("<script>alert(1)</script>\n\nHi <img src=x onerror=alert(1)>");
+        assert!(!out.contains("<script"), "{out:?}");
+        assert!(!out.contains("<img"), "{out:?}");
+        assert!(out.contains("&lt;script&gt;"), "{out:?}");
+        assert!(
+            out.contains("&lt;img src=x onerror=alert(1)&gt;"),
+            "{out:?}"
+        );
+    }
+
+    #[test]
+    fn keeps_safe_links() {
+        assert_eq!(
+            render("[x](https://example.com/a?b#c)"),
+            "<p><a href=\"https://example.com/a?b#c\">x</a></p>\n"
+        );
+        assert!(render("[x](mailto:a@example.com)").contains(r#"href="mailto:a@example.com""#));
+        assert!(render("[x](/jobs/1)").contains(r#"href="/jobs/1""#));
+        // A colon after the path isn't a scheme
+        assert!(render("[x](jobs/a:b)").contains(r#"href="jobs/a:b""#));
+        assert!(render("![x](https://example.com/a.png)")
+            .contains(r#"src="https://example.com/a.png""#));
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use opentelemetry::{
+    global,
+    metrics::{Histogram, Meter, Unit, UpDownCounter},
+    KeyValue,
+};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::error;
+
+use crate::db::{
+    jobs::{self, JobStatus},
+    pipeline::{self, Stage},
+    DbPool,
+};
+
+/// How often the job and application counts are refreshed
+const TICK: Duration = Duration::from_secs(60);
+
+fn meter() -> Meter {
+    return global::meter("wantjob");
+}
+
+/// Requests served, per `http.route` like the request spans
+pub struct HttpMetrics {
+    /// How long requests took, in milliseconds. Also counts them, and their
+    /// errors by `http.response.status_code`.
+    pub duration: Histogram<f64>,
+    pub active: UpDownCounter<i64>,
+}
+
+pub static HTTP: LazyLock<HttpMetrics> = LazyLock::new(|| {
+    let meter = meter();
+    return HttpMetrics {
+        duration: meter
+            .f64_histogram("http.server.duration")
+            .with_description("How long requests took to serve")
+            .with_unit(Unit::new("ms"))
+            .init(),
+        active: meter
+            .i64_up_down_counter("http.server.active_requests")
+            .with_description("Requests being served")
+            .init(),
+    };
+});
+
+/// How long pages took to render (`phase` = `render`) and then minify
+/// (`minify`), in milliseconds
+pub static RENDER: LazyLock<Histogram<f64>> = LazyLock::new(|| {
+    return meter()
+        .f64_histogram("wantjob.render.duration")
+        .with_description("How long pages took to render and minify")
+        .with_unit(Unit::new("ms"))
+        .init();
+});
+
+/// Reports how many of the pool's connections are in use
+pub fn observe_pool(db: DbPool, max_connections: u32) {
+    let meter = meter();
+    meter
+        .i64_observable_up_down_counter("db.client.connections.usage")
+        .with_description("Database connections by state")
+        .with_callback(move |usage| {
+            let idle = db.num_idle() as i64;
+            usage.observe(idle, &[KeyValue::new("state", "idle")]);
+            usage.observe(
+                i64::from(db.size()) - idle,
+                &[KeyValue::new("state", "used")],
+            );
+        })
+        .init();
+    meter
+        .i64_observable_up_down_counter("db.client.connections.max")
+        .with_description("Most database connections the pool will open")
+        .with_callback(move |max| {
+            max.observe(i64::from(max_connections), &[]);
+        })
+        .init();
+}
+
+#[derive(Default)]
+struct Counts {
+    jobs: Vec<(JobStatus, i64)>,
+    applications: Vec<(Stage, i64)>,
+}
+
+async fn count(db: &DbPool) -> Result<Counts> {
+    return Ok(Counts {
+        jobs: jobs::count_by_status(db).await?,
+        applications: pipeline::count_by_stage(db).await?,
+    });
+}
+
+/// Reports how many jobs there are by status, and applications by stage,
+/// counting them in the background until `stop` is cancelled. Metrics are
+/// collected without waiting on the database, so they report the last count.
+pub fn spawn(db: DbPool, stop: CancellationToken) -> JoinHandle<()> {
+    let counts = Arc::new(Mutex::new(Counts::default()));
+
+    let meter = meter();
+    let observed = counts.clone();
+    meter
+        .i64_observable_gauge("wantjob.jobs")
+        .with_description("Jobs by status, counting duplicates once")
+        .with_callback(move |gauge| {
+            for (status, count) in &observed.lock().expect("counts lock").jobs {
+                gauge.observe(*count, &[KeyValue::new("job.status", status.to_string())]);
+            }
+        })
+        .init();
+    let observed = counts.clone();
+    meter
+        .i64_observable_gauge("wantjob.applications")
+        .with_description("Jobs in users' pipelines by stage")
+        .with_callback(move |gauge| {
+            for (stage, count) in &observed.lock().expect("counts lock").applications {
+                gauge.observe(*count, &[KeyValue::new("application.stage", stage.slug())]);
+            }
+        })
+        .init();
+
+    return tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stop.cancelled() => return,
+            }
+            match count(&db).await {
+                Ok(latest) => *counts.lock().expect("counts lock") = latest,
+                Err(err) => error!("Could not count jobs for metrics: {err:#}"),
+            }
+        }
+    });
+}
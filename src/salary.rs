@@ -0,0 +1,601 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Working hours in a year, used to annualize hourly pay (40h x 52w)
+const HOURS_PER_YEAR: f64 = 2080.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SalaryPeriod {
+    Hourly,
+    Monthly,
+    Yearly,
+}
+
+impl SalaryPeriod {
+    fn per_year(self) -> f64 {
+        return match self {
+            SalaryPeriod::Hourly => HOURS_PER_YEAR,
+            SalaryPeriod::Monthly => 12.0,
+            SalaryPeriod::Yearly => 1.0,
+        };
+    }
+
+    fn suffix(self) -> &'static str {
+        return match self {
+            SalaryPeriod::Hourly => "/hr",
+            SalaryPeriod::Monthly => "/mo",
+            SalaryPeriod::Yearly => "/yr",
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Salary {
+    pub min: f64,
+    pub max: f64,
+    /// ISO 4217 code
+    pub currency: String,
+    pub period: SalaryPeriod,
+    pub equity: bool,
+}
+
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("CA$", "CAD"),
+    ("C$", "CAD"),
+    ("A$", "AUD"),
+    ("NZ$", "NZD"),
+    ("US$", "USD"),
+    ("$", "USD"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+];
+
+const CURRENCY_CODES: &[&str] = &[
+    "USD", "CAD", "EUR", "GBP", "AUD", "NZD", "JPY", "CHF", "INR", "SEK", "NOK", "DKK", "PLN",
+    "SGD", "HKD", "MXN", "BRL",
+];
+
+const PERIOD_MARKERS: &[(&str, SalaryPeriod)] = &[
+    ("/hr", SalaryPeriod::Hourly),
+    ("/hour", SalaryPeriod::Hourly),
+    ("/h", SalaryPeriod::Hourly),
+    ("per hour", SalaryPeriod::Hourly),
+    ("an hour", SalaryPeriod::Hourly),
+    ("hourly", SalaryPeriod::Hourly),
+    ("/mo", SalaryPeriod::Monthly),
+    ("/month", SalaryPeriod::Monthly),
+    ("per month", SalaryPeriod::Monthly),
+    ("a month", SalaryPeriod::Monthly),
+    ("monthly", SalaryPeriod::Monthly),
+    ("/yr", SalaryPeriod::Yearly),
+    ("/year", SalaryPeriod::Yearly),
+    ("per year", SalaryPeriod::Yearly),
+    ("a year", SalaryPeriod::Yearly),
+    ("p.a.", SalaryPeriod::Yearly),
+    ("per annum", SalaryPeriod::Yearly),
+    ("annual", SalaryPeriod::Yearly),
+    ("yearly", SalaryPeriod::Yearly),
+];
+
+/// Words after the pay that mean it comes with equity, as in "$150k + RSUs"
+const EQUITY_MARKERS: &[&str] = &["equity", "stock", "stocks", "rsu", "rsus"];
+
+/// The pay period written right after an amount, past a currency code if
+/// there is one: "60/hr", "5.000 EUR per month"
+fn period_after(text: &str) -> Option<SalaryPeriod> {
+    let lower = text.trim_start().to_lowercase();
+    let after = CURRENCY_CODES
+        .iter()
+        .find_map(|code| return lower.strip_prefix(&code.to_lowercase()))
+        .map(str::trim_start)
+        .unwrap_or(&lower);
+
+    return PERIOD_MARKERS
+        .iter()
+        .find(|(marker, _)| return after.starts_with(marker))
+        .map(|(_, period)| return *period);
+}
+
+/// Whether the clause following the pay mentions equity. "Options" alone only
+/// counts straight after a `+`, so "relocation options" doesn't.
+fn equity_after(tokens: &[Token]) -> bool {
+    let mut previous = None;
+    for token in tokens {
+        match token {
+            Token::Other(',' | ';' | '.' | '|' | '(' | ')') => return false,
+            Token::Word(word) if EQUITY_MARKERS.contains(&word.as_str()) => return true,
+            Token::Word(word) if word == "options" && previous == Some(&Token::Other('+')) => {
+                return true;
+            }
+            _ => {}
+        }
+        previous = Some(token);
+    }
+
+    return false;
+}
+
+/// Parses a number as written in a posting: `120`, `120k`, `1.5M`, `120,000`,
+/// `70.000` (European thousands) or `62.50`
+fn parse_amount(token: &str) -> Option<f64> {
+    let (digits, multiplier) = match token.chars().last()? {
+        'k' | 'K' => (&token[..token.len() - 1], 1_000.0),
+        'm' | 'M' => (&token[..token.len() - 1], 1_000_000.0),
+        _ => (token, 1.0),
+    };
+
+    // With both separators, the last one is the decimal point
+    let normalized = match (digits.rfind('.'), digits.rfind(',')) {
+        (Some(dot), Some(comma)) if dot > comma => digits.replace(',', ""),
+        (Some(_), Some(_)) => digits.replace('.', "").replace(',', "."),
+        (Some(_), None) | (None, Some(_)) => {
+            let sep = if digits.contains('.') { '.' } else { ',' };
+            let groups = digits.split(sep).collect::<Vec<_>>();
+            let thousands = groups[1..].iter().all(|g| return g.len() == 3)
+                && (groups.len() > 2 || multiplier == 1.0);
+            if thousands {
+                digits.replace(sep, "")
+            } else {
+                digits.replace(',', ".")
+            }
+        }
+        (None, None) => digits.to_owned(),
+    };
+
+    return normalized
+        .parse::<f64>()
+        .ok()
+        .map(|n| return n * multiplier);
+}
+
+/// Written like amounts but never pay, as in "401k match"
+const NOT_AMOUNTS: &[&str] = &["401(k)", "401k", "403(b)", "403b"];
+
+/// Words before a number that make it something other than pay, as in "Level 3"
+const NOT_PAY_BEFORE: &[&str] = &["level", "l", "grade", "band", "tier", "step"];
+
+/// Words after a number that make it something other than pay, as in "2 days in office"
+const NOT_PAY_AFTER: &[&str] = &[
+    "day",
+    "days",
+    "week",
+    "weeks",
+    "month",
+    "months",
+    "year",
+    "years",
+    "yrs",
+    "hour",
+    "hours",
+    "people",
+    "employees",
+];
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Amount {
+        value: f64,
+        /// Written with a `k`/`M` multiplier
+        suffixed: bool,
+        /// Byte offset just past the amount
+        end: usize,
+    },
+    Currency(&'static str),
+    /// `-`, `–`, `—` or "to", between the ends of a range
+    Dash,
+    Percent,
+    Word(String),
+    Other(char),
+}
+
+/// Reads the number at the start of `text`, returning it as written (without
+/// spaces between thousands, as in "600 000") and how many bytes it took up
+fn lex_amount(text: &str) -> (String, usize) {
+    let mut raw = String::new();
+    let mut end = 0;
+    let mut grouped = false;
+
+    while let Some(c) = text[end..].chars().next() {
+        let after = &text[end + c.len_utf8()..];
+        let next_is_digit = after.starts_with(|n: char| return n.is_ascii_digit());
+
+        if c.is_ascii_digit() || (matches!(c, '.' | ',') && next_is_digit) {
+            raw.push(c);
+        } else if matches!(c, ' ' | '\u{a0}' | '\u{202f}')
+            && (grouped || raw.len() <= 3)
+            && !raw.contains(['.', ','])
+            && after.chars().take_while(char::is_ascii_digit).count() == 3
+        {
+            grouped = true;
+        } else {
+            break;
+        }
+        end += c.len_utf8();
+    }
+
+    // A multiplier only counts when it isn't the start of a word ("120k", not "120 kilos")
+    let mut rest = text[end..].chars();
+    if let Some(c @ ('k' | 'K' | 'm' | 'M')) = rest.next() {
+        if !rest.next().is_some_and(|n| return n.is_alphabetic()) {
+            raw.push(c);
+            end += 1;
+        }
+    }
+
+    return (raw, end);
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+
+        if let Some(word) = NOT_AMOUNTS.iter().find(|w| {
+            return rest
+                .get(..w.len())
+                .is_some_and(|s| return s.eq_ignore_ascii_case(w));
+        }) {
+            tokens.push(Token::Word((*word).to_owned()));
+            i += word.len();
+            continue;
+        }
+
+        if let Some((symbol, code)) = CURRENCY_SYMBOLS
+            .iter()
+            .find(|(s, _)| return rest.starts_with(s))
+        {
+            tokens.push(Token::Currency(code));
+            i += symbol.len();
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let (raw, len) = lex_amount(rest);
+            if let Some(value) = parse_amount(&raw) {
+                tokens.push(Token::Amount {
+                    value,
+                    suffixed: raw.ends_with(['k', 'K', 'm', 'M']),
+                    end: i + len,
+                });
+            }
+            i += len;
+            continue;
+        }
+
+        if c.is_alphabetic() {
+            let len = rest
+                .find(|c: char| return !c.is_alphabetic())
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            let upper = word.to_uppercase();
+            tokens.push(
+                match CURRENCY_CODES.iter().find(|code| return **code == upper) {
+                    Some(code) => Token::Currency(code),
+                    None if word.eq_ignore_ascii_case("to") => Token::Dash,
+                    None => Token::Word(word.to_lowercase()),
+                },
+            );
+            i += len;
+            continue;
+        }
+
+        match c {
+            '-' | '–' | '—' => tokens.push(Token::Dash),
+            '%' => tokens.push(Token::Percent),
+            c if c.is_whitespace() => {}
+            c => tokens.push(Token::Other(c)),
+        }
+        i += c.len_utf8();
+    }
+
+    return tokens;
+}
+
+/// The amounts in a posting that are pay, each with whether it's marked as
+/// money by a currency, a `k`/`M` multiplier or a period ("60/hr")
+fn pay_amounts(text: &str, tokens: &[Token]) -> Vec<(usize, f64, bool)> {
+    let word_is = |i: Option<usize>, words: &[&str]| {
+        return match i.and_then(|i| return tokens.get(i)) {
+            Some(Token::Word(w)) => words.contains(&w.as_str()),
+            _ => false,
+        };
+    };
+    let is_currency = |i: Option<usize>| {
+        return matches!(
+            i.and_then(|i| return tokens.get(i)),
+            Some(Token::Currency(_))
+        );
+    };
+
+    let mut amounts = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Amount {
+            value,
+            suffixed,
+            end,
+        } = token
+        else {
+            continue;
+        };
+
+        // "5+ years" reads the same as "5 years"
+        let next = match tokens.get(i + 1) {
+            Some(Token::Other('+')) => i + 2,
+            _ => i + 1,
+        };
+        let not_pay = *value <= 0.0
+            || tokens.get(next) == Some(&Token::Percent)
+            || word_is(Some(next), NOT_PAY_AFTER)
+            || word_is(i.checked_sub(1), NOT_PAY_BEFORE);
+        if not_pay {
+            continue;
+        }
+
+        let marked = *suffixed
+            || is_currency(i.checked_sub(1))
+            || is_currency(Some(i + 1))
+            || period_after(&text[*end..]).is_some();
+        amounts.push((i, *value, marked));
+    }
+
+    return amounts;
+}
+
+/// The first two amounts joined by a dash or "to", with a currency allowed
+/// on either side of it: `$120k - $150k`, `120K USD to 150K USD`. Returns the
+/// ends of the range and the position of its last amount.
+fn find_range(tokens: &[Token], amounts: &[(usize, f64, bool)]) -> Option<(f64, f64, usize)> {
+    for (i, min, min_marked) in amounts {
+        let mut j = i + 1;
+        if matches!(tokens.get(j), Some(Token::Currency(_))) {
+            j += 1;
+        }
+        if tokens.get(j) != Some(&Token::Dash) {
+            continue;
+        }
+        j += 1;
+        if matches!(tokens.get(j), Some(Token::Currency(_))) {
+            j += 1;
+        }
+
+        let max = amounts.iter().find(|(k, _, _)| return *k == j);
+        if let Some((_, max, max_marked)) = max {
+            if *min_marked || *max_marked {
+                return Some((*min, *max, j));
+            }
+        }
+    }
+
+    return None;
+}
+
+impl Salary {
+    /// Parses a free-text salary such as `"120K USD"`, `"$120k–$150k + equity"`,
+    /// `"60/hr"` or `"€70.000 p.a."`
+    pub fn parse(text: &str) -> Option<Salary> {
+        let tokens = tokenize(text);
+        let amounts = pay_amounts(text, &tokens);
+
+        let (mut min, mut max, last) = match find_range(&tokens, &amounts) {
+            Some(range) => range,
+            None => {
+                let single = match amounts.as_slice() {
+                    [only] => Some(only),
+                    _ => amounts.iter().find(|(_, _, marked)| return *marked),
+                };
+                let (i, n, _) = single?;
+                (*n, *n, *i)
+            }
+        };
+        let Token::Amount { end, .. } = tokens[last] else {
+            unreachable!("pay amounts point at amount tokens");
+        };
+
+        // "120-150k": the multiplier written once applies to both ends
+        if min < 1_000.0 && max >= 1_000.0 && min * 1_000.0 <= max {
+            min *= 1_000.0;
+        }
+        if min > max {
+            std::mem::swap(&mut min, &mut max);
+        }
+
+        let currency = tokens
+            .iter()
+            .find_map(|t| {
+                return match t {
+                    Token::Currency(code) => Some(*code),
+                    _ => None,
+                };
+            })
+            .unwrap_or("USD")
+            .to_owned();
+
+        let period = period_after(&text[end..])
+            // Nobody is paid 60 a year or 8000 an hour
+            .unwrap_or(match max {
+                n if n < 500.0 => SalaryPeriod::Hourly,
+                n if n < 20_000.0 => SalaryPeriod::Monthly,
+                _ => SalaryPeriod::Yearly,
+            });
+
+        let equity = equity_after(&tokens[last + 1..]);
+
+        return Some(Salary {
+            min,
+            max,
+            currency,
+            period,
+            equity,
+        });
+    }
+
+    pub fn annual_min(&self) -> f64 {
+        return self.min * self.period.per_year();
+    }
+
+    pub fn annual_max(&self) -> f64 {
+        return self.max * self.period.per_year();
+    }
+
+    /// The same salary expressed per year, so postings compare like for like
+    pub fn annualized(&self) -> Salary {
+        return Salary {
+            min: self.annual_min().round(),
+            max: self.annual_max().round(),
+            period: SalaryPeriod::Yearly,
+            ..self.clone()
+        };
+    }
+}
+
+/// Compact amount for display, e.g. `120K`, `72.5K` or `62.50`
+pub fn format_amount(amount: f64) -> String {
+    if amount >= 1_000_000.0 {
+        return format!("{}M", trim_decimals(amount / 1_000_000.0));
+    }
+    if amount >= 1_000.0 {
+        return format!("{}K", trim_decimals(amount / 1_000.0));
+    }
+    if amount.fract() == 0.0 {
+        return format!("{amount:.0}");
+    }
+    return format!("{amount:.2}");
+}
+
+fn trim_decimals(n: f64) -> String {
+    let s = format!("{n:.1}");
+    return s.strip_suffix(".0").map(str::to_owned).unwrap_or(s);
+}
+
+impl fmt::Display for Salary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_amount(self.min))?;
+        if self.max != self.min {
+            write!(f, "–{}", format_amount(self.max))?;
+        }
+        write!(f, " {}{}", self.currency, self.period.suffix())?;
+        if self.equity {
+            write!(f, " + equity")?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> (f64, f64, String, SalaryPeriod) {
+        let salary = Salary::parse(text).unwrap_or_else(|| panic!("{text:?} didn't parse"));
+        return (salary.min, salary.max, salary.currency, salary.period);
+    }
+
+    #[test]
+    fn parses_common_formats() {
+        use SalaryPeriod::*;
+
+        let cases = [
+            ("120K USD", (120_000.0, 120_000.0, "USD", Yearly)),
+            ("187K CAD", (187_000.0, 187_000.0, "CAD", Yearly)),
+            ("$120k–$150k", (120_000.0, 150_000.0, "USD", Yearly)),
+            ("$120k - $150k", (120_000.0, 150_000.0, "USD", Yearly)),
+            ("120-150k", (120_000.0, 150_000.0, "USD", Yearly)),
+            (
+                "120K USD to 150K USD",
+                (120_000.0, 150_000.0, "USD", Yearly),
+            ),
+            ("60/hr", (60.0, 60.0, "USD", Hourly)),
+            ("$60-70/hr", (60.0, 70.0, "USD", Hourly)),
+            ("€70.000 p.a.", (70_000.0, 70_000.0, "EUR", Yearly)),
+            ("£5,000 per month", (5_000.0, 5_000.0, "GBP", Monthly)),
+            ("1.5M JPY", (1_500_000.0, 1_500_000.0, "JPY", Yearly)),
+            ("NOK 600 000", (600_000.0, 600_000.0, "NOK", Yearly)),
+        ];
+        for (text, (min, max, currency, period)) in cases {
+            assert_eq!(
+                parse(text),
+                (min, max, currency.to_owned(), period),
+                "{text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_numbers_that_arent_pay() {
+        use SalaryPeriod::*;
+
+        let cases = [
+            ("$120,000 + 401k match", (120_000.0, 120_000.0)),
+            ("$120,000 + 401(k) match", (120_000.0, 120_000.0)),
+            ("Up to $150k, 2 days in office", (150_000.0, 150_000.0)),
+            ("Level 3: $120k-$140k", (120_000.0, 140_000.0)),
+            ("$140k base + 0.1% equity", (140_000.0, 140_000.0)),
+            ("5+ years, $130k", (130_000.0, 130_000.0)),
+        ];
+        for (text, (min, max)) in cases {
+            assert_eq!(
+                parse(text),
+                (min, max, "USD".to_owned(), Yearly),
+                "{text:?}"
+            );
+        }
+
+        assert_eq!(Salary::parse("Competitive"), None);
+        assert_eq!(Salary::parse("2 days in office, 10% bonus"), None);
+    }
+
+    #[test]
+    fn flags_equity() {
+        assert!(Salary::parse("$120k–$150k + equity").unwrap().equity);
+        assert!(Salary::parse("$140k base + 0.1% equity").unwrap().equity);
+        assert!(Salary::parse("$150k + RSUs").unwrap().equity);
+        assert!(Salary::parse("$150k + options").unwrap().equity);
+        assert!(!Salary::parse("$120,000 + 401k match").unwrap().equity);
+        assert!(!Salary::parse("$120k, relocation options").unwrap().equity);
+        assert!(!Salary::parse("$120k + relocation options").unwrap().equity);
+        assert!(!Salary::parse("Equity-free role. $120k").unwrap().equity);
+    }
+
+    #[test]
+    fn reads_the_period_next_to_the_pay() {
+        use SalaryPeriod::*;
+
+        let cases = [
+            ("$120k, start within a month", Yearly),
+            ("Hourly shifts available, $130k", Yearly),
+            ("5.000 EUR per month", Monthly),
+            ("€5.000-€6.000 a month", Monthly),
+        ];
+        for (text, period) in cases {
+            assert_eq!(parse(text).3, period, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn annualizes() {
+        let salary = Salary::parse("60/hr").unwrap().annualized();
+        assert_eq!(
+            (salary.min, salary.period),
+            (124_800.0, SalaryPeriod::Yearly)
+        );
+
+        let salary = Salary::parse("€5.000-€6.000 per month")
+            .unwrap()
+            .annualized();
+        assert_eq!((salary.min, salary.max), (60_000.0, 72_000.0));
+    }
+
+    #[test]
+    fn displays_range() {
+        let salary = Salary::parse("$120k–$150k + equity").unwrap();
+        assert_eq!(salary.to_string(), "120K–150K USD/yr + equity");
+        assert_eq!(Salary::parse("60/hr").unwrap().to_string(), "60 USD/hr");
+    }
+}
@@ -0,0 +1,109 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use rand::Rng;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::{
+    db::{
+        runs,
+        sources::{self, Source},
+        DbPool,
+    },
+    ingest::{self, HttpClient},
+};
+
+/// How often due sources are looked for
+const TICK: Duration = Duration::from_secs(60);
+
+/// Wait before retrying a failed source, doubled for each failure in a row
+const RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Longest a failing source is left before it's tried again
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Imports sources in the background as they come due, until `stop` is
+/// cancelled. A run underway when it is finishes first.
+pub fn spawn(db: DbPool, client: Arc<dyn HttpClient>, stop: CancellationToken) -> JoinHandle<()> {
+    return tokio::spawn(async move {
+        if let Err(err) = runs::abandon_unfinished(&db).await {
+            error!("Could not tidy up import runs: {err:#}");
+        }
+
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stop.cancelled() => return,
+            }
+            if let Err(err) = run_due(&db, client.as_ref(), &stop).await {
+                error!("Could not run due imports: {err:#}");
+            }
+        }
+    });
+}
+
+async fn run_due(db: &DbPool, client: &dyn HttpClient, stop: &CancellationToken) -> Result<()> {
+    for source in sources::due_sources(db).await? {
+        // Those left over are still due next time
+        if stop.is_cancelled() {
+            break;
+        }
+        run(db, client, &source).await?;
+    }
+
+    return Ok(());
+}
+
+/// `delay` give or take a tenth, so sources added together drift apart
+fn jittered(delay: Duration) -> Duration {
+    return delay.mul_f64(rand::thread_rng().gen_range(0.9..1.1));
+}
+
+/// When to try a source again after `failures` runs failed in a row: sooner
+/// than usual at first, backing off the more it fails
+fn retry_delay(failures: i64, interval: Duration) -> Duration {
+    let backoff = RETRY_DELAY.saturating_mul(1 << (failures.clamp(1, 16) - 1));
+    // Sources imported less often than daily aren't retried later than usual
+    return backoff.min(interval.max(MAX_RETRY_DELAY));
+}
+
+/// Imports a source, recording the run and scheduling the next one
+#[tracing::instrument(
+    name = "import_run",
+    skip_all,
+    fields(
+        otel.name = format!("import {} {}", source.kind.slug(), source.board),
+        source.id = source.id,
+        run.id = tracing::field::Empty,
+    )
+)]
+async fn run(db: &DbPool, client: &dyn HttpClient, source: &Source) -> Result<()> {
+    let run_id = runs::start_run(db, source.id).await?;
+    tracing::Span::current().record("run.id", run_id);
+
+    let outcome = ingest::import_source(db, client, source).await;
+    runs::finish_run(db, run_id, &outcome).await?;
+
+    let interval = Duration::from_secs(source.interval_minutes as u64 * 60);
+    let (delay, failures) = match &outcome {
+        Ok(_) => (jittered(interval), 0),
+        Err(err) => {
+            let failures = source.failures + 1;
+            let delay = jittered(retry_delay(failures, interval));
+            warn!(
+                failures,
+                "Import failed, retrying in {}s: {err:#}",
+                delay.as_secs()
+            );
+            (delay, failures)
+        }
+    };
+    sources::schedule(db, source.id, delay.as_secs() as i64, failures).await?;
+
+    info!(next_run_in = delay.as_secs(), "import run finished");
+    return Ok(());
+}
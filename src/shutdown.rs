@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Cancels `stop` on Ctrl+C, or SIGTERM as sent by container orchestrators
+pub async fn on_signal(stop: CancellationToken) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Ctrl+C handler to install");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("SIGTERM handler to install")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+        // Stopped for another reason
+        _ = stop.cancelled() => return,
+    }
+
+    info!("shutting down");
+    stop.cancel();
+}
+
+/// Waits up to `timeout` for background tasks to stop, aborting those which
+/// haven't
+pub async fn join(tasks: Vec<JoinHandle<()>>, timeout: Duration) {
+    let deadline = tokio::time::Instant::now() + timeout;
+    for mut task in tasks {
+        if tokio::time::timeout_at(deadline, &mut task).await.is_err() {
+            warn!(
+                "Background task still running after {}s, aborting it",
+                timeout.as_secs()
+            );
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::error;
+
+use crate::{
+    config::JobsConfig,
+    db::{lifecycle, DbPool},
+};
+
+/// How often jobs are swept
+const TICK: Duration = Duration::from_secs(60 * 60);
+
+/// Closes and archives jobs in the background as they go stale, until `stop` is
+/// cancelled
+pub fn spawn(db: DbPool, config: JobsConfig, stop: CancellationToken) -> JoinHandle<()> {
+    return tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stop.cancelled() => return,
+            }
+            let swept =
+                lifecycle::sweep(&db, config.stale_after_days, config.archive_after_days).await;
+            if let Err(err) = swept {
+                error!("Could not sweep jobs: {err:#}");
+            }
+        }
+    });
+}
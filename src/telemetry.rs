@@ -1,24 +1,69 @@
-use std::env;
-
 use gethostname::gethostname;
-use opentelemetry::KeyValue;
+use opentelemetry::{global, KeyValue};
 use opentelemetry_otlp::WithExportConfig;
-use opentelemetry_sdk::{runtime, trace as sdktrace, Resource};
-use rustc_version::version;
+use opentelemetry_sdk::{metrics::MeterProvider, runtime, trace as sdktrace, Resource};
 use tonic::metadata::*;
+use tracing::error;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-pub fn init_telemetry() {
+use crate::{build_info::BUILD_INFO, config::TelemetryConfig};
+
+/// What's running, attached to every span and metric
+fn resource() -> Resource {
+    return Resource::new(vec![
+        KeyValue::new("service.name", "wantjob"),
+        KeyValue::new("service.version", BUILD_INFO.version),
+        KeyValue::new("vcs.ref.head.revision", BUILD_INFO.git_sha),
+        KeyValue::new("process.runtime.name", "rustc"),
+        KeyValue::new("process.runtime.version", BUILD_INFO.rustc_version),
+        KeyValue::new(
+            "process.command",
+            std::env::args().next().expect("executable name to exist"),
+        ),
+        KeyValue::new(
+            "process.command_line",
+            std::env::args().collect::<Vec<_>>().join(" "),
+        ),
+        KeyValue::new(
+            "process.executable.name",
+            std::env::current_exe()
+                .expect("current executable details to exist")
+                .file_name()
+                .expect("executable name to exist")
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        KeyValue::new(
+            "process.executable.path",
+            std::env::current_exe()
+                .expect("current executable details to exist")
+                .display()
+                .to_string(),
+        ),
+        KeyValue::new("process.pid", std::process::id() as i64),
+        KeyValue::new("host.arch", std::env::consts::ARCH),
+        KeyValue::new(
+            "host.name",
+            gethostname().into_string().expect("hostname to exist"),
+        ),
+    ]);
+}
+
+/// What has to be flushed on exit
+pub struct Telemetry {
+    meter_provider: MeterProvider,
+}
+
+pub fn init_telemetry(config: &TelemetryConfig) -> Telemetry {
     let mut map = MetadataMap::with_capacity(1);
 
-    if env::var("OTEL_HONEYCOMB_KEY").is_ok() {
-        map.insert(
-            "x-honeycomb-team",
-            env::var("OTEL_HEADER_VALUE")
-                .expect("OTEL_HEADER_VALUE must be set if OTEL_HEADER_KEY is")
-                .parse()
-                .expect("OTEL_HEADER_VALUE to be parseable"),
-        );
+    // Checked to be a valid header value when the config was loaded
+    if let Some(team) = config
+        .honeycomb_team
+        .as_deref()
+        .and_then(|t| return t.parse().ok())
+    {
+        map.insert("x-honeycomb-team", team);
     }
     let tracer = tracing_opentelemetry::layer().with_tracer(
         opentelemetry_otlp::new_pipeline()
@@ -26,61 +71,54 @@ pub fn init_telemetry() {
             .with_exporter(
                 opentelemetry_otlp::new_exporter()
                     .tonic()
-                    .with_endpoint(env::var("OTEL_ENDPOINT").unwrap_or_else(|_| {
-                        return "http://localhost:4317".to_owned();
-                    }))
-                    .with_metadata(map),
+                    .with_endpoint(&config.otlp_endpoint)
+                    .with_metadata(map.clone()),
             )
-            .with_trace_config(sdktrace::config().with_resource(Resource::new(vec![
-                            KeyValue::new("service.name", "wantjob"),
-                            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
-                            KeyValue::new("process.runtime.name", "rustc"),
-                            KeyValue::new(
-                                "process.runtime.version",
-                                version().expect("rustc version to exist").to_string(),
-                            ),
-                            KeyValue::new(
-                                "process.command",
-                                std::env::args().next().expect("executable name to exist"),
-                            ),
-                            KeyValue::new(
-                                "process.command_line",
-                                std::env::args().collect::<Vec<_>>().join(" "),
-                            ),
-                            KeyValue::new(
-                                "process.executable.name",
-                                std::env::current_exe()
-                                    .expect("current executable details to exist")
-                                    .file_name()
-                                    .expect("executable name to exist")
-                                    .to_string_lossy()
-                                    .into_owned(),
-                            ),
-                            KeyValue::new(
-                                "process.executable.path",
-                                std::env::current_exe()
-                                    .expect("current executable details to exist")
-                                    .display()
-                                    .to_string(),
-                            ),
-                            KeyValue::new("process.pid", std::process::id() as i64),
-                            KeyValue::new("host.arch", std::env::consts::ARCH),
-                            KeyValue::new(
-                                "host.name",
-                                gethostname().into_string().expect("hostname to exist"),
-                            ),
-                        ])))
+            .with_trace_config(sdktrace::config().with_resource(resource()))
             .install_batch(runtime::Tokio)
             .expect("otel exporter to start"),
     );
 
     tracing_subscriber::registry()
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| return "wantjob=debug,axum::rejection=trace".into()),
+            tracing_subscriber::EnvFilter::try_new(&config.log_filter)
+                .expect("log_filter to be validated"),
         )
         .with(tracing_subscriber::fmt::layer())
         .with(tracer)
         .try_init()
         .expect("Tracing to start");
+
+    let meter_provider = opentelemetry_otlp::new_pipeline()
+        .metrics(runtime::Tokio)
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&config.otlp_endpoint)
+                .with_metadata(map),
+        )
+        .with_resource(resource())
+        .build()
+        .expect("otel metrics exporter to start");
+    global::set_meter_provider(meter_provider.clone());
+
+    return Telemetry { meter_provider };
+}
+
+/// Sends off the spans and metrics still waiting to be exported
+pub async fn shutdown_telemetry(telemetry: Telemetry) {
+    // Both block until their exporters, running on the runtime, have flushed
+    let flushed = tokio::task::spawn_blocking(move || {
+        global::shutdown_tracer_provider();
+        let flushed = telemetry.meter_provider.force_flush();
+        // Shutting down flushes too, but errors doing so as it stops the
+        // reader before collecting from it
+        let _ = telemetry.meter_provider.shutdown();
+        return flushed;
+    });
+    match flushed.await {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => error!("Could not flush metrics: {err}"),
+        Err(err) => error!("Could not flush telemetry: {err}"),
+    }
 }